@@ -31,3 +31,20 @@ impl fmt::Display for AddressStatus {
         }
     }
 }
+
+impl std::str::FromStr for AddressStatus {
+    type Err = String;
+
+    /// Parses the name of a status, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "current" => Ok(Self::Current),
+            "pending" => Ok(Self::Pending),
+            "retired" => Ok(Self::Retired),
+            "temporary" => Ok(Self::Temporary),
+            "virtual" => Ok(Self::Virtual),
+            "other" => Ok(Self::Other),
+            _ => Err(format!("{s} is not an address status.")),
+        }
+    }
+}
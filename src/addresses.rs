@@ -1,24 +1,35 @@
-use crate::prelude::{save, Columnar, Filtration, Tabular};
-use address::prelude::{
-    Address, AddressStatus, MatchRecord, MatchRecords, MatchStatus, SpatialAddress,
-    SpatialAddresses,
+use crate::address_components::{
+    self, match_mixed_post_type, match_mixed_pre_directional, match_mixed_subaddress_type,
 };
-use galileo::galileo_types::cartesian::{CartesianPoint2d, CartesianPoint3d, Point2d};
-use galileo::galileo_types::geo::impls::GeoPoint2d;
-use galileo::galileo_types::geo::{GeoPoint, NewGeoPoint}; //, Projection};
-use galileo::galileo_types::geometry::Geom;
-use galileo::galileo_types::geometry_type::{
-    AmbiguousSpace, GeoSpace2d, GeometryType, PointGeometryType,
+use crate::mapped::{self, Mapped, Records};
+use crate::prelude::{
+    lonlat_to_mercator, save, sort_by_cell, Attributed, Cell, Columnar, EditError, Filtration,
+    Tabular,
 };
-use galileo::galileo_types::impls::{Contour, Polygon};
+#[cfg(feature = "gui")]
+use crate::symbology::{point_paint, LayerStyle};
+use address::prelude::{Address, MatchRecord, MatchRecords, SpatialAddress, SpatialAddresses};
+#[cfg(feature = "gui")]
 use galileo::layer::feature_layer::symbol::Symbol;
+#[cfg(feature = "gui")]
 use galileo::layer::feature_layer::Feature;
-use galileo::render::point_paint::PointPaint;
+#[cfg(feature = "gui")]
 use galileo::render::render_bundle::RenderPrimitive;
-use galileo::Color;
+use galileo_types::cartesian::{CartesianPoint2d, CartesianPoint3d, Point2d};
+use galileo_types::geo::impls::GeoPoint2d;
+use galileo_types::geo::{GeoPoint, NewGeoPoint}; //, Projection};
+#[cfg(feature = "gui")]
+use galileo_types::geometry::Geom;
+use galileo_types::geometry_type::{AmbiguousSpace, GeoSpace2d, GeometryType, PointGeometryType};
+#[cfg(feature = "gui")]
+use galileo_types::impls::{Contour, Polygon};
+#[cfg(feature = "gui")]
 use num_traits::AsPrimitive;
 use polite::Polite;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::path::Path;
 use strum::{EnumIter, IntoEnumIterator};
@@ -38,6 +49,7 @@ pub enum AddressColumns {
     SubaddressId,
     Zip,
     Status,
+    Location,
 }
 
 impl AddressColumns {
@@ -62,6 +74,7 @@ impl fmt::Display for AddressColumns {
             Self::SubaddressId => write!(f, "Subaddress ID"),
             Self::Zip => write!(f, "Zip"),
             Self::Status => write!(f, "Status"),
+            Self::Location => write!(f, "Location"),
         }
     }
 }
@@ -95,6 +108,12 @@ impl TryFrom<usize> for AddressColumns {
     }
 }
 
+/// Namespace for the UUIDv5 ids assigned to each [`AddressPoint`].  Changing this value changes
+/// every address id, orphaning any checks, selections or edits keyed by id.
+pub const ADDRESS_NAMESPACE: Uuid = Uuid::from_bytes([
+    0x6a, 0x1d, 0x2f, 0x4e, 0x93, 0x0c, 0x4b, 0x8a, 0xa5, 0x6e, 0x2b, 0x51, 0xc7, 0x0f, 0x93, 0xd2,
+]);
+
 #[derive(Debug, Default, Clone, PartialEq, PartialOrd, Deserialize, Serialize)]
 pub struct AddressPoint {
     pub address: SpatialAddress,
@@ -103,6 +122,57 @@ pub struct AddressPoint {
     pub geo_point: GeoPoint2d,
 }
 
+fn invalid(field: &str, value: &str) -> EditError {
+    EditError::Invalid(format!("{value} is not a valid {field}."))
+}
+
+/// Key pairing records at the same EPSG:3857 location, rounded to the centimeter as in
+/// [`AddressPoint::id_key`].
+fn location_key(point: &Point2d) -> String {
+    format!("{:.2}|{:.2}", point.x(), point.y())
+}
+
+/// Parses an integer field, such as the address number or zip code.
+fn integer<T: std::str::FromStr>(value: &str, field: &str) -> Result<T, EditError> {
+    value.parse().map_err(|_| invalid(field, value))
+}
+
+/// Parses an optional component with `parse`, after converting it to upper case to match the
+/// spellings recognized by the `match_mixed_*` functions.  An empty value clears the field.
+fn optional<T: DeserializeOwned, U: Serialize, F: Fn(&str) -> Result<U, EditError>>(
+    value: &str,
+    parse: F,
+) -> Result<Option<T>, EditError> {
+    match value.is_empty() {
+        true => Ok(None),
+        false => Ok(Some(component(parse(&value.to_uppercase())?)?)),
+    }
+}
+
+/// Parses a coordinate in decimal degrees, which must fall within `limit` of zero.
+fn coordinate(value: &str, field: &str, limit: f64) -> Result<f64, EditError> {
+    let value = value.trim();
+    match value.parse::<f64>() {
+        Ok(degrees) if degrees.abs() <= limit => Ok(degrees),
+        _ => Err(invalid(field, value)),
+    }
+}
+
+/// Converts a component type from [`crate::address_components`] to the matching type used by
+/// the `address` crate.  The two share variant names, so the conversion passes through serde.
+fn component<T: Serialize, U: DeserializeOwned>(value: T) -> Result<U, EditError> {
+    serde_json::to_value(value)
+        .and_then(serde_json::from_value)
+        .map_err(|e| EditError::Invalid(e.to_string()))
+}
+
+/// Position of a component from the `address` crate among the variants of the matching type
+/// in [`crate::address_components`], read by `position`.  Used to sort enum cells in the order of
+/// the variants.  Components without a match sort last.
+fn ordinal<T: Serialize, U: DeserializeOwned>(value: T, position: impl Fn(U) -> u32) -> u32 {
+    component(value).map(position).unwrap_or(u32::MAX)
+}
+
 impl AddressPoint {
     pub fn geo_point(&self) -> geo::geometry::Point {
         let x = CartesianPoint2d::x(self);
@@ -111,41 +181,156 @@ impl AddressPoint {
     }
 
     pub fn column<T: fmt::Display>(&self, columns: &AddressColumns) -> String {
+        self.cell(columns).to_string()
+    }
+
+    /// The `cell` method returns the typed value of the address in column `columns`.
+    pub fn cell(&self, columns: &AddressColumns) -> Cell {
         match *columns {
-            AddressColumns::Label => self.address.label(),
-            AddressColumns::Number => format!("{}", self.address.number()),
+            AddressColumns::Label => Cell::Text(self.address.label()),
+            AddressColumns::Number => Cell::Integer(self.address.number().into()),
+            AddressColumns::Directional => self
+                .address
+                .directional()
+                .as_ref()
+                .map(|v| {
+                    let ordinal = ordinal(v, |v: address_components::StreetNamePreDirectional| {
+                        v as u32
+                    });
+                    Cell::Enum(ordinal, v.to_string())
+                })
+                .into(),
+            AddressColumns::StreetName => Cell::Text(self.address.street_name().to_string()),
+            AddressColumns::StreetType => self
+                .address
+                .street_type()
+                .as_ref()
+                .map(|v| {
+                    let ordinal = ordinal(v, |v: address_components::StreetNamePostType| v as u32);
+                    Cell::Enum(ordinal, v.abbreviate())
+                })
+                .into(),
+            AddressColumns::SubaddressType => self
+                .address
+                .subaddress_type()
+                .as_ref()
+                .map(|v| {
+                    let ordinal = ordinal(v, |v: address_components::SubaddressType| v as u32);
+                    Cell::Enum(ordinal, v.to_string())
+                })
+                .into(),
+            AddressColumns::SubaddressId => self
+                .address
+                .subaddress_id()
+                .as_ref()
+                .map(|v| Cell::Text(v.to_string()))
+                .into(),
+            AddressColumns::Zip => Cell::Integer(self.address.zip().into()),
+            AddressColumns::Status => {
+                let status = self.address.status();
+                let ordinal = ordinal(&status, |v: address_components::AddressStatus| v as u32);
+                Cell::Enum(ordinal, status.to_string())
+            }
+            // Written in full, so the text reads back to the same location.
+            AddressColumns::Location => Cell::Text(format!(
+                "{}, {}",
+                self.address.latitude, self.address.longitude
+            )),
+        }
+    }
+
+    /// The `set_cell` method parses `value` for column `columns` and writes it to the address.
+    /// Street types, directionals and subaddress types accept the spellings recognized by the
+    /// `match_mixed_*` functions, statuses must name an
+    /// [`AddressStatus`](address_components::AddressStatus), and numbers must be integers.  An
+    /// empty value clears an optional field.  The location is a latitude and longitude separated
+    /// by a comma, as shown in the table.  The label is derived from the other fields, so it
+    /// cannot be set.
+    pub fn set_cell(&mut self, columns: &AddressColumns, value: &str) -> Result<(), EditError> {
+        let value = value.trim();
+        match *columns {
+            AddressColumns::Label => return Err(EditError::ReadOnly),
+            AddressColumns::Number => *self.address.number_mut() = integer(value, "number")?,
             AddressColumns::Directional => {
-                if let Some(prefix) = self.address.directional() {
-                    format!("{}", prefix)
-                } else {
-                    "".to_string()
+                *self.address.directional_mut() = optional(value, |v| {
+                    match_mixed_pre_directional(v).ok_or_else(|| invalid("directional", v))
+                })?
+            }
+            AddressColumns::StreetName => {
+                if value.is_empty() {
+                    return Err(EditError::Invalid("Street name is required.".to_string()));
                 }
+                *self.address.street_name_mut() = value.to_uppercase();
             }
-            AddressColumns::StreetName => self.address.street_name().to_string(),
             AddressColumns::StreetType => {
-                if let Some(value) = &self.address.street_type() {
-                    value.abbreviate()
-                } else {
-                    "".to_string()
-                }
+                *self.address.street_type_mut() = optional(value, |v| {
+                    match_mixed_post_type(v).ok_or_else(|| invalid("street type", v))
+                })?
             }
             AddressColumns::SubaddressType => {
-                if let Some(subtype) = &self.address.subaddress_type() {
-                    format!("{}", subtype)
-                } else {
-                    "".to_string()
-                }
+                *self.address.subaddress_type_mut() = optional(value, |v| {
+                    match_mixed_subaddress_type(v).ok_or_else(|| invalid("subaddress type", v))
+                })?
             }
             AddressColumns::SubaddressId => {
-                if let Some(value) = &self.address.subaddress_id() {
-                    value.to_string()
-                } else {
-                    "".to_string()
+                *self.address.subaddress_id_mut() = match value.is_empty() {
+                    true => None,
+                    false => Some(value.to_string()),
                 }
             }
-            AddressColumns::Zip => format!("{}", self.address.zip()),
-            AddressColumns::Status => format!("{}", self.address.status()),
+            AddressColumns::Zip => *self.address.zip_mut() = integer(value, "zip")?,
+            AddressColumns::Status => {
+                let status = value
+                    .parse::<address_components::AddressStatus>()
+                    .map_err(|_| invalid("status", value))?;
+                *self.address.status_mut() = component(status)?;
+            }
+            AddressColumns::Location => {
+                let (latitude, longitude) = value
+                    .split_once(',')
+                    .ok_or_else(|| invalid("latitude and longitude", value))?;
+                let latitude = coordinate(latitude, "latitude", 85.0)?;
+                let longitude = coordinate(longitude, "longitude", 180.0)?;
+                self.locate(latitude, longitude);
+            }
+        }
+        Ok(())
+    }
+
+    /// The `locate` method moves the address to `latitude` and `longitude`, updating the
+    /// EPSG:3857 location in `point` to match.  The id is left unchanged, so the moved point
+    /// keeps its checks, selections and edit history.
+    pub fn locate(&mut self, latitude: f64, longitude: f64) {
+        let point = lonlat_to_mercator(longitude, latitude);
+        self.address.latitude = latitude;
+        self.address.longitude = longitude;
+        self.address.x = CartesianPoint2d::x(&point);
+        self.address.y = CartesianPoint2d::y(&point);
+        self.point = point;
+        self.geo_point = GeoPoint2d::latlon(latitude, longitude);
+    }
+
+    /// The `pending` method creates a new address at `latitude` and `longitude` with status
+    /// [`Pending`](address_components::AddressStatus::Pending), taking its components from
+    /// `values`, which hold the text of each column in [`AddressColumns`] order.  The label and
+    /// location columns are skipped, and an empty status keeps the default.  The id derives
+    /// from the components and the location, as for imported records.
+    pub fn pending(values: &[String], latitude: f64, longitude: f64) -> Result<Self, EditError> {
+        let mut record = Self::default();
+        *record.address.status_mut() = component(address_components::AddressStatus::Pending)?;
+        record.locate(latitude, longitude);
+        for (column, value) in AddressColumns::iter().zip(values) {
+            match column {
+                AddressColumns::Label | AddressColumns::Location => {}
+                AddressColumns::Status if value.trim().is_empty() => {}
+                _ => record.set_cell(&column, value)?,
+            }
         }
+        if record.address.street_name().is_empty() {
+            return Err(EditError::Invalid("Street name is required.".to_string()));
+        }
+        record.id = Self::stable_id(&Self::id_key(&record.address));
+        Ok(record)
     }
 
     pub fn columns(&self) -> Vec<String> {
@@ -155,6 +340,57 @@ impl AddressPoint {
         }
         values
     }
+
+    /// The `id_key` method builds the source key used to derive the id of an address.  The key
+    /// joins the normalized address components with the EPSG:3857 location rounded to the
+    /// centimeter, so the same record produces the same key on every import.
+    pub fn id_key(address: &SpatialAddress) -> String {
+        format!(
+            "{}|{:.2}|{:.2}",
+            Self::component_key(address),
+            CartesianPoint2d::x(address),
+            CartesianPoint2d::y(address)
+        )
+    }
+
+    /// The `component_key` method joins the normalized address components, without the location.
+    /// Used to pair records across snapshots when the location has changed.
+    pub fn component_key(address: &SpatialAddress) -> String {
+        let mut parts = Vec::new();
+        parts.push(address.number().to_string());
+        if let Some(prefix) = address.directional() {
+            parts.push(prefix.to_string());
+        } else {
+            parts.push(String::new());
+        }
+        parts.push(address.street_name().to_string());
+        if let Some(value) = &address.street_type() {
+            parts.push(value.abbreviate());
+        } else {
+            parts.push(String::new());
+        }
+        if let Some(subtype) = &address.subaddress_type() {
+            parts.push(subtype.to_string());
+        } else {
+            parts.push(String::new());
+        }
+        if let Some(value) = &address.subaddress_id() {
+            parts.push(value.to_string());
+        } else {
+            parts.push(String::new());
+        }
+        parts.push(address.zip().to_string());
+        parts
+            .iter()
+            .map(|v| v.trim().to_uppercase())
+            .collect::<Vec<String>>()
+            .join("|")
+    }
+
+    /// The `stable_id` method derives a UUIDv5 from `key` in the [`ADDRESS_NAMESPACE`].
+    pub fn stable_id(key: &str) -> Uuid {
+        Uuid::new_v5(&ADDRESS_NAMESPACE, key.as_bytes())
+    }
 }
 
 impl Columnar for AddressPoint {
@@ -166,6 +402,10 @@ impl Columnar for AddressPoint {
         self.columns()
     }
 
+    fn cells(&self) -> Vec<Cell> {
+        AddressColumns::iter().map(|v| self.cell(&v)).collect()
+    }
+
     fn id(&self) -> &Uuid {
         &self.id
     }
@@ -178,7 +418,7 @@ impl From<&SpatialAddress> for AddressPoint {
             galileo_types::geo::GeoPoint::lat(address),
             galileo_types::geo::GeoPoint::lon(address),
         );
-        let id = Uuid::new_v4();
+        let id = Self::stable_id(&Self::id_key(address));
         let address = address.clone();
         Self {
             address,
@@ -237,6 +477,7 @@ impl GeometryType for AddressPoint {
     type Space = AmbiguousSpace;
 }
 
+#[cfg(feature = "gui")]
 impl Feature for AddressPoint {
     type Geom = GeoPoint2d;
 
@@ -247,114 +488,169 @@ impl Feature for AddressPoint {
 
 #[derive(Debug, Clone, Default, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct AddressPoints {
-    pub records: Vec<AddressPoint>,
+    pub records: Records<AddressPoint>,
 }
 
 impl AddressPoints {
+    /// Sorts the records by the typed value of column `column_index`.
+    /// Wraps [`sort_by_cell`].
     pub fn sort_by_col(&mut self, column_index: usize, reverse: bool) {
-        // Parse the index to an address column.
-        if let Ok(column) = AddressColumns::try_from(column_index) {
-            // Match against the column type and sort.
-            match column {
-                AddressColumns::Label => {
-                    if reverse {
-                        self.records
-                            .sort_by(|a, b| b.address.label().cmp(&a.address.label()));
-                    } else {
-                        self.records
-                            .sort_by(|a, b| a.address.label().cmp(&b.address.label()));
-                    }
-                }
-                AddressColumns::Number => {
-                    if reverse {
-                        self.records
-                            .sort_by(|a, b| b.address.number().cmp(&a.address.number()));
-                    } else {
-                        self.records
-                            .sort_by(|a, b| a.address.number().cmp(&b.address.number()));
-                    }
-                }
-                AddressColumns::Directional => {
-                    if reverse {
-                        self.records
-                            .sort_by(|a, b| b.address.directional().cmp(a.address.directional()));
-                    } else {
-                        self.records
-                            .sort_by(|a, b| a.address.directional().cmp(b.address.directional()));
-                    }
-                }
-                AddressColumns::StreetName => {
-                    if reverse {
-                        self.records
-                            .sort_by(|a, b| b.address.street_name().cmp(a.address.street_name()));
-                    } else {
-                        self.records
-                            .sort_by(|a, b| a.address.street_name().cmp(b.address.street_name()));
-                    }
-                }
-                AddressColumns::StreetType => {
-                    if reverse {
-                        self.records
-                            .sort_by(|a, b| b.address.street_type().cmp(a.address.street_type()));
-                    } else {
-                        self.records
-                            .sort_by(|a, b| a.address.street_type().cmp(b.address.street_type()));
-                    }
-                }
-                AddressColumns::SubaddressType => {
-                    if reverse {
-                        self.records.sort_by(|a, b| {
-                            b.address.subaddress_type().cmp(a.address.subaddress_type())
-                        });
-                    } else {
-                        self.records.sort_by(|a, b| {
-                            a.address.subaddress_type().cmp(b.address.subaddress_type())
-                        });
-                    }
-                }
-                AddressColumns::SubaddressId => {
-                    if reverse {
-                        self.records.sort_by(|a, b| {
-                            b.address.subaddress_id().cmp(a.address.subaddress_id())
-                        });
-                    } else {
-                        self.records.sort_by(|a, b| {
-                            a.address.subaddress_id().cmp(b.address.subaddress_id())
-                        });
-                    }
-                }
-                AddressColumns::Zip => {
-                    if reverse {
-                        self.records
-                            .sort_by(|a, b| b.address.zip().cmp(&a.address.zip()));
-                    } else {
-                        self.records
-                            .sort_by(|a, b| a.address.zip().cmp(&b.address.zip()));
-                    }
+        sort_by_cell(&mut self.records, column_index, reverse);
+    }
+    /// The `rekey` method replaces the id of each record with the stable id derived from its
+    /// address.  Use it to migrate datasets saved before ids were content-derived.
+    /// Records sharing the same key receive an occurrence suffix, so ids remain unique and
+    /// repeat in the same order on every import.
+    pub fn rekey(&mut self) {
+        self.assign_ids(|_| true);
+    }
+
+    /// The `rekey_legacy` method rekeys only the records with an id that was not derived by
+    /// [`AddressPoint::stable_id`], such as the random ids of datasets saved before ids were
+    /// content-derived.  Ids kept across edits or by [`AddressPoints::keep_ids`] are left alone.
+    pub fn rekey_legacy(&mut self) {
+        self.assign_ids(|id| id.get_version_num() != 5);
+    }
+
+    /// Assigns the stable id derived from its address to each record with an id matching
+    /// `replace`.
+    fn assign_ids<F: Fn(&Uuid) -> bool>(&mut self, replace: F) {
+        let mut seen = HashMap::new();
+        for record in self.records.iter_mut() {
+            let key = AddressPoint::id_key(&record.address);
+            let count = seen.entry(key.clone()).or_insert(0);
+            if replace(&record.id) {
+                record.id = if *count == 0 {
+                    AddressPoint::stable_id(&key)
+                } else {
+                    AddressPoint::stable_id(&format!("{key}#{count}"))
+                };
+            }
+            *count += 1;
+        }
+    }
+
+    /// The `keep_ids` method carries the ids of `previous` over to the matching records of a
+    /// fresh import, so checks, selections, findings and edits keyed by id survive changes made
+    /// to an address at the source.  Records pair by id first, then by their address components
+    /// to follow a moved point, then by their location to follow an edited address.  Records
+    /// without a match keep their content-derived id.  Called by [`AddressPoints::import`].
+    pub fn keep_ids(&mut self, previous: &AddressPoints) {
+        let ids = self.records.iter().map(|v| v.id).collect::<HashSet<Uuid>>();
+        let mut by_components: HashMap<String, Vec<Uuid>> = HashMap::new();
+        let mut by_location: HashMap<String, Vec<Uuid>> = HashMap::new();
+        // Walk backwards so popping the lists hands out ids in their original order.
+        for record in previous.records.iter().rev() {
+            if ids.contains(&record.id) {
+                continue;
+            }
+            by_components
+                .entry(AddressPoint::component_key(&record.address))
+                .or_default()
+                .push(record.id);
+            by_location
+                .entry(location_key(&record.point))
+                .or_default()
+                .push(record.id);
+        }
+        // Takes the next id from `list` not already given to another record.
+        fn claim(list: Option<&mut Vec<Uuid>>, kept: &HashSet<Uuid>) -> Option<Uuid> {
+            let list = list?;
+            while let Some(id) = list.pop() {
+                if !kept.contains(&id) {
+                    return Some(id);
                 }
-                AddressColumns::Status => {
-                    if reverse {
-                        self.records
-                            .sort_by(|a, b| b.address.status().cmp(a.address.status()));
-                    } else {
-                        self.records
-                            .sort_by(|a, b| a.address.status().cmp(b.address.status()));
-                    }
+            }
+            None
+        }
+        let mut kept = HashSet::new();
+        let previous_ids = previous
+            .records
+            .iter()
+            .map(|v| v.id)
+            .collect::<HashSet<Uuid>>();
+        let unmatched = self
+            .records
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| !previous_ids.contains(&v.id))
+            .map(|(i, _)| i)
+            .collect::<Vec<usize>>();
+        let mut remaining = Vec::new();
+        for index in unmatched {
+            let key = AddressPoint::component_key(&self.records[index].address);
+            match claim(by_components.get_mut(&key), &kept) {
+                Some(id) => {
+                    kept.insert(id);
+                    self.records[index].id = id;
                 }
+                None => remaining.push(index),
+            }
+        }
+        for index in remaining {
+            let key = location_key(&self.records[index].point);
+            if let Some(id) = claim(by_location.get_mut(&key), &kept) {
+                kept.insert(id);
+                self.records[index].id = id;
             }
         }
     }
+
     pub fn save<P: AsRef<Path>>(&self, path: P) -> Polite<()> {
         tracing::info!("Serializing to binary.");
         save(self, path)
     }
 
+    /// Loads address points from a file at location `path`.  Keeps files in the [`mapped`]
+    /// format behind a memory map, decoding records as they are read, and otherwise falls back
+    /// to a plain `bincode` file.  Records in a `bincode` file saved before ids were
+    /// content-derived receive their stable id, as by [`AddressPoints::rekey_legacy`].  Mapped
+    /// files postdate content-derived ids, so loading one does not decode every record to check.
     pub fn load<P: AsRef<Path>>(path: P) -> Polite<Self> {
+        if mapped::is_mapped(&path)? {
+            let records = Self::open_mapped(path)?.into();
+            return Ok(Self { records });
+        }
         tracing::info!("Deserializing from binary.");
         let vec: Vec<u8> = std::fs::read(path)?;
-        let addresses: AddressPoints = bincode::deserialize(&vec[..])?;
+        let mut addresses = bincode::deserialize::<AddressPoints>(&vec[..])?;
+        addresses.rekey_legacy();
         Ok(addresses)
     }
+
+    /// The `import` method converts `addresses` read from the source and saves them to a file
+    /// at location `path`.  If a dataset can already be read from `path`, the records matching
+    /// it keep their ids, as by [`AddressPoints::keep_ids`], and the file keeps its format, so a
+    /// re-import leaves the checks, selections and edits keyed by id in place.
+    pub fn import<P: AsRef<Path>>(addresses: &SpatialAddresses, path: P) -> Polite<Self> {
+        let mut points = Self::from(addresses);
+        let mapped = match Self::load(&path) {
+            Ok(previous) => {
+                points.keep_ids(&previous);
+                mapped::is_mapped(&path)?
+            }
+            Err(e) => {
+                tracing::info!("No previous dataset to keep ids from: {}", e.to_string());
+                false
+            }
+        };
+        match mapped {
+            true => points.save_mapped(&path)?,
+            false => points.save(&path)?,
+        }
+        Ok(points)
+    }
+
+    /// Writes the address points to a file at location `path` in the [`mapped`] format.
+    pub fn save_mapped<P: AsRef<Path>>(&self, path: P) -> Polite<()> {
+        mapped::save_mapped(&self.records, path)
+    }
+
+    /// Maps a file written by [`AddressPoints::save_mapped`] into memory without decoding the
+    /// records.
+    pub fn open_mapped<P: AsRef<Path>>(path: P) -> Polite<Mapped<AddressPoint>> {
+        Mapped::open(path)
+    }
 }
 
 impl Tabular<AddressPoint> for AddressPoints {
@@ -363,7 +659,83 @@ impl Tabular<AddressPoint> for AddressPoints {
     }
 
     fn rows(&self) -> Vec<AddressPoint> {
-        self.records.clone()
+        self.records.to_vec()
+    }
+
+    fn rows_ref(&self) -> Cow<'_, [AddressPoint]> {
+        Cow::Borrowed(&self.records[..])
+    }
+
+    fn row(&self, index: usize) -> Option<Cow<'_, AddressPoint>> {
+        self.records.row(index).map(Cow::Borrowed)
+    }
+
+    fn set_value(&mut self, id: &Uuid, column: usize, value: &str) -> Result<(), EditError> {
+        let column = AddressColumns::try_from(column).map_err(|_| EditError::ReadOnly)?;
+        match self.records.iter_mut().find(|v| v.id == *id) {
+            Some(record) => record.set_cell(&column, value),
+            None => Err(EditError::MissingRow),
+        }
+    }
+
+    fn is_editable(column: usize) -> bool {
+        !matches!(
+            AddressColumns::try_from(column),
+            Ok(AddressColumns::Label) | Err(_)
+        )
+    }
+
+    fn row_text(row: &AddressPoint) -> Result<String, EditError> {
+        serde_json::to_string(row).map_err(|e| EditError::Invalid(e.to_string()))
+    }
+
+    /// Rejects a row with the same id as an existing record, so ids stay unique.
+    fn insert_row(&mut self, index: usize, row: &str) -> Result<(), EditError> {
+        let record = serde_json::from_str::<AddressPoint>(row)
+            .map_err(|e| EditError::Invalid(e.to_string()))?;
+        if self.records.iter().any(|v| v.id == record.id) {
+            return Err(EditError::Invalid(format!(
+                "An address with id {} already exists.",
+                record.id
+            )));
+        }
+        let index = index.min(self.records.len());
+        self.records.insert(index, record);
+        Ok(())
+    }
+
+    fn remove_row(&mut self, id: &Uuid) -> Result<(usize, String), EditError> {
+        let index = self
+            .records
+            .iter()
+            .position(|v| v.id == *id)
+            .ok_or(EditError::MissingRow)?;
+        let text = Self::row_text(&self.records[index])?;
+        self.records.remove(index);
+        Ok((index, text))
+    }
+
+    /// Replaces the record in place, rejecting a row with a different id.
+    fn replace_row(&mut self, id: &Uuid, row: &str) -> Result<(), EditError> {
+        let record = serde_json::from_str::<AddressPoint>(row)
+            .map_err(|e| EditError::Invalid(e.to_string()))?;
+        if record.id != *id {
+            return Err(EditError::Invalid(format!(
+                "The row for {} holds id {}.",
+                id, record.id
+            )));
+        }
+        match self.records.iter_mut().find(|v| v.id == *id) {
+            Some(current) => {
+                *current = record;
+                Ok(())
+            }
+            None => Err(EditError::MissingRow),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.records.len()
     }
 
     fn sort_by_col(&mut self, column_index: usize, reverse: bool) {
@@ -383,12 +755,37 @@ impl From<&SpatialAddresses> for AddressPoints {
             .iter()
             .map(AddressPoint::from)
             .collect::<Vec<AddressPoint>>();
-        Self { records }
+        let mut points = Self {
+            records: records.into(),
+        };
+        // Duplicate records at the same location would otherwise share an id.
+        points.rekey();
+        points
     }
 }
 
-pub struct AddressSymbol {}
+impl Attributed for AddressPoint {
+    fn columns() -> Vec<String> {
+        AddressColumns::names()
+    }
 
+    fn attribute(&self, column: &str) -> Cell {
+        AddressColumns::iter()
+            .find(|v| v.to_string().eq_ignore_ascii_case(column))
+            .map(|v| self.cell(&v))
+            .unwrap_or_default()
+    }
+}
+
+/// The `AddressSymbol` struct draws address points as circles styled by the symbology rules,
+/// which color them by status unless configured otherwise.
+#[cfg(feature = "gui")]
+pub struct AddressSymbol {
+    pub style: LayerStyle,
+    pub opacity: f32,
+}
+
+#[cfg(feature = "gui")]
 impl Symbol<AddressPoint> for AddressSymbol {
     fn render<'a, N, P>(
         &self,
@@ -400,22 +797,14 @@ impl Symbol<AddressPoint> for AddressSymbol {
         N: AsPrimitive<f32>,
         P: CartesianPoint3d<Num = N> + Clone,
     {
-        let size = 7.0 as f32;
         let mut primitives = Vec::new();
         let Geom::Point(point) = geometry else {
             return primitives;
         };
-        let color = match &feature.address.status() {
-            AddressStatus::Current => Color::BLUE,
-            AddressStatus::Other => Color::from_hex("#dbc200"),
-            AddressStatus::Pending => Color::from_hex("#db00d4"),
-            AddressStatus::Temporary => Color::from_hex("#db6e00"),
-            AddressStatus::Retired => Color::from_hex("#ad0000"),
-            AddressStatus::Virtual => Color::from_hex("#32a852"),
-        };
+        let style = self.style.style_for(feature);
         primitives.push(RenderPrimitive::new_point_ref(
             point,
-            PointPaint::circle(color, size),
+            point_paint(style, self.opacity),
         ));
         primitives
     }
@@ -444,6 +833,7 @@ impl GeometryType for MatchPoint {
     type Space = GeoSpace2d;
 }
 
+#[cfg(feature = "gui")]
 impl Feature for MatchPoint {
     type Geom = GeoPoint2d;
 
@@ -478,8 +868,31 @@ impl From<&MatchRecords> for MatchPoints {
     }
 }
 
-pub struct MatchSymbol {}
+impl Attributed for MatchPoint {
+    fn columns() -> Vec<String> {
+        vec!["Status".to_string()]
+    }
+
+    /// Match points have the single column `Status`, the match status of the record.
+    fn attribute(&self, column: &str) -> Cell {
+        match column.eq_ignore_ascii_case("status") {
+            // The `address` crate does not expose the order of match statuses, so they sort by
+            // name.
+            true => Cell::Enum(0, format!("{:?}", self.record.match_status)),
+            false => Cell::Empty,
+        }
+    }
+}
+
+/// The `MatchSymbol` struct draws match points as circles styled by the symbology rules, which
+/// color them by match status unless configured otherwise.
+#[cfg(feature = "gui")]
+pub struct MatchSymbol {
+    pub style: LayerStyle,
+    pub opacity: f32,
+}
 
+#[cfg(feature = "gui")]
 impl Symbol<MatchPoint> for MatchSymbol {
     fn render<'a, N, P>(
         &self,
@@ -491,19 +904,14 @@ impl Symbol<MatchPoint> for MatchSymbol {
         N: AsPrimitive<f32>,
         P: CartesianPoint3d<Num = N> + Clone,
     {
-        let size = 7.0 as f32;
         let mut primitives = Vec::new();
         let Geom::Point(point) = geometry else {
             return primitives;
         };
-        let color = match &feature.record.match_status {
-            MatchStatus::Matching => Color::BLUE,
-            MatchStatus::Divergent => Color::from_hex("#dbc200"),
-            MatchStatus::Missing => Color::from_hex("#ad0000"),
-        };
+        let style = self.style.style_for(feature);
         primitives.push(RenderPrimitive::new_point_ref(
             point,
-            PointPaint::circle(color, size),
+            point_paint(style, self.opacity),
         ));
         primitives
     }
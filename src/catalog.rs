@@ -0,0 +1,54 @@
+//! The `catalog` module reads the dataset catalog, which names the files holding each dataset
+//! and the basemap.
+use crate::tiles::TileSource;
+use polite::Polite;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::{fs, io};
+
+/// Default location of the dataset catalog.
+pub const CATALOG_PATH: &str = "data/catalog.toml";
+
+/// The `Catalog` struct lists the locations of the datasets used by the application.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Catalog {
+    /// Path to the address snapshot.
+    pub addresses: String,
+    /// Path to the parcel snapshot.
+    pub parcels: String,
+    /// Source of basemap tiles, if any.  Without one, maps draw on a blank background.
+    pub basemap: Option<TileSource>,
+}
+
+impl Default for Catalog {
+    fn default() -> Self {
+        Self {
+            addresses: "data/addresses.data".to_string(),
+            parcels: "data/parcels.data".to_string(),
+            basemap: None,
+        }
+    }
+}
+
+impl Catalog {
+    /// Reads the catalog from the TOML file at `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Polite<Self> {
+        let text = fs::read_to_string(path)?;
+        let catalog = toml::from_str(&text)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        Ok(catalog)
+    }
+
+    /// Reads the catalog at [`CATALOG_PATH`], or returns the default catalog if it cannot be
+    /// read.
+    pub fn open() -> Self {
+        match Self::load(CATALOG_PATH) {
+            Ok(catalog) => catalog,
+            Err(e) => {
+                tracing::info!("Using default catalog: {}", e.to_string());
+                Self::default()
+            }
+        }
+    }
+}
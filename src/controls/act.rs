@@ -261,6 +261,8 @@ pub enum EguiAct {
     PreviousWindow,
     NextRow,
     PreviousRow,
+    ExtendNextRow,
+    ExtendPreviousRow,
     FocusedLeaf,
     #[default]
     Be,
@@ -283,8 +285,10 @@ impl EguiAct {
             Self::PreviousWindow => 7,
             Self::NextRow => 8,
             Self::PreviousRow => 9,
-            Self::FocusedLeaf => 10,
-            Self::Be => 11,
+            Self::ExtendNextRow => 10,
+            Self::ExtendPreviousRow => 11,
+            Self::FocusedLeaf => 12,
+            Self::Be => 13,
         }
     }
 }
@@ -316,6 +320,8 @@ impl fmt::Display for EguiAct {
             Self::PreviousWindow => write!(f, "Previous Window"),
             Self::NextRow => write!(f, "Next Row"),
             Self::PreviousRow => write!(f, "Previous Row"),
+            Self::ExtendNextRow => write!(f, "Extend Next Row"),
+            Self::ExtendPreviousRow => write!(f, "Extend Previous Row"),
             Self::FocusedLeaf => write!(f, "Focused Leaf"),
             Self::Be => write!(f, "Be"),
         }
@@ -336,6 +342,8 @@ impl std::str::FromStr for EguiAct {
             "previous_window" => Ok(Self::PreviousWindow),
             "next_row" => Ok(Self::NextRow),
             "previous_row" => Ok(Self::PreviousRow),
+            "extend_next_row" => Ok(Self::ExtendNextRow),
+            "extend_previous_row" => Ok(Self::ExtendPreviousRow),
             "focused_leaf" => Ok(Self::FocusedLeaf),
             "be" => Ok(Self::Be),
             _ => Err(polite::FauxPas::Unknown),
@@ -1,5 +1,6 @@
 use crate::prelude::{
-    Act, AppAct, Columnar, EguiAct, Filtration, NamedAct, TableConfig, TableView, Tabular,
+    sort_by_cell, Act, AppAct, Columnar, EguiAct, Filtration, NamedAct, TableConfig, TableView,
+    Tabular,
 };
 // see https://www.howtocodeit.com/articles/ultimate-guide-rust-newtypes
 use derive_more::{Deref, DerefMut};
@@ -10,6 +11,7 @@ use nom::sequence::delimited;
 use nom::IResult;
 use polite::{FauxPas, Polite};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fmt;
 use strum::IntoEnumIterator;
@@ -761,26 +763,18 @@ impl Tabular<CommandRow> for CommandTable {
         self.0.clone()
     }
 
+    fn rows_ref(&self) -> Cow<'_, [CommandRow]> {
+        Cow::Borrowed(&self.0)
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Sorts the rows by the typed value of column `column_index`.
+    /// Wraps [`sort_by_cell`].
     fn sort_by_col(&mut self, column_index: usize, reverse: bool) {
-        match column_index {
-            0 => {
-                if reverse {
-                    self.0.sort_by(|a, b| b.command.cmp(&a.command));
-                } else {
-                    self.0.sort_by(|a, b| a.command.cmp(&b.command));
-                }
-            }
-            1 => {
-                if reverse {
-                    self.0.sort_by(|a, b| b.act.cmp(&a.act));
-                } else {
-                    self.0.sort_by(|a, b| a.act.cmp(&b.act));
-                }
-            }
-            _ => {
-                tracing::info!("Column index not recognized.");
-            }
-        }
+        sort_by_cell(&mut self.0, column_index, reverse);
     }
 }
 
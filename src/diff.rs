@@ -0,0 +1,799 @@
+//! The `diff` module compares two snapshots of a dataset and classifies each difference as an
+//! added, removed, moved or changed record.
+use crate::addresses::AddressColumns;
+use crate::prelude::{
+    mercator_distance, mercator_to_lonlat, sort_by_cell, to_csv, AddressPoint, AddressPoints, Cell,
+    Columnar, Filtration, Parcel, Parcels, Tabular,
+};
+#[cfg(feature = "gui")]
+use crate::prelude::{TableConfig, TableLayouts, TableView};
+use derive_more::{Deref, DerefMut};
+use galileo_types::cartesian::{CartesianPoint2d, Point2d};
+use polite::Polite;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+use strum::{EnumIter, IntoEnumIterator};
+use uuid::Uuid;
+
+/// Records closer than this distance in meters between snapshots are not considered moved.
+pub const MOVE_TOLERANCE: f64 = 0.05;
+
+/// The `ChangeKind` enum classifies the difference between a record in two snapshots.
+#[derive(
+    Debug,
+    Default,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    EnumIter,
+    Serialize,
+    Deserialize,
+)]
+pub enum ChangeKind {
+    /// Present in the later snapshot only.
+    Added,
+    /// Present in the earlier snapshot only.
+    Removed,
+    /// Location differs by more than [`MOVE_TOLERANCE`].
+    Moved,
+    /// Location is the same but one or more attributes differ.
+    #[default]
+    Changed,
+}
+
+impl fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Added => write!(f, "Added"),
+            Self::Removed => write!(f, "Removed"),
+            Self::Moved => write!(f, "Moved"),
+            Self::Changed => write!(f, "Changed"),
+        }
+    }
+}
+
+/// The `FieldChange` struct holds the before and after values of a single attribute.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: String,
+    pub after: String,
+}
+
+impl FieldChange {
+    pub fn new(field: &str, before: &str, after: &str) -> Self {
+        Self {
+            field: field.to_string(),
+            before: before.to_string(),
+            after: after.to_string(),
+        }
+    }
+
+    /// Compares the values of each named field, returning a `FieldChange` for each difference.
+    pub fn compare(names: &[String], before: &[String], after: &[String]) -> Vec<Self> {
+        names
+            .iter()
+            .zip(before.iter().zip(after.iter()))
+            .filter(|(_, (b, a))| b != a)
+            .map(|(n, (b, a))| Self::new(n, b, a))
+            .collect::<Vec<Self>>()
+    }
+}
+
+/// The `Change` struct describes how a single record differs between two snapshots.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Change {
+    /// Id of the record in the later snapshot, or the earlier snapshot if removed.
+    pub id: Uuid,
+    /// Key used to pair the record across snapshots, the address id or parcel MapNum.
+    pub key: String,
+    /// Human readable label for the record.
+    pub label: String,
+    pub kind: ChangeKind,
+    /// Ground distance in meters between the two locations, if the record has moved.
+    pub distance: Option<f64>,
+    /// Attribute differences between the two snapshots.
+    pub fields: Vec<FieldChange>,
+    /// Location of the record in EPSG:3857, taken from the later snapshot unless removed.
+    pub point: Point2d,
+    /// Location of the record in the earlier snapshot, if the record has moved.
+    pub previous: Option<Point2d>,
+}
+
+impl Change {
+    /// Creates a `Change` for a record present in one snapshot only.
+    pub fn single(id: Uuid, key: &str, label: &str, kind: ChangeKind, point: Point2d) -> Self {
+        Self {
+            id,
+            key: key.to_string(),
+            label: label.to_string(),
+            kind,
+            point,
+            ..Default::default()
+        }
+    }
+
+    /// Creates a `Change` for a record present in both snapshots.  Returns `None` if the record
+    /// has neither moved nor changed.
+    pub fn paired(
+        id: Uuid,
+        key: &str,
+        label: &str,
+        before: Point2d,
+        after: Point2d,
+        fields: Vec<FieldChange>,
+    ) -> Option<Self> {
+        let distance = mercator_distance(&before, &after);
+        let kind = if distance > MOVE_TOLERANCE {
+            ChangeKind::Moved
+        } else if !fields.is_empty() {
+            ChangeKind::Changed
+        } else {
+            return None;
+        };
+        let (distance, previous) = match kind {
+            ChangeKind::Moved => (Some(distance), Some(before)),
+            _ => (None, None),
+        };
+        Some(Self {
+            id,
+            key: key.to_string(),
+            label: label.to_string(),
+            kind,
+            distance,
+            fields,
+            point: after,
+            previous,
+        })
+    }
+
+    /// Creates a table row for each field in the change.  Moves contribute a "Location" row.
+    pub fn rows(&self) -> Vec<ChangeRow> {
+        let mut rows = Vec::new();
+        if let Some(previous) = &self.previous {
+            let before = format!("{:.2}, {:.2}", previous.x(), previous.y());
+            let after = format!("{:.2}, {:.2}", self.point.x(), self.point.y());
+            rows.push(ChangeRow::new(
+                self,
+                &FieldChange::new("Location", &before, &after),
+            ));
+        }
+        for field in &self.fields {
+            rows.push(ChangeRow::new(self, field));
+        }
+        if rows.is_empty() {
+            rows.push(ChangeRow::new(self, &FieldChange::default()));
+        }
+        rows
+    }
+}
+
+/// The `ChangeLog` struct holds the differences between two snapshots of a dataset.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, Deref, DerefMut)]
+pub struct ChangeLog {
+    pub records: Vec<Change>,
+}
+
+impl ChangeLog {
+    /// Compares two snapshots of [`AddressPoints`].  Records are paired by id first.  Any records
+    /// left over are paired by their address components, so that a fresh export with new ids
+    /// still reports moves and edits rather than a removal and an addition.
+    pub fn addresses(before: &AddressPoints, after: &AddressPoints) -> Self {
+        let names = AddressColumns::names();
+        let after_ids = after
+            .records
+            .iter()
+            .map(|v| (v.id, v))
+            .collect::<HashMap<Uuid, &AddressPoint>>();
+        let before_ids = before
+            .records
+            .iter()
+            .map(|v| v.id)
+            .collect::<HashSet<Uuid>>();
+
+        let mut records = Vec::new();
+        let mut unmatched_before = Vec::new();
+        for old in &before.records {
+            if let Some(new) = after_ids.get(&old.id) {
+                records.extend(Self::address_pair(&names, old, new));
+            } else {
+                unmatched_before.push(old);
+            }
+        }
+        let unmatched_after = after
+            .records
+            .iter()
+            .filter(|v| !before_ids.contains(&v.id))
+            .collect::<Vec<&AddressPoint>>();
+
+        // Pair the remaining records when the address components are unique on both sides.
+        let mut old_keys: HashMap<String, Vec<&AddressPoint>> = HashMap::new();
+        for old in &unmatched_before {
+            old_keys
+                .entry(AddressPoint::component_key(&old.address))
+                .or_default()
+                .push(old);
+        }
+        let mut new_keys: HashMap<String, Vec<&AddressPoint>> = HashMap::new();
+        for new in &unmatched_after {
+            new_keys
+                .entry(AddressPoint::component_key(&new.address))
+                .or_default()
+                .push(new);
+        }
+        // Walk the records in snapshot order, so the changes come out in the same order each run.
+        let mut paired = HashSet::new();
+        for old in &unmatched_before {
+            let key = AddressPoint::component_key(&old.address);
+            if let (Some(olds), Some(news)) = (old_keys.get(&key), new_keys.get(&key)) {
+                if olds.len() == 1 && news.len() == 1 {
+                    records.extend(Self::address_pair(&names, olds[0], news[0]));
+                    paired.insert(olds[0].id);
+                    paired.insert(news[0].id);
+                }
+            }
+        }
+
+        for old in unmatched_before {
+            if !paired.contains(&old.id) {
+                records.push(Change::single(
+                    old.id,
+                    &old.id.to_string(),
+                    &old.address.label(),
+                    ChangeKind::Removed,
+                    old.point,
+                ));
+            }
+        }
+        for new in unmatched_after {
+            if !paired.contains(&new.id) {
+                records.push(Change::single(
+                    new.id,
+                    &new.id.to_string(),
+                    &new.address.label(),
+                    ChangeKind::Added,
+                    new.point,
+                ));
+            }
+        }
+        tracing::info!("Address changes found: {}", records.len());
+        Self { records }
+    }
+
+    /// Moves are measured from `point`, so the location column is left out of the fields.
+    fn address_pair(names: &[String], old: &AddressPoint, new: &AddressPoint) -> Option<Change> {
+        let location = AddressColumns::Location.to_string();
+        let fields = FieldChange::compare(names, &old.columns(), &new.columns())
+            .into_iter()
+            .filter(|v| v.field != location)
+            .collect();
+        Change::paired(
+            new.id,
+            &new.id.to_string(),
+            &new.address.label(),
+            old.point,
+            new.point,
+            fields,
+        )
+    }
+
+    /// Compares two snapshots of [`Parcels`], pairing records by MapNum.  A parcel has moved if
+    /// the center of its bounds has shifted, and its geometry has changed if the bounds differ.
+    pub fn parcels(before: &Parcels, after: &Parcels) -> Self {
+        let old_map = Self::parcel_map(before);
+        let new_map = Self::parcel_map(after);
+        let mut records = Vec::new();
+        // Walk the records in snapshot order, so the changes come out in the same order each
+        // run.  A duplicate MapNum is compared once, using the record kept in the map.
+        let mut seen = HashSet::new();
+        for parcel in &before.records {
+            let key = &parcel.owner.id;
+            if !seen.insert(key) {
+                continue;
+            }
+            let old = old_map[key];
+            let id = Self::parcel_id(key);
+            match new_map.get(key) {
+                Some(new) => {
+                    let mut fields = Vec::new();
+                    let old_owner = old.owner.name.clone().unwrap_or_default();
+                    let new_owner = new.owner.name.clone().unwrap_or_default();
+                    if old_owner != new_owner {
+                        fields.push(FieldChange::new("Owner", &old_owner, &new_owner));
+                    }
+                    let old_bounds = Self::bounds_text(old);
+                    let new_bounds = Self::bounds_text(new);
+                    if old_bounds != new_bounds {
+                        fields.push(FieldChange::new("Bounds", &old_bounds, &new_bounds));
+                    }
+                    records.extend(Change::paired(
+                        id,
+                        key,
+                        key,
+                        Self::parcel_center(old),
+                        Self::parcel_center(new),
+                        fields,
+                    ));
+                }
+                None => records.push(Change::single(
+                    id,
+                    key,
+                    key,
+                    ChangeKind::Removed,
+                    Self::parcel_center(old),
+                )),
+            }
+        }
+        for parcel in &after.records {
+            let key = &parcel.owner.id;
+            if !old_map.contains_key(key) && seen.insert(key) {
+                let new = new_map[key];
+                records.push(Change::single(
+                    Self::parcel_id(key),
+                    key,
+                    key,
+                    ChangeKind::Added,
+                    Self::parcel_center(new),
+                ));
+            }
+        }
+        tracing::info!("Parcel changes found: {}", records.len());
+        Self { records }
+    }
+
+    fn parcel_map(parcels: &Parcels) -> HashMap<String, &Parcel> {
+        let mut map = HashMap::new();
+        for parcel in &parcels.records {
+            if map.insert(parcel.owner.id.clone(), parcel).is_some() {
+                tracing::info!("Duplicate MapNum: {}", parcel.owner.id);
+            }
+        }
+        map
+    }
+
+    fn parcel_id(key: &str) -> Uuid {
+        Uuid::new_v5(&Uuid::NAMESPACE_OID, key.as_bytes())
+    }
+
+    fn parcel_center(parcel: &Parcel) -> Point2d {
+        let x = (parcel.bounds.x_min() + parcel.bounds.x_max()) / 2.0;
+        let y = (parcel.bounds.y_min() + parcel.bounds.y_max()) / 2.0;
+        Point2d::new(x, y)
+    }
+
+    fn bounds_text(parcel: &Parcel) -> String {
+        format!(
+            "{:.2}, {:.2}, {:.2}, {:.2}",
+            parcel.bounds.x_min(),
+            parcel.bounds.y_min(),
+            parcel.bounds.x_max(),
+            parcel.bounds.y_max()
+        )
+    }
+
+    /// Returns the number of changes of type `kind`.
+    pub fn count(&self, kind: &ChangeKind) -> usize {
+        self.records.iter().filter(|v| v.kind == *kind).count()
+    }
+
+    /// Flattens the change log into one row per changed field.
+    pub fn rows(&self) -> ChangeRows {
+        let rows = self
+            .records
+            .iter()
+            .flat_map(|v| v.rows())
+            .collect::<Vec<ChangeRow>>();
+        ChangeRows(rows)
+    }
+
+    /// Writes the change log to a CSV file at `path`, with one line per changed field.
+    pub fn to_csv<P: AsRef<Path>>(&self, path: P) -> Polite<()> {
+        let mut rows = self.rows().0;
+        to_csv(&mut rows, path)?;
+        Ok(())
+    }
+
+    /// Writes the change log to a GeoJSON file at `path`, with one point feature per changed
+    /// record.  Coordinates are longitude and latitude, per the GeoJSON specification.
+    pub fn to_geojson<P: AsRef<Path>>(&self, path: P) -> Polite<()> {
+        let features = self
+            .records
+            .iter()
+            .map(|change| {
+                let (lon, lat) = mercator_to_lonlat(&change.point);
+                let fields = change
+                    .fields
+                    .iter()
+                    .map(|v| format!("{}: {} -> {}", v.field, v.before, v.after))
+                    .collect::<Vec<String>>()
+                    .join("; ");
+                let mut properties = geojson::JsonObject::new();
+                properties.insert("kind".to_string(), change.kind.to_string().into());
+                properties.insert("key".to_string(), change.key.clone().into());
+                properties.insert("label".to_string(), change.label.clone().into());
+                properties.insert("distance".to_string(), change.distance.into());
+                properties.insert("changes".to_string(), fields.into());
+                geojson::Feature {
+                    bbox: None,
+                    geometry: Some(geojson::Geometry::new(geojson::Value::Point(vec![
+                        lon, lat,
+                    ]))),
+                    id: Some(geojson::feature::Id::String(change.id.to_string())),
+                    properties: Some(properties),
+                    foreign_members: None,
+                }
+            })
+            .collect::<Vec<geojson::Feature>>();
+        let collection = geojson::FeatureCollection {
+            bbox: None,
+            features,
+            foreign_members: None,
+        };
+        std::fs::write(path, collection.to_string())?;
+        Ok(())
+    }
+}
+
+/// The `ChangeColumns` enum names the columns of a [`ChangeRow`].
+#[derive(
+    Debug, Default, Clone, PartialEq, PartialOrd, Eq, Ord, Hash, EnumIter, Serialize, Deserialize,
+)]
+pub enum ChangeColumns {
+    #[default]
+    Kind,
+    Label,
+    Distance,
+    Field,
+    Before,
+    After,
+}
+
+impl ChangeColumns {
+    pub fn names() -> Vec<String> {
+        Self::iter().map(|v| v.to_string()).collect::<Vec<String>>()
+    }
+}
+
+impl fmt::Display for ChangeColumns {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Kind => write!(f, "Change"),
+            Self::Label => write!(f, "Record"),
+            Self::Distance => write!(f, "Distance (m)"),
+            Self::Field => write!(f, "Field"),
+            Self::Before => write!(f, "Before"),
+            Self::After => write!(f, "After"),
+        }
+    }
+}
+
+/// The `ChangeRow` struct is a single line of the change log, for display in a [`TableView`] and
+/// export to CSV.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChangeRow {
+    /// Row id derived from the record id and the field name.
+    pub id: Uuid,
+    /// Id of the changed record.
+    pub record: Uuid,
+    pub kind: ChangeKind,
+    pub key: String,
+    pub label: String,
+    pub distance: Option<f64>,
+    pub field: String,
+    pub before: String,
+    pub after: String,
+}
+
+impl ChangeRow {
+    pub fn new(change: &Change, field: &FieldChange) -> Self {
+        Self {
+            id: Uuid::new_v5(&change.id, field.field.as_bytes()),
+            record: change.id,
+            kind: change.kind,
+            key: change.key.clone(),
+            label: change.label.clone(),
+            distance: change.distance,
+            field: field.field.clone(),
+            before: field.before.clone(),
+            after: field.after.clone(),
+        }
+    }
+}
+
+impl Columnar for ChangeRow {
+    fn names() -> Vec<String> {
+        ChangeColumns::names()
+    }
+
+    fn values(&self) -> Vec<String> {
+        let distance = match self.distance {
+            Some(value) => format!("{:.2}", value),
+            None => String::new(),
+        };
+        vec![
+            self.kind.to_string(),
+            self.label.clone(),
+            distance,
+            self.field.clone(),
+            self.before.clone(),
+            self.after.clone(),
+        ]
+    }
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            Cell::Enum(self.kind as u32, self.kind.to_string()),
+            Cell::Text(self.label.clone()),
+            self.distance.map(|v| (v * 100.0).round() / 100.0).into(),
+            Cell::Text(self.field.clone()),
+            Cell::Text(self.before.clone()),
+            Cell::Text(self.after.clone()),
+        ]
+    }
+
+    fn id(&self) -> &Uuid {
+        &self.id
+    }
+}
+
+/// The `ChangeRows` struct is a wrapper around a vector of type [`ChangeRow`].  Implements
+/// [`Tabular`] for display in a [`TableView`], and [`Filtration`] by [`ChangeKind`].
+#[derive(Debug, Default, Clone, PartialEq, Deref, DerefMut, Serialize, Deserialize)]
+pub struct ChangeRows(Vec<ChangeRow>);
+
+impl Tabular<ChangeRow> for ChangeRows {
+    fn headers() -> Vec<String> {
+        ChangeColumns::names()
+    }
+
+    fn rows(&self) -> Vec<ChangeRow> {
+        self.0.clone()
+    }
+
+    fn rows_ref(&self) -> Cow<'_, [ChangeRow]> {
+        Cow::Borrowed(&self.0)
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn sort_by_col(&mut self, column_index: usize, reverse: bool) {
+        sort_by_cell(&mut self.0, column_index, reverse);
+    }
+}
+
+impl Filtration<ChangeRows, ChangeKind> for ChangeRows {
+    fn filter(self, filter: &ChangeKind) -> Self {
+        let rows = self
+            .0
+            .into_iter()
+            .filter(|v| v.kind == *filter)
+            .collect::<Vec<ChangeRow>>();
+        Self(rows)
+    }
+}
+
+/// The `Snapshot` enum selects the type of dataset compared in a [`DiffView`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, EnumIter, Serialize, Deserialize)]
+pub enum Snapshot {
+    #[default]
+    Addresses,
+    Parcels,
+}
+
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Addresses => write!(f, "Addresses"),
+            Self::Parcels => write!(f, "Parcels"),
+        }
+    }
+}
+
+/// The `DiffView` struct is the diff viewer tab.  The user picks two binary snapshots, and the
+/// resulting [`ChangeLog`] is shown in a [`TableView`] and can be exported to CSV or GeoJSON.
+#[cfg(feature = "gui")]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DiffView {
+    /// Type of dataset to compare.
+    pub snapshot: Snapshot,
+    /// Path to the earlier snapshot.
+    pub before: String,
+    /// Path to the later snapshot.
+    pub after: String,
+    /// Path to the export file, without extension.
+    pub export: String,
+    /// Result of the last comparison.
+    pub log: Option<ChangeLog>,
+    /// Table view of the rows in `log`.
+    pub table: Option<TableView<ChangeRows, ChangeRow, ChangeKind>>,
+    /// Column settings given to the table of each comparison, saved with the session.
+    pub layouts: TableLayouts,
+    /// Limits the table to one kind of change when set.
+    pub show: Option<ChangeKind>,
+    /// Status line reporting the result of the last operation.
+    pub status: String,
+    /// Unique identifier assigned to the hosting tab.
+    pub identifier: Option<String>,
+}
+
+#[cfg(feature = "gui")]
+impl DiffView {
+    pub fn new() -> Self {
+        Self {
+            before: "data/addresses_before.data".to_string(),
+            after: "data/addresses.data".to_string(),
+            export: "data/changes".to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// The `with_layouts` method sets the column settings given to the table of each comparison.
+    pub fn with_layouts(mut self, layouts: TableLayouts) -> Self {
+        self.layouts = layouts;
+        self
+    }
+
+    /// Saves the column settings of the table in `layouts`, if there is a table.  Keeps them in
+    /// the view as well, for the table of the next comparison.
+    pub fn store_layout(&mut self, layouts: &mut TableLayouts) {
+        if let Some(table) = &self.table {
+            table.store_layout(&mut self.layouts);
+            table.store_layout(layouts);
+        }
+    }
+
+    /// Applies the column settings in `layouts` to the table, and keeps them for the table of
+    /// later comparisons.
+    pub fn restore_layout(&mut self, layouts: &TableLayouts) {
+        self.layouts = layouts.clone();
+        if let Some(table) = &mut self.table {
+            table.restore_layout(layouts);
+        }
+    }
+
+    /// Loads the snapshots at `before` and `after` and compares them.  The new table keeps the
+    /// column settings of the last one.
+    pub fn compare(&mut self) -> Polite<()> {
+        let log = match self.snapshot {
+            Snapshot::Addresses => {
+                let before = AddressPoints::load(&self.before)?;
+                let after = AddressPoints::load(&self.after)?;
+                ChangeLog::addresses(&before, &after)
+            }
+            Snapshot::Parcels => {
+                let before = Parcels::load(&self.before)?;
+                let after = Parcels::load(&self.after)?;
+                ChangeLog::parcels(&before, &after)
+            }
+        };
+        let config = TableConfig::new()
+            .resizable()
+            .with_search()
+            .striped()
+            .with_slider()
+            .with_chooser();
+        if let Some(table) = &self.table {
+            table.store_layout(&mut self.layouts);
+        }
+        let mut table = TableView::with_config(log.rows(), config);
+        table.with_name("changes");
+        table.restore_layout(&self.layouts);
+        self.table = Some(table);
+        self.log = Some(log);
+        self.show = None;
+        Ok(())
+    }
+
+    /// Restricts the table view to changes of type `kind`, or all changes if `None`.
+    pub fn set_show(&mut self, kind: Option<ChangeKind>) {
+        self.show = kind;
+        if let Some(table) = &mut self.table {
+            let rows = match kind {
+                Some(k) => table.data.clone().filter(&k),
+                None => table.data.clone(),
+            };
+            *table.view_mut() = rows;
+        }
+    }
+
+    /// The `view` method displays the diff viewer within an [`egui::Ui`].
+    pub fn view(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            for snapshot in Snapshot::iter() {
+                ui.selectable_value(&mut self.snapshot, snapshot, snapshot.to_string());
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Before");
+            ui.text_edit_singleline(&mut self.before);
+        });
+        ui.horizontal(|ui| {
+            ui.label("After");
+            ui.text_edit_singleline(&mut self.after);
+        });
+        if ui.button("Compare").clicked() {
+            self.status = match self.compare() {
+                Ok(()) => "Comparison complete.".to_string(),
+                Err(e) => format!("Could not compare snapshots: {}", e),
+            };
+        }
+
+        let mut show = self.show;
+        if let Some(log) = &self.log {
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut show, None, format!("All: {}", log.len()));
+                for kind in ChangeKind::iter() {
+                    ui.selectable_value(
+                        &mut show,
+                        Some(kind),
+                        format!("{}: {}", kind, log.count(&kind)),
+                    );
+                }
+            });
+        }
+        if show != self.show {
+            self.set_show(show);
+        }
+
+        if let Some(table) = &mut self.table {
+            if table.view().is_empty() {
+                ui.label("No changes to display.");
+            } else {
+                table.table(ui);
+            }
+        }
+
+        if let Some(log) = &self.log {
+            let mut status = None;
+            ui.horizontal(|ui| {
+                ui.label("Export");
+                ui.text_edit_singleline(&mut self.export);
+                if ui.button("CSV").clicked() {
+                    let path = format!("{}.csv", self.export);
+                    status = Some(match log.to_csv(&path) {
+                        Ok(()) => format!("Change log written to {}.", path),
+                        Err(e) => format!("Could not write {}: {}", path, e),
+                    });
+                }
+                if ui.button("GeoJSON").clicked() {
+                    let path = format!("{}.geojson", self.export);
+                    status = Some(match log.to_geojson(&path) {
+                        Ok(()) => format!("Change log written to {}.", path),
+                        Err(e) => format!("Could not write {}: {}", path, e),
+                    });
+                }
+            });
+            if let Some(value) = status {
+                self.status = value;
+            }
+        }
+        if !self.status.is_empty() {
+            ui.label(&self.status);
+        }
+    }
+}
+
+impl AddressPoints {
+    /// Compares `self` as the earlier snapshot against `other`.
+    /// Wraps [`ChangeLog::addresses`].
+    pub fn diff(&self, other: &AddressPoints) -> ChangeLog {
+        ChangeLog::addresses(self, other)
+    }
+}
+
+impl Parcels {
+    /// Compares `self` as the earlier snapshot against `other`.
+    /// Wraps [`ChangeLog::parcels`].
+    pub fn diff(&self, other: &Parcels) -> ChangeLog {
+        ChangeLog::parcels(self, other)
+    }
+}
@@ -1,14 +1,22 @@
+use crate::addresses::AddressPoints;
 use crate::controls::act;
+use crate::diff::DiffView;
 use crate::identifier::Identifier;
+use crate::map::{LayerData, LayerKind, LayerStack, MapEdit, MapView, SpatialSelection};
+use crate::merge::MergeView;
 use crate::observer;
-use crate::rpg::character::{Attributes, Character, DisplayField};
+use crate::parcels::Parcels;
+#[cfg(feature = "rpg")]
+use crate::rpg::character::Character;
+#[cfg(feature = "rpg")]
 use crate::rpg::players;
-use crate::table::{Columnar, Filtration, TableView, Tabular};
+use crate::table::{Columnar, Filtration, TableLayouts, TableView, Tabular};
 use derive_more::{Deref, DerefMut};
 // use egui_dock::dock_state::surface_index::SurfaceIndex;
 // use egui_dock::dock_state::tree::{node_index::NodeIndex, tab_index::TabIndex};
 use egui_dock::{NodeIndex, SurfaceIndex, TabIndex};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
 // pub type Tab = table::TableView<character::Attributes, character::DisplayField, String>;
 /// The `Tab` type follows the naming convention of [`egui_dock`].
@@ -16,10 +24,88 @@ use std::collections::HashSet;
 /// but since we are constantly swapping it out with new variations in the development process, I
 /// placed it top of module for high visibility and easy access.
 ///
-/// The [`TabView`] holds a view of a [`Attributes`], currently Paeva.
-/// The [`DisplayField`] defines the content of columns in the table.
-/// The [`String`] is the type used for enabling search within the contents of the table.
-pub type Tab = Character;
+/// The `Character` variant holds a [`Character`] sheet, currently Paeva.
+/// The `Diff` variant holds a [`DiffView`] comparing two snapshots of a dataset.
+/// The `Merge` variant holds a [`MergeView`] reconciling two edited copies of a dataset.
+/// The `Map` variant holds a [`MapView`] displaying address points and parcels.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tab {
+    #[cfg(feature = "rpg")]
+    Character(Character),
+    Diff(DiffView),
+    Merge(MergeView),
+    Map(MapView),
+}
+
+impl Tab {
+    /// The `starting` method returns the tab opened with the dock, the Paeva character sheet if
+    /// the `rpg` feature is enabled, or else the diff viewer.
+    pub fn starting() -> Self {
+        #[cfg(feature = "rpg")]
+        return Self::Character(players::Players::paeva());
+        #[cfg(not(feature = "rpg"))]
+        return Self::Diff(DiffView::new());
+    }
+
+    /// The `name` method returns the display name of the tab contents.
+    pub fn name(&self) -> String {
+        match self {
+            #[cfg(feature = "rpg")]
+            Self::Character(character) => character.name().clone(),
+            Self::Diff(_) => "Changes".to_string(),
+            Self::Merge(_) => "Merge".to_string(),
+            Self::Map(_) => "Map".to_string(),
+        }
+    }
+
+    /// The `identifier` method returns the unique identifier assigned by the [`TabViewer`], if
+    /// set.
+    pub fn identifier(&self) -> Option<&String> {
+        match self {
+            #[cfg(feature = "rpg")]
+            Self::Character(character) => character.identifier().as_ref(),
+            Self::Diff(diff) => diff.identifier.as_ref(),
+            Self::Merge(merge) => merge.identifier.as_ref(),
+            Self::Map(map) => map.identifier.as_ref(),
+        }
+    }
+
+    /// The `identify` method returns the unique identifier of the tab, first assigning it the
+    /// next number from `identifier` if it has none.
+    pub fn identify(&mut self, identifier: &mut Identifier) -> String {
+        if let Some(value) = self.identifier() {
+            return value.clone();
+        }
+        let concat = format!("{}: {}", self.name(), identifier.number());
+        self.with_identifier(concat.clone());
+        concat
+    }
+
+    /// The `with_identifier` method sets the unique identifier of the tab to `identifier`.
+    pub fn with_identifier(&mut self, identifier: String) {
+        match self {
+            #[cfg(feature = "rpg")]
+            Self::Character(character) => {
+                character.with_identifier(identifier);
+            }
+            Self::Diff(diff) => diff.identifier = Some(identifier),
+            Self::Merge(merge) => merge.identifier = Some(identifier),
+            Self::Map(map) => map.identifier = Some(identifier),
+        }
+    }
+
+    /// The `view` method displays the contents of the tab within an [`egui::Ui`].
+    #[cfg_attr(not(feature = "rpg"), allow(unused_variables))]
+    pub fn view(&mut self, ui: &mut egui::Ui, name: &str) {
+        match self {
+            #[cfg(feature = "rpg")]
+            Self::Character(character) => character.view(ui, name),
+            Self::Diff(diff) => diff.view(ui),
+            Self::Merge(merge) => merge.view(ui),
+            Self::Map(map) => map.view(ui),
+        }
+    }
+}
 // pub type Tab = TabView<Attributes, DisplayField, String>;
 
 /// The `TabView` struct is a wrapper around a [`TableView`] that provides a unique name for the
@@ -88,6 +174,10 @@ pub enum ContextMenu {
     App,
     /// The `Map` variant indicates a map-based interface using `galileo`.
     Map,
+    /// The `Diff` variant indicates a comparison of two dataset snapshots.
+    Diff,
+    /// The `Merge` variant indicates a three-way merge of edited dataset snapshots.
+    Merge,
 }
 
 #[derive(Debug, Clone, derive_new::new, derive_getters::Getters)]
@@ -115,22 +205,14 @@ impl egui_dock::TabViewer for TabViewer<'_, '_> {
     /// The `title` method provides a unique id for [`egui`] to track, so each tab needs a unique
     /// title.
     fn title(&mut self, tab: &mut Self::Tab) -> egui::WidgetText {
-        if let Some(value) = tab.identifier() {
-            value.into()
-        } else {
-            let name = tab.name();
-            let id = self.identifier.number();
-            let concat = format!("{name}: {id}");
-            tab.with_identifier(concat.clone());
-            concat.into()
-        }
+        tab.identify(self.identifier).into()
     }
 
     /// The `ui` method presents the user with an interface inside the [`egui_dock::DockArea`].
     fn ui(&mut self, ui: &mut egui::Ui, tab: &mut Self::Tab) {
         let name = tab.name();
-        ui.push_id(name, |ui| {
-            tab.view(ui, name);
+        ui.push_id(&name, |ui| {
+            tab.view(ui, &name);
         });
         // tab.view(ui, name);
     }
@@ -153,6 +235,16 @@ impl egui_dock::TabViewer for TabViewer<'_, '_> {
             self.added_nodes
                 .push(TabContext::new(ContextMenu::Map, surface, node));
         }
+
+        if ui.button("Changes").clicked() {
+            self.added_nodes
+                .push(TabContext::new(ContextMenu::Diff, surface, node));
+        }
+
+        if ui.button("Merge").clicked() {
+            self.added_nodes
+                .push(TabContext::new(ContextMenu::Merge, surface, node));
+        }
     }
 }
 
@@ -353,21 +445,17 @@ pub struct TabState {
     // Observability helper.
     observer: observer::Observer,
     identifier: Identifier,
+    // Layer panel of each map tab, by tab identifier, saved with the session.
+    layers: HashMap<String, LayerStack>,
+    // Column settings given to the tables of new tabs, and saved with the session.
+    layouts: TableLayouts,
 }
 
 impl TabState {
     pub fn new() -> Self {
-        // Create a `DockState` with an initial tab "tab1" in the main `Surface`'s root node.
-        let paeva = players::Players::paeva();
+        // Create a `DockState` with an initial tab in the main `Surface`'s root node.
         let identifier: Identifier = Default::default();
-        let attributes = paeva.attributes();
-        let table = TableView::new(*attributes);
-        let mut gen = names::Generator::with_naming(names::Name::Numbered);
-        let name = gen.next().expect("Could not get name from generator.");
-        let mut tab_names = HashSet::new();
-        tab_names.insert(name.clone());
-        let tab_view = TabView::named(table, &name);
-        let tree = egui_dock::DockState::new(vec![paeva]);
+        let tree = egui_dock::DockState::new(vec![Tab::starting()]);
         let records = Records::from(&tree);
         let surfaces = records.surfaces();
         let nodes = records.nodes();
@@ -405,6 +493,8 @@ impl TabState {
             tab_names,
             observer,
             identifier,
+            layers: HashMap::new(),
+            layouts: TableLayouts::default(),
         }
     }
 
@@ -416,6 +506,166 @@ impl TabState {
         }
     }
 
+    /// The `prepare` method renders the map tabs to their textures ahead of the frame.
+    pub fn prepare(
+        &mut self,
+        device: &std::sync::Arc<wgpu::Device>,
+        queue: &std::sync::Arc<wgpu::Queue>,
+        renderer: &mut egui_wgpu::Renderer,
+    ) {
+        for (_, tab) in self.tree.iter_all_tabs_mut() {
+            if let Tab::Map(map) = tab {
+                map.prepare(device, queue, renderer);
+            }
+        }
+    }
+
+    /// The `store_layers` method keeps the layer panel of each map tab under the identifier of
+    /// the tab, to save with the session.  Panels kept for tabs not open now are left in place.
+    pub fn store_layers(&mut self) {
+        for (_, tab) in self.tree.iter_all_tabs() {
+            if let Tab::Map(map) = tab {
+                if let Some(identifier) = &map.identifier {
+                    self.layers.insert(identifier.clone(), map.layers.clone());
+                }
+            }
+        }
+    }
+
+    /// The `store_layouts` method saves the column settings of the table in each tab to
+    /// `layouts`, and keeps them for new tabs.
+    pub fn store_layouts(&mut self, layouts: &mut TableLayouts) {
+        for (_, tab) in self.tree.iter_all_tabs_mut() {
+            if let Tab::Diff(diff) = tab {
+                diff.store_layout(layouts);
+            }
+        }
+        self.layouts = layouts.clone();
+    }
+
+    /// The `restore_layouts` method applies the column settings in `layouts` to the table in each
+    /// tab, and keeps them for new tabs, as when loading a saved session.
+    pub fn restore_layouts(&mut self, layouts: &TableLayouts) {
+        for (_, tab) in self.tree.iter_all_tabs_mut() {
+            if let Tab::Diff(diff) = tab {
+                diff.restore_layout(layouts);
+            }
+        }
+        self.layouts = layouts.clone();
+    }
+
+    /// The `restore_layers` method sets the layer panels given to map tabs as they open, by
+    /// tab identifier, as when loading a saved session.
+    pub fn restore_layers(&mut self, layers: HashMap<String, LayerStack>) {
+        self.layers = layers;
+    }
+
+    /// The `take_picks` method returns the ids of the address points identified on any map tab
+    /// since the last call.
+    pub fn take_picks(&mut self) -> Vec<uuid::Uuid> {
+        let mut picks = Vec::new();
+        for (_, tab) in self.tree.iter_all_tabs_mut() {
+            if let Tab::Map(map) = tab {
+                picks.extend(map.take_picks());
+            }
+        }
+        picks
+    }
+
+    /// The `take_selections` method returns the selections drawn on any map tab since the last
+    /// call.
+    pub fn take_selections(&mut self) -> Vec<SpatialSelection> {
+        let mut selections = Vec::new();
+        for (_, tab) in self.tree.iter_all_tabs_mut() {
+            if let Tab::Map(map) = tab {
+                selections.extend(map.take_selections());
+            }
+        }
+        selections
+    }
+
+    /// The `take_edits` method returns the edits made with the editing tools of any map tab
+    /// since the last call.
+    pub fn take_edits(&mut self) -> Vec<MapEdit> {
+        let mut edits = Vec::new();
+        for (_, tab) in self.tree.iter_all_tabs_mut() {
+            if let Tab::Map(map) = tab {
+                edits.extend(map.take_edits());
+            }
+        }
+        edits
+    }
+
+    /// The `set_editable` method enables or disables the editing tools of every map tab.
+    pub fn set_editable(&mut self, editable: bool) {
+        for (_, tab) in self.tree.iter_all_tabs_mut() {
+            if let Tab::Map(map) = tab {
+                map.editable = editable;
+            }
+        }
+    }
+
+    /// The `sync_addresses` method copies the rows of the address table to the map tabs not yet
+    /// showing the table as of `revision`, once for all of them to share.
+    pub fn sync_addresses(&mut self, addresses: &AddressPoints, revision: u64) {
+        let mut records = None;
+        for (_, tab) in self.tree.iter_all_tabs_mut() {
+            if let Tab::Map(map) = tab {
+                if !map.is_synced(revision) {
+                    let records =
+                        records.get_or_insert_with(|| Arc::new(addresses.records.to_vec()));
+                    map.sync_addresses(records.clone(), revision);
+                }
+            }
+        }
+    }
+
+    /// The `share_parcels` method gives `parcels`, read from `path`, to every map tab that does
+    /// not have them yet, so the maps draw them without reading the file again.
+    pub fn share_parcels(&mut self, parcels: &Parcels, path: &str) {
+        let mut records = None;
+        for (_, tab) in self.tree.iter_all_tabs_mut() {
+            if let Tab::Map(map) = tab {
+                if !map.is_shared(LayerKind::Parcels, path) {
+                    let records = records.get_or_insert_with(|| Arc::new(parcels.records.to_vec()));
+                    map.share(
+                        LayerKind::Parcels,
+                        path,
+                        LayerData::Parcels(records.clone()),
+                    );
+                }
+            }
+        }
+    }
+
+    /// The `report` method shows `message` on the status line of every map tab.
+    pub fn report(&mut self, message: &str) {
+        for (_, tab) in self.tree.iter_all_tabs_mut() {
+            if let Tab::Map(map) = tab {
+                map.report(message);
+            }
+        }
+    }
+
+    /// The `highlight` method highlights the address points with an id in `ids` on every map
+    /// tab.
+    pub fn highlight(&mut self, ids: &HashSet<uuid::Uuid>) {
+        for (_, tab) in self.tree.iter_all_tabs_mut() {
+            if let Tab::Map(map) = tab {
+                map.highlight(ids);
+            }
+        }
+    }
+
+    /// The `navigate` method passes `act` to the focused tab if it is a map.  Returns `false` if
+    /// the act was not handled, so the caller can pass it on.
+    pub fn navigate(&mut self, act: &act::EguiAct) -> bool {
+        match self.leaf() {
+            Some(Tab::Map(map)) => map.act(act),
+            _ => false,
+        }
+    }
+
     pub fn new_name(&mut self) -> String {
         let mut gen = names::Generator::with_naming(names::Name::Numbered);
         loop {
@@ -1108,13 +1358,24 @@ impl TabState {
 
         // At this point we can inspect the TabContext and take different actions according the
         // variant of the ContextMenu.
-        // Currently we do one action and do not match on the ContextMenu.
         added_nodes.drain(..).for_each(|tab_context| {
             self.tree
                 .set_focused_node_and_surface((tab_context.surface, tab_context.node));
-            self.tree.push_to_focused_leaf({
-                players::Players::paeva()
-
+            self.tree.push_to_focused_leaf(match tab_context.kind {
+                ContextMenu::Diff => Tab::Diff(DiffView::new().with_layouts(self.layouts.clone())),
+                ContextMenu::Merge => Tab::Merge(MergeView::new()),
+                ContextMenu::Map => {
+                    // Identify the tab now, so it opens with the layer panel saved under its
+                    // identifier.
+                    let mut tab = Tab::Map(MapView::new());
+                    let identifier = tab.identify(&mut self.identifier);
+                    if let (Tab::Map(map), Some(layers)) = (&mut tab, self.layers.get(&identifier))
+                    {
+                        map.layers = layers.clone();
+                    }
+                    tab
+                }
+                _ => Tab::starting(),
                 // let attr = paeva.attributes();
                 // let table = TableView::new(*attr);
                 // TabView::with_name(
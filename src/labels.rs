@@ -0,0 +1,134 @@
+//! The `labels` module places the text labels of map features.
+//!
+//! Labels sit beside points and at an interior point of polygons, and [`declutter`] drops any
+//! label overlapping one placed before it, so the labels of the topmost layers win.
+use galileo_types::cartesian::{CartesianPoint2d, Point2d};
+use std::collections::HashMap;
+
+/// Greatest number of labels considered for one view.
+pub const MAX_LABELS: usize = 4000;
+/// Width in screen points of the grid cells used to find overlapping labels.
+pub const LABEL_CELL: f32 = 64.0;
+
+/// Returns twice the signed area of `ring`, positive if its points run counterclockwise.
+fn signed_area(ring: &[Point2d]) -> f64 {
+    ring.iter()
+        .zip(ring.iter().cycle().skip(1))
+        .map(|(a, b)| a.x() * b.y() - b.x() * a.y())
+        .sum()
+}
+
+/// The `largest_ring` function returns the ring of `rings` enclosing the greatest area.
+pub fn largest_ring(rings: &[Vec<Point2d>]) -> Option<&Vec<Point2d>> {
+    rings
+        .iter()
+        .max_by(|a, b| signed_area(a).abs().total_cmp(&signed_area(b).abs()))
+}
+
+/// The `interior_point` function returns a point inside the polygon `ring` for placing its
+/// label.  The centroid of a concave polygon may fall outside it, so we cross the polygon with a
+/// horizontal line through the centroid and take the middle of the widest span inside it.
+pub fn interior_point(ring: &[Point2d]) -> Option<Point2d> {
+    if ring.len() < 3 {
+        return None;
+    }
+    let area = signed_area(ring);
+    let n = ring.len() as f64;
+    let (cx, cy) = match area.abs() > f64::EPSILON {
+        true => {
+            let (sx, sy) = ring.iter().zip(ring.iter().cycle().skip(1)).fold(
+                (0.0, 0.0),
+                |(sx, sy), (a, b)| {
+                    let cross = a.x() * b.y() - b.x() * a.y();
+                    (sx + (a.x() + b.x()) * cross, sy + (a.y() + b.y()) * cross)
+                },
+            );
+            (sx / (3.0 * area), sy / (3.0 * area))
+        }
+        false => (
+            ring.iter().map(|v| v.x()).sum::<f64>() / n,
+            ring.iter().map(|v| v.y()).sum::<f64>() / n,
+        ),
+    };
+    let mut crossings = ring
+        .iter()
+        .zip(ring.iter().cycle().skip(1))
+        .filter(|(a, b)| (a.y() > cy) != (b.y() > cy))
+        .map(|(a, b)| a.x() + (cy - a.y()) / (b.y() - a.y()) * (b.x() - a.x()))
+        .collect::<Vec<f64>>();
+    crossings.sort_by(f64::total_cmp);
+    let span = crossings
+        .chunks_exact(2)
+        .max_by(|a, b| (a[1] - a[0]).total_cmp(&(b[1] - b[0])));
+    match span {
+        Some(span) => Some(Point2d::new((span[0] + span[1]) / 2.0, cy)),
+        None => Some(Point2d::new(cx, cy)),
+    }
+}
+
+/// The `LabelBox` struct is the area of the screen covered by a label, in points.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LabelBox {
+    pub x_min: f32,
+    pub y_min: f32,
+    pub x_max: f32,
+    pub y_max: f32,
+}
+
+impl LabelBox {
+    /// Returns the box of size `width` by `height` with its top left corner at `(x, y)`.
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x_min: x,
+            y_min: y,
+            x_max: x + width,
+            y_max: y + height,
+        }
+    }
+
+    /// Grows the box by `margin` on every side, to keep labels apart.
+    pub fn expand(self, margin: f32) -> Self {
+        Self {
+            x_min: self.x_min - margin,
+            y_min: self.y_min - margin,
+            x_max: self.x_max + margin,
+            y_max: self.y_max + margin,
+        }
+    }
+
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.x_min < other.x_max
+            && other.x_min < self.x_max
+            && self.y_min < other.y_max
+            && other.y_min < self.y_max
+    }
+
+    /// Returns the cells of a grid of width `cell` touched by the box.
+    fn cells(&self, cell: f32) -> impl Iterator<Item = (i32, i32)> {
+        let index = |v: f32| (v / cell).floor() as i32;
+        let (x_min, x_max) = (index(self.x_min), index(self.x_max));
+        let (y_min, y_max) = (index(self.y_min), index(self.y_max));
+        (x_min..=x_max).flat_map(move |x| (y_min..=y_max).map(move |y| (x, y)))
+    }
+}
+
+/// The `declutter` function returns the indices of the `boxes` kept so that no two overlap.
+/// Boxes are taken in order, so a box is dropped if it overlaps any box kept before it.
+pub fn declutter(boxes: &[LabelBox]) -> Vec<usize> {
+    let mut grid: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+    let mut kept = Vec::new();
+    for (index, label) in boxes.iter().enumerate() {
+        let clear = label.cells(LABEL_CELL).all(|cell| {
+            grid.get(&cell)
+                .map(|v| v.iter().all(|other| !boxes[*other].overlaps(label)))
+                .unwrap_or(true)
+        });
+        if clear {
+            for cell in label.cells(LABEL_CELL) {
+                grid.entry(cell).or_default().push(index);
+            }
+            kept.push(index);
+        }
+    }
+    kept
+}
@@ -0,0 +1,1112 @@
+//! The `layout` module composes printed maps and writes them to SVG and PDF.
+//!
+//! A [`Layout`] places the map at a chosen center and scale on a page, with a title, a legend
+//! drawn from the [`LayerStyle`] of each layer, a scale bar, a north arrow, the date and the
+//! credit line.  [`Layout::compose`] draws the page as a list of vector [`Mark`]s in points, and
+//! the [`Page`] writes the marks to SVG or PDF on the CPU, so maps print on machines without a
+//! GPU.  The PDF uses the standard Helvetica fonts, which every reader supplies, so no fonts are
+//! embedded.
+use crate::map::{LayerData, MapLayer};
+use crate::measure::{FEET_PER_MILE, METERS_PER_FOOT};
+use crate::prelude::{lonlat_to_mercator, HexColor, LayerStyle, Style};
+use crate::tiles::TILE_SIZE;
+use crate::utils::EARTH_RADIUS;
+use chrono::NaiveDate;
+#[cfg(feature = "gui")]
+use flate2::{write::ZlibEncoder, Compression};
+use galileo_types::cartesian::{CartesianPoint2d, Point2d, Rect};
+use galileo_types::geo::GeoPoint;
+use polite::Polite;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+#[cfg(feature = "gui")]
+use std::io::Write as _;
+use std::path::Path;
+
+/// Credit line printed on maps, from the rights statement of the address metadata.
+pub const CREDIT: &str = "The City of Grants Pass and various departments maintain GIS data related to their division.  For specific information pertaining to this GIS layer please contact the responsible department.  For general inquiry please contact the GIS department at 541-450-6112.";
+/// Points per inch on the page.
+pub const POINTS_PER_INCH: f64 = 72.0;
+const METERS_PER_INCH: f64 = 0.0254;
+/// Points per pixel, for drawing the sizes in the symbology on the page.
+const POINTS_PER_PIXEL: f64 = 0.75;
+/// Space around the edges of the page, in points.
+const MARGIN: f64 = 36.0;
+/// Space between the parts of the layout, in points.
+const GAP: f64 = 12.0;
+/// Height of the title above the map frame, in points.
+const HEADER: f64 = 30.0;
+/// Height of the scale bar, date and credit below the map frame, in points.
+const FOOTER: f64 = 64.0;
+/// Width of the legend beside the map frame, in points.
+const LEGEND_WIDTH: f64 = 168.0;
+
+/// Widths of the printable ASCII characters in Helvetica, in thousandths of the font size.
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+/// The `text_width` function returns the width in points of `text` set in Helvetica at `size`,
+/// or in Helvetica Bold if `bold` is set.  Bold is wider by about a twentieth.
+pub fn text_width(text: &str, size: f64, bold: bool) -> f64 {
+    let width = text
+        .chars()
+        .map(|c| match c as usize {
+            v @ 0x20..=0x7e => HELVETICA_WIDTHS[v - 0x20] as f64,
+            _ => 556.0,
+        })
+        .sum::<f64>()
+        * size
+        / 1000.0;
+    match bold {
+        true => width * 1.05,
+        false => width,
+    }
+}
+
+/// The `wrap` function breaks `text` into lines no wider than `width` points at `size`.
+pub fn wrap(text: &str, size: f64, width: f64) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let next = match line.is_empty() {
+            true => word.to_string(),
+            false => format!("{line} {word}"),
+        };
+        if !line.is_empty() && text_width(&next, size, false) > width {
+            lines.push(std::mem::replace(&mut line, word.to_string()));
+        } else {
+            line = next;
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// The `PageSize` enum is the size of the paper a layout prints on.
+#[derive(
+    Debug,
+    Default,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    Deserialize,
+    Serialize,
+    strum_macros::Display,
+    strum_macros::EnumIter,
+)]
+pub enum PageSize {
+    /// 8.5 by 11 inches.
+    #[default]
+    Letter,
+    /// 8.5 by 14 inches.
+    Legal,
+    /// 11 by 17 inches.
+    Tabloid,
+}
+
+impl PageSize {
+    /// Returns the width and height of the page in portrait, in points.
+    pub fn points(&self) -> (f64, f64) {
+        match self {
+            Self::Letter => (612.0, 792.0),
+            Self::Legal => (612.0, 1008.0),
+            Self::Tabloid => (792.0, 1224.0),
+        }
+    }
+}
+
+/// The `Anchor` enum is the part of a line of text placed at the position of a [`Mark::Text`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Anchor {
+    #[default]
+    Start,
+    Middle,
+    End,
+}
+
+/// The `Mark` enum is one vector shape drawn on a [`Page`].  Positions are `[x, y]` in points
+/// from the top left corner of the page.  A shape without a fill or stroke draws nothing.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mark {
+    /// A polygon of one or more rings, filled by the even-odd rule so inner rings make holes.
+    Polygon {
+        rings: Vec<Vec<[f64; 2]>>,
+        fill: Option<HexColor>,
+        /// Color and width of the edges.
+        stroke: Option<(HexColor, f64)>,
+    },
+    /// An open line.
+    Line {
+        points: Vec<[f64; 2]>,
+        stroke: (HexColor, f64),
+    },
+    Circle {
+        center: [f64; 2],
+        radius: f64,
+        fill: Option<HexColor>,
+        stroke: Option<(HexColor, f64)>,
+    },
+    /// A line of text in Helvetica, with `position` on its baseline.
+    Text {
+        position: [f64; 2],
+        text: String,
+        size: f64,
+        bold: bool,
+        color: HexColor,
+        anchor: Anchor,
+    },
+    /// Marks drawn only within the rectangle `[x, y, width, height]`.
+    Clip { rect: [f64; 4], marks: Vec<Mark> },
+}
+
+impl Mark {
+    /// Returns the rectangle `[x, y, width, height]` as a polygon.
+    pub fn rect(rect: [f64; 4], fill: Option<HexColor>, stroke: Option<(HexColor, f64)>) -> Self {
+        let [x, y, width, height] = rect;
+        Self::Polygon {
+            rings: vec![vec![
+                [x, y],
+                [x + width, y],
+                [x + width, y + height],
+                [x, y + height],
+            ]],
+            fill,
+            stroke,
+        }
+    }
+
+    pub fn text(position: [f64; 2], text: &str, size: f64) -> Self {
+        Self::Text {
+            position,
+            text: text.to_string(),
+            size,
+            bold: false,
+            color: HexColor::rgb(0, 0, 0),
+            anchor: Anchor::Start,
+        }
+    }
+
+    pub fn bold(mut self) -> Self {
+        if let Self::Text { bold, .. } = &mut self {
+            *bold = true;
+        }
+        self
+    }
+
+    pub fn anchored(mut self, to: Anchor) -> Self {
+        if let Self::Text { anchor, .. } = &mut self {
+            *anchor = to;
+        }
+        self
+    }
+}
+
+/// The `Page` struct is a composed layout, ready to write to SVG or PDF.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page {
+    pub title: String,
+    /// Width of the page in points.
+    pub width: f64,
+    /// Height of the page in points.
+    pub height: f64,
+    /// Marks in drawing order, bottom first.
+    pub marks: Vec<Mark>,
+}
+
+/// Writes `value` with at most two decimal places and no trailing zeros.
+fn number(value: f64) -> String {
+    let text = format!("{:.2}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    match text {
+        "-0" => "0".to_string(),
+        _ => text.to_string(),
+    }
+}
+
+/// Writes an opacity from the alpha channel of `color`.
+fn alpha(color: &HexColor) -> String {
+    number(color.0[3] as f64 / 255.0)
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The `pdf_stream` function compresses the PDF content stream `content`, returning the bytes of
+/// the stream and the filter entry of its dictionary.
+#[cfg(feature = "gui")]
+fn pdf_stream(content: &str) -> Polite<(Vec<u8>, &'static str)> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(content.as_bytes())?;
+    Ok((encoder.finish()?, " /Filter /FlateDecode"))
+}
+
+/// The `pdf_stream` function returns the PDF content stream `content` uncompressed, since builds
+/// without the `gui` feature leave out the compression library.
+#[cfg(not(feature = "gui"))]
+fn pdf_stream(content: &str) -> Polite<(Vec<u8>, &'static str)> {
+    Ok((content.as_bytes().to_vec(), ""))
+}
+
+/// Writes `text` as a PDF string in the WinAnsi encoding of the standard fonts.  Characters
+/// outside Latin-1 print as `?`.
+fn escape_pdf(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            ' '..='~' => escaped.push(c),
+            '\u{a0}'..='\u{ff}' => {
+                let _ = write!(escaped, "\\{:03o}", c as u32);
+            }
+            _ => escaped.push('?'),
+        }
+    }
+    escaped
+}
+
+impl Page {
+    /// The `to_svg` method writes the page as an SVG document sized in points.
+    pub fn to_svg(&self) -> String {
+        let mut svg = String::new();
+        let _ = writeln!(svg, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}pt" height="{h}pt" viewBox="0 0 {w} {h}">"#,
+            w = number(self.width),
+            h = number(self.height)
+        );
+        let _ = writeln!(svg, "<title>{}</title>", escape_xml(&self.title));
+        let mut clips = 0;
+        for mark in &self.marks {
+            Self::svg_mark(&mut svg, mark, &mut clips);
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    fn svg_paint(fill: &Option<HexColor>, stroke: &Option<(HexColor, f64)>) -> String {
+        let mut paint = String::new();
+        match fill {
+            Some(color) => {
+                let [r, g, b, _] = color.0;
+                let _ = write!(paint, r##" fill="#{r:02x}{g:02x}{b:02x}""##);
+                if color.0[3] < 255 {
+                    let _ = write!(paint, r#" fill-opacity="{}""#, alpha(color));
+                }
+            }
+            None => paint.push_str(r#" fill="none""#),
+        }
+        if let Some((color, width)) = stroke {
+            let [r, g, b, _] = color.0;
+            let _ = write!(
+                paint,
+                r##" stroke="#{r:02x}{g:02x}{b:02x}" stroke-width="{}" stroke-linejoin="round""##,
+                number(*width)
+            );
+            if color.0[3] < 255 {
+                let _ = write!(paint, r#" stroke-opacity="{}""#, alpha(color));
+            }
+        }
+        paint
+    }
+
+    fn svg_path(points: &[[f64; 2]], close: bool) -> String {
+        let mut path = String::new();
+        for (i, [x, y]) in points.iter().enumerate() {
+            let command = if i == 0 { 'M' } else { 'L' };
+            let _ = write!(path, "{command}{} {} ", number(*x), number(*y));
+        }
+        if close && !points.is_empty() {
+            path.push('Z');
+        }
+        path.trim_end().to_string()
+    }
+
+    fn svg_mark(svg: &mut String, mark: &Mark, clips: &mut usize) {
+        match mark {
+            Mark::Polygon {
+                rings,
+                fill,
+                stroke,
+            } => {
+                let path = rings
+                    .iter()
+                    .map(|v| Self::svg_path(v, true))
+                    .collect::<Vec<String>>()
+                    .join(" ");
+                let _ = writeln!(
+                    svg,
+                    r#"<path d="{path}" fill-rule="evenodd"{}/>"#,
+                    Self::svg_paint(fill, stroke)
+                );
+            }
+            Mark::Line { points, stroke } => {
+                let _ = writeln!(
+                    svg,
+                    r#"<path d="{}" stroke-linecap="round"{}/>"#,
+                    Self::svg_path(points, false),
+                    Self::svg_paint(&None, &Some(*stroke))
+                );
+            }
+            Mark::Circle {
+                center,
+                radius,
+                fill,
+                stroke,
+            } => {
+                let _ = writeln!(
+                    svg,
+                    r#"<circle cx="{}" cy="{}" r="{}"{}/>"#,
+                    number(center[0]),
+                    number(center[1]),
+                    number(*radius),
+                    Self::svg_paint(fill, stroke)
+                );
+            }
+            Mark::Text {
+                position,
+                text,
+                size,
+                bold,
+                color,
+                anchor,
+            } => {
+                let anchor = match anchor {
+                    Anchor::Start => "start",
+                    Anchor::Middle => "middle",
+                    Anchor::End => "end",
+                };
+                let weight = match bold {
+                    true => r#" font-weight="bold""#,
+                    false => "",
+                };
+                let _ = writeln!(
+                    svg,
+                    r#"<text x="{}" y="{}" font-family="Helvetica, Arial, sans-serif" font-size="{}"{weight} text-anchor="{anchor}"{}>{}</text>"#,
+                    number(position[0]),
+                    number(position[1]),
+                    number(*size),
+                    Self::svg_paint(&Some(*color), &None),
+                    escape_xml(text)
+                );
+            }
+            Mark::Clip { rect, marks } => {
+                let id = format!("clip{}", clips);
+                *clips += 1;
+                let [x, y, width, height] = rect.map(number);
+                let _ = writeln!(
+                    svg,
+                    r#"<clipPath id="{id}"><rect x="{x}" y="{y}" width="{width}" height="{height}"/></clipPath>"#
+                );
+                let _ = writeln!(svg, r#"<g clip-path="url(#{id})">"#);
+                for mark in marks {
+                    Self::svg_mark(svg, mark, clips);
+                }
+                svg.push_str("</g>\n");
+            }
+        }
+    }
+
+    /// The `to_pdf` method writes the page as a single page PDF document.  The content stream is
+    /// compressed in builds with the `gui` feature.
+    pub fn to_pdf(&self) -> Polite<Vec<u8>> {
+        let mut content = String::new();
+        let mut states = BTreeMap::new();
+        for mark in &self.marks {
+            self.pdf_mark(&mut content, mark, &mut states);
+        }
+        let (stream, filter) = pdf_stream(&content)?;
+
+        let states_dict = states
+            .iter()
+            .map(|((fill, stroke), name)| {
+                format!(
+                    "/{name} << /Type /ExtGState /ca {} /CA {} >>",
+                    number(*fill as f64 / 255.0),
+                    number(*stroke as f64 / 255.0)
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+        let mut objects = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".as_bytes().to_vec(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>"
+                .as_bytes()
+                .to_vec(),
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Contents 4 0 R /Resources << /Font << /F1 5 0 R /F2 6 0 R >> /ExtGState << {} >> >> >>",
+                number(self.width),
+                number(self.height),
+                states_dict
+            )
+            .into_bytes(),
+        ];
+        let mut contents = format!("<< /Length {}{filter} >>\nstream\n", stream.len()).into_bytes();
+        contents.extend(stream);
+        contents.extend(b"\nendstream");
+        objects.push(contents);
+        for font in ["Helvetica", "Helvetica-Bold"] {
+            objects.push(
+                format!(
+                    "<< /Type /Font /Subtype /Type1 /BaseFont /{font} /Encoding /WinAnsiEncoding >>"
+                )
+                .into_bytes(),
+            );
+        }
+        objects.push(
+            format!(
+                "<< /Title ({}) /Producer (whimsy) >>",
+                escape_pdf(&self.title)
+            )
+            .into_bytes(),
+        );
+
+        let mut pdf = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+        let mut offsets = Vec::new();
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend(format!("{} 0 obj\n", i + 1).as_bytes());
+            pdf.extend(object);
+            pdf.extend(b"\nendobj\n");
+        }
+        let xref = pdf.len();
+        let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+        for offset in offsets {
+            let _ = writeln!(table, "{:010} 00000 n ", offset);
+        }
+        let _ = writeln!(
+            table,
+            "trailer\n<< /Size {} /Root 1 0 R /Info {} 0 R >>\nstartxref\n{}\n%%EOF",
+            objects.len() + 1,
+            objects.len(),
+            xref
+        );
+        pdf.extend(table.as_bytes());
+        Ok(pdf)
+    }
+
+    /// Writes the point `[x, y]` in PDF coordinates, measured up from the bottom of the page.
+    fn pdf_point(&self, [x, y]: [f64; 2]) -> String {
+        format!("{} {}", number(x), number(self.height - y))
+    }
+
+    /// Writes the operators setting the colors of a shape, returning the operator that paints
+    /// it once its path is built, or `None` if it has neither fill nor stroke.
+    fn pdf_paint(
+        content: &mut String,
+        fill: &Option<HexColor>,
+        stroke: &Option<(HexColor, f64)>,
+        states: &mut BTreeMap<(u8, u8), String>,
+        even_odd: bool,
+    ) -> Option<&'static str> {
+        let rgb = |color: &HexColor| {
+            let [r, g, b, _] = color.0;
+            format!(
+                "{} {} {}",
+                number(r as f64 / 255.0),
+                number(g as f64 / 255.0),
+                number(b as f64 / 255.0)
+            )
+        };
+        let fill_alpha = fill.map(|v| v.0[3]).unwrap_or(255);
+        let stroke_alpha = stroke.map(|v| v.0 .0[3]).unwrap_or(255);
+        if (fill_alpha, stroke_alpha) != (255, 255) {
+            let count = states.len();
+            let name = states
+                .entry((fill_alpha, stroke_alpha))
+                .or_insert_with(|| format!("GS{count}"));
+            let _ = writeln!(content, "/{name} gs");
+        }
+        if let Some(color) = fill {
+            let _ = writeln!(content, "{} rg", rgb(color));
+        }
+        if let Some((color, width)) = stroke {
+            let _ = writeln!(content, "{} RG {} w 1 J 1 j", rgb(color), number(*width));
+        }
+        match (fill.is_some(), stroke.is_some(), even_odd) {
+            (true, true, true) => Some("B*"),
+            (true, true, false) => Some("B"),
+            (true, false, true) => Some("f*"),
+            (true, false, false) => Some("f"),
+            (false, true, _) => Some("S"),
+            (false, false, _) => None,
+        }
+    }
+
+    fn pdf_mark(&self, content: &mut String, mark: &Mark, states: &mut BTreeMap<(u8, u8), String>) {
+        content.push_str("q\n");
+        match mark {
+            Mark::Polygon {
+                rings,
+                fill,
+                stroke,
+            } => {
+                if let Some(paint) = Self::pdf_paint(content, fill, stroke, states, true) {
+                    for ring in rings.iter().filter(|v| !v.is_empty()) {
+                        let _ = writeln!(content, "{} m", self.pdf_point(ring[0]));
+                        for point in &ring[1..] {
+                            let _ = writeln!(content, "{} l", self.pdf_point(*point));
+                        }
+                        content.push_str("h\n");
+                    }
+                    let _ = writeln!(content, "{paint}");
+                }
+            }
+            Mark::Line { points, stroke } => {
+                Self::pdf_paint(content, &None, &Some(*stroke), states, false);
+                for (i, point) in points.iter().enumerate() {
+                    let operator = if i == 0 { 'm' } else { 'l' };
+                    let _ = writeln!(content, "{} {operator}", self.pdf_point(*point));
+                }
+                content.push_str("S\n");
+            }
+            Mark::Circle {
+                center,
+                radius,
+                fill,
+                stroke,
+            } => {
+                if let Some(paint) = Self::pdf_paint(content, fill, stroke, states, false) {
+                    // Four cubic Bézier curves, each within a tenth of a percent of a quarter
+                    // circle.
+                    let [x, y] = *center;
+                    let r = *radius;
+                    let k = r * 0.552_284_75;
+                    let _ = writeln!(content, "{} m", self.pdf_point([x + r, y]));
+                    let quarters = [
+                        [[x + r, y - k], [x + k, y - r], [x, y - r]],
+                        [[x - k, y - r], [x - r, y - k], [x - r, y]],
+                        [[x - r, y + k], [x - k, y + r], [x, y + r]],
+                        [[x + k, y + r], [x + r, y + k], [x + r, y]],
+                    ];
+                    for [a, b, c] in quarters {
+                        let _ = writeln!(
+                            content,
+                            "{} {} {} c",
+                            self.pdf_point(a),
+                            self.pdf_point(b),
+                            self.pdf_point(c)
+                        );
+                    }
+                    let _ = writeln!(content, "h {paint}");
+                }
+            }
+            Mark::Text {
+                position,
+                text,
+                size,
+                bold,
+                color,
+                anchor,
+            } => {
+                Self::pdf_paint(content, &Some(*color), &None, states, false);
+                let width = text_width(text, *size, *bold);
+                let x = match anchor {
+                    Anchor::Start => position[0],
+                    Anchor::Middle => position[0] - width / 2.0,
+                    Anchor::End => position[0] - width,
+                };
+                let font = if *bold { "F2" } else { "F1" };
+                let _ = writeln!(
+                    content,
+                    "BT /{font} {} Tf {} Td ({}) Tj ET",
+                    number(*size),
+                    self.pdf_point([x, position[1]]),
+                    escape_pdf(text)
+                );
+            }
+            Mark::Clip { rect, marks } => {
+                let [x, y, width, height] = *rect;
+                let _ = writeln!(
+                    content,
+                    "{} {} {} re W n",
+                    self.pdf_point([x, y + height]),
+                    number(width),
+                    number(height)
+                );
+                for mark in marks {
+                    self.pdf_mark(content, mark, states);
+                }
+            }
+        }
+        content.push_str("Q\n");
+    }
+
+    /// Writes the page to an SVG file at `path`.
+    pub fn save_svg<P: AsRef<Path>>(&self, path: P) -> Polite<()> {
+        fs::write(path, self.to_svg())?;
+        Ok(())
+    }
+
+    /// Writes the page to a PDF file at `path`.
+    pub fn save_pdf<P: AsRef<Path>>(&self, path: P) -> Polite<()> {
+        fs::write(path, self.to_pdf()?)?;
+        Ok(())
+    }
+}
+
+/// The `nice_scale` function rounds the scale denominator `scale` up to a round number, such as
+/// 1:2500, or an engineering scale of whole feet to the inch, such as 1:1200, 1:2400, 1:3600,
+/// 1:4800 or 1:7200.
+pub fn nice_scale(scale: f64) -> f64 {
+    if !scale.is_finite() || scale <= 1.0 {
+        return 1.0;
+    }
+    let magnitude = 10_f64.powf(scale.log10().floor());
+    // Steps in tenths, so the products are exact.
+    [10, 12, 15, 20, 24, 25, 30, 36, 40, 48, 50, 60, 72, 80, 100]
+        .iter()
+        .map(|v| f64::from(*v) * magnitude / 10.0)
+        .find(|v| *v >= scale * (1.0 - 1e-9))
+        .unwrap_or(10.0 * magnitude)
+}
+
+/// Returns the greatest number of the form 1, 2 or 5 times a power of ten no more than `value`.
+fn nice_length(value: f64) -> f64 {
+    let magnitude = 10_f64.powf(value.log10().floor());
+    [5.0, 2.0, 1.0]
+        .iter()
+        .map(|v| v * magnitude)
+        .find(|v| *v <= value)
+        .unwrap_or(magnitude)
+}
+
+/// Writes `value` rounded to a whole number with commas between the thousands.
+fn thousands(value: f64) -> String {
+    let digits = format!("{:.0}", value.abs());
+    let mut text = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            text.push(',');
+        }
+        text.push(c);
+    }
+    match value < 0.0 {
+        true => format!("-{text}"),
+        false => text,
+    }
+}
+
+/// The `PrintLayer` struct is one layer of a map printed by [`Layout::compose`].
+#[derive(Debug, Clone)]
+pub struct PrintLayer<'a> {
+    pub name: String,
+    pub data: &'a LayerData,
+    pub style: &'a LayerStyle,
+    /// Opacity from 0.0 to 1.0.
+    pub opacity: f32,
+}
+
+impl<'a> PrintLayer<'a> {
+    pub fn new(layer: &MapLayer, data: &'a LayerData, style: &'a LayerStyle) -> Self {
+        Self {
+            name: layer.name.clone(),
+            data,
+            style,
+            opacity: layer.opacity,
+        }
+    }
+
+    /// Returns `true` if the features are points, drawn in the legend as circles.
+    fn is_point(&self) -> bool {
+        !matches!(self.data, LayerData::Parcels(_))
+    }
+}
+
+/// The `Layout` struct holds the settings of a printed map.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Layout {
+    pub title: String,
+    pub page: PageSize,
+    /// Turns the page on its side.
+    pub landscape: bool,
+    /// Center of the map in EPSG:3857.
+    pub center: [f64; 2],
+    /// Denominator of the map scale, as 2400 for 1:2400.
+    pub scale: f64,
+    pub legend: bool,
+    pub scale_bar: bool,
+    pub north_arrow: bool,
+    /// Date printed on the map, or the day of printing if `None`.
+    pub date: Option<NaiveDate>,
+    /// Credit line printed below the map.
+    pub credit: String,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            title: "City of Grants Pass".to_string(),
+            page: PageSize::Letter,
+            landscape: true,
+            center: [0.0, 0.0],
+            scale: 2400.0,
+            legend: true,
+            scale_bar: true,
+            north_arrow: true,
+            date: None,
+            credit: CREDIT.to_string(),
+        }
+    }
+}
+
+impl Layout {
+    /// Returns the width and height of the page in points.
+    pub fn page_size(&self) -> (f64, f64) {
+        let (width, height) = self.page.points();
+        match self.landscape {
+            true => (height, width),
+            false => (width, height),
+        }
+    }
+
+    /// Returns the map frame on the page, as `[x, y, width, height]` in points.
+    pub fn frame(&self) -> [f64; 4] {
+        let (width, height) = self.page_size();
+        let legend = match self.legend {
+            true => LEGEND_WIDTH + GAP,
+            false => 0.0,
+        };
+        let top = MARGIN + HEADER;
+        [
+            MARGIN,
+            top,
+            (width - 2.0 * MARGIN - legend).max(1.0),
+            (height - MARGIN - FOOTER - top).max(1.0),
+        ]
+    }
+
+    /// Ground meters per point on the page at the scale of the layout.
+    fn ground_per_point(&self) -> f64 {
+        self.scale * METERS_PER_INCH / POINTS_PER_INCH
+    }
+
+    /// EPSG:3857 units per ground meter at the center of the map.  Web Mercator stretches
+    /// lengths by `1 / cos(lat)`, so the map resolution grows with the latitude to keep the
+    /// ground scale.
+    fn stretch(&self) -> f64 {
+        (self.center[1] / EARTH_RADIUS).cosh()
+    }
+
+    /// Returns the resolution of the map in EPSG:3857 units per point on the page.
+    pub fn resolution(&self) -> f64 {
+        self.ground_per_point() * self.stretch()
+    }
+
+    /// Returns the zoom level of the map, on the scale of web map tiles, for choosing the layers
+    /// drawn.
+    pub fn zoom_level(&self) -> f64 {
+        (2.0 * std::f64::consts::PI * EARTH_RADIUS / (TILE_SIZE * self.resolution())).log2()
+    }
+
+    /// Returns the extent of the map frame in EPSG:3857.
+    pub fn extent(&self) -> Rect {
+        let [_, _, width, height] = self.frame();
+        let resolution = self.resolution();
+        let [x, y] = self.center;
+        Rect::new(
+            x - width / 2.0 * resolution,
+            y - height / 2.0 * resolution,
+            x + width / 2.0 * resolution,
+            y + height / 2.0 * resolution,
+        )
+    }
+
+    /// Centers the map on `bounds` at the round scale that shows all of it.
+    pub fn fit(&mut self, bounds: &Rect) {
+        self.center = [
+            (bounds.x_min() + bounds.x_max()) / 2.0,
+            (bounds.y_min() + bounds.y_max()) / 2.0,
+        ];
+        let [_, _, width, height] = self.frame();
+        let resolution = ((bounds.x_max() - bounds.x_min()) / width)
+            .max((bounds.y_max() - bounds.y_min()) / height);
+        self.scale = nice_scale(resolution / self.stretch() * POINTS_PER_INCH / METERS_PER_INCH);
+    }
+
+    /// Centers the map on `center` at the round scale nearest above the screen `resolution`, so
+    /// the page shows the map about as it looks on screen.
+    pub fn view(&mut self, center: &Point2d, resolution: f64) {
+        self.center = [center.x(), center.y()];
+        self.scale = nice_scale(resolution / self.stretch() * POINTS_PER_INCH / METERS_PER_INCH);
+    }
+
+    /// Returns the position on the page of `point` in EPSG:3857.
+    fn to_page(&self, point: &Point2d) -> [f64; 2] {
+        let [x, y, width, height] = self.frame();
+        let resolution = self.resolution();
+        [
+            x + width / 2.0 + (point.x() - self.center[0]) / resolution,
+            y + height / 2.0 - (point.y() - self.center[1]) / resolution,
+        ]
+    }
+
+    /// The `compose` method draws the map of `layers`, topmost first, and the furniture of the
+    /// layout on a [`Page`].
+    pub fn compose(&self, layers: &[PrintLayer]) -> Page {
+        let (width, height) = self.page_size();
+        let frame = self.frame();
+        let mut marks = vec![Mark::text([MARGIN, MARGIN + 18.0], &self.title, 18.0).bold()];
+
+        let mut features = vec![Mark::rect(frame, Some(HexColor::rgb(255, 255, 255)), None)];
+        for layer in layers.iter().rev() {
+            self.features(layer, &mut features);
+        }
+        marks.push(Mark::Clip {
+            rect: frame,
+            marks: features,
+        });
+        marks.push(Mark::rect(frame, None, Some((HexColor::rgb(0, 0, 0), 1.0))));
+
+        if self.legend {
+            self.legend(layers, &mut marks);
+        }
+        let [_, top, _, frame_height] = frame;
+        let footer = top + frame_height + GAP;
+        let mut right = width - MARGIN;
+        if self.north_arrow {
+            self.north_arrow(right - 10.0, footer, &mut marks);
+            right -= 32.0;
+        }
+        if self.scale_bar {
+            self.scale_bar(MARGIN, footer, &mut marks);
+        }
+        let date = self
+            .date
+            .unwrap_or_else(|| chrono::Local::now().date_naive());
+        marks.push(
+            Mark::text(
+                [right, footer + 8.0],
+                &format!("Printed {}", date.format("%B %-d, %Y")),
+                8.0,
+            )
+            .anchored(Anchor::End),
+        );
+        let credit_width = right - MARGIN;
+        for (i, line) in wrap(&self.credit, 6.5, credit_width)
+            .iter()
+            .take(3)
+            .enumerate()
+        {
+            marks.push(Mark::text(
+                [MARGIN, footer + 36.0 + i as f64 * 8.0],
+                line,
+                6.5,
+            ));
+        }
+        Page {
+            title: self.title.clone(),
+            width,
+            height,
+            marks,
+        }
+    }
+
+    /// Draws the features of `layer` within the extent of the map.
+    fn features(&self, layer: &PrintLayer, marks: &mut Vec<Mark>) {
+        let extent = self.extent();
+        let margin = 16.0 * self.resolution();
+        let inside = |p: &Point2d| {
+            p.x() >= extent.x_min() - margin
+                && p.x() <= extent.x_max() + margin
+                && p.y() >= extent.y_min() - margin
+                && p.y() <= extent.y_max() + margin
+        };
+        let circle = |point: &Point2d, style: &Style| {
+            let opacity = style.opacity * layer.opacity;
+            Mark::Circle {
+                center: self.to_page(point),
+                radius: style.size as f64 * POINTS_PER_PIXEL / 2.0,
+                fill: Some(style.fill.with_opacity(opacity)),
+                stroke: stroke(style, opacity),
+            }
+        };
+        match layer.data {
+            LayerData::Addresses(v) => {
+                for address in v.iter() {
+                    let point =
+                        lonlat_to_mercator(address.geo_point.lon(), address.geo_point.lat());
+                    if inside(&point) {
+                        marks.push(circle(&point, layer.style.style_for(address)));
+                    }
+                }
+            }
+            LayerData::Matches(v) => {
+                for record in v.iter() {
+                    let point = lonlat_to_mercator(record.lon(), record.lat());
+                    if inside(&point) {
+                        marks.push(circle(&point, layer.style.style_for(record)));
+                    }
+                }
+            }
+            LayerData::Parcels(v) => {
+                for parcel in v.iter().filter(|v| {
+                    v.bounds.x_min() <= extent.x_max()
+                        && v.bounds.x_max() >= extent.x_min()
+                        && v.bounds.y_min() <= extent.y_max()
+                        && v.bounds.y_max() >= extent.y_min()
+                }) {
+                    let style = layer.style.style_for(parcel);
+                    let opacity = style.opacity * layer.opacity;
+                    let rings = parcel
+                        .geometry
+                        .parts
+                        .iter()
+                        .flat_map(|v| {
+                            std::iter::once(&v.outer_contour).chain(v.inner_contours.iter())
+                        })
+                        .map(|v| {
+                            v.points
+                                .iter()
+                                .map(|p| self.to_page(p))
+                                .collect::<Vec<[f64; 2]>>()
+                        })
+                        .collect();
+                    marks.push(Mark::Polygon {
+                        rings,
+                        fill: Some(style.fill.with_opacity(opacity)),
+                        stroke: stroke(style, opacity),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Draws the legend beside the map, with an entry for each style of each layer.  Entries
+    /// that do not fit beside the map are left off.
+    fn legend(&self, layers: &[PrintLayer], marks: &mut Vec<Mark>) {
+        let [x, top, width, height] = self.frame();
+        let left = x + width + GAP;
+        let bottom = top + height;
+        let mut y = top + 12.0;
+        marks.push(Mark::text([left, y], "Legend", 12.0).bold());
+        y += 8.0;
+        let swatch = |y: f64, style: &Style, point: bool| match point {
+            true => Mark::Circle {
+                center: [left + 5.0, y - 3.0],
+                radius: (style.size as f64 * POINTS_PER_PIXEL / 2.0).clamp(1.5, 5.0),
+                fill: Some(style.fill.with_opacity(style.opacity)),
+                stroke: stroke(style, style.opacity),
+            },
+            false => Mark::rect(
+                [left, y - 8.0, 10.0, 10.0],
+                Some(style.fill.with_opacity(style.opacity)),
+                stroke(style, style.opacity),
+            ),
+        };
+        for layer in layers {
+            let entries = layer.style.legend();
+            let single = entries.len() == 1;
+            if !single {
+                y += 14.0;
+                if y > bottom {
+                    return;
+                }
+                marks.push(Mark::text([left, y], &layer.name, 9.0).bold());
+            }
+            for (label, mut style) in entries {
+                style.opacity *= layer.opacity;
+                y += 14.0;
+                if y > bottom {
+                    return;
+                }
+                let label = match single {
+                    true => &layer.name,
+                    false => &label,
+                };
+                marks.push(swatch(y, &style, layer.is_point()));
+                marks.push(Mark::text([left + 16.0, y], label, 8.0));
+            }
+        }
+    }
+
+    /// Draws a scale bar in feet or miles with its top left corner at `(x, y)`, about a quarter
+    /// of the width of the map, with the scale written beneath.
+    fn scale_bar(&self, x: f64, y: f64, marks: &mut Vec<Mark>) {
+        let [_, _, width, _] = self.frame();
+        let feet_per_point = self.ground_per_point() / METERS_PER_FOOT;
+        let target = width / 4.0 * feet_per_point;
+        let (length, unit, feet_per_unit) = match target >= FEET_PER_MILE / 2.0 {
+            true => (nice_length(target / FEET_PER_MILE), "mi", FEET_PER_MILE),
+            false => (nice_length(target), "ft", 1.0),
+        };
+        let bar = length * feet_per_unit / feet_per_point;
+        let black = HexColor::rgb(0, 0, 0);
+        let white = HexColor::rgb(255, 255, 255);
+        let top = y + 10.0;
+        for i in 0..4 {
+            let fill = if i % 2 == 0 { black } else { white };
+            marks.push(Mark::rect(
+                [x + bar * i as f64 / 4.0, top, bar / 4.0, 5.0],
+                Some(fill),
+                Some((black, 0.5)),
+            ));
+        }
+        marks.push(Mark::text([x, y + 7.0], "0", 7.0).anchored(Anchor::Middle));
+        marks.push(
+            Mark::text(
+                [x + bar, y + 7.0],
+                &format!("{} {unit}", number(length)),
+                7.0,
+            )
+            .anchored(Anchor::Middle),
+        );
+        let text = format!(
+            "Scale 1:{} (1 in = {} ft)",
+            thousands(self.scale),
+            number(self.scale / 12.0)
+        );
+        marks.push(Mark::text([x, top + 14.0], &text, 7.0));
+    }
+
+    /// Draws a north arrow centered on `x`, with its top at `y`.  North is up in Web Mercator.
+    fn north_arrow(&self, x: f64, y: f64, marks: &mut Vec<Mark>) {
+        let black = HexColor::rgb(0, 0, 0);
+        marks.push(
+            Mark::text([x, y + 8.0], "N", 9.0)
+                .bold()
+                .anchored(Anchor::Middle),
+        );
+        marks.push(Mark::Polygon {
+            rings: vec![vec![
+                [x, y + 11.0],
+                [x + 7.0, y + 32.0],
+                [x, y + 27.0],
+                [x - 7.0, y + 32.0],
+            ]],
+            fill: Some(black),
+            stroke: Some((black, 0.5)),
+        });
+    }
+}
+
+/// Returns the stroke of `style` with its alpha scaled by `opacity`, or `None` if the style has
+/// no stroke.
+fn stroke(style: &Style, opacity: f32) -> Option<(HexColor, f64)> {
+    match style.stroke_width > 0.0 {
+        true => Some((
+            style.stroke.with_opacity(opacity),
+            style.stroke_width as f64 * POINTS_PER_PIXEL,
+        )),
+        false => None,
+    }
+}
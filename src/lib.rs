@@ -1,16 +1,42 @@
+//! The `whimsy` crate reads, edits and displays address and parcel datasets.
+//!
+//! The data, geometry, import/export and parsing modules make up the core and build without any
+//! GUI dependencies.  The `gui` feature adds the window, renderer, dock and table views, and the
+//! `rpg` feature adds the character sheets.  Both are enabled by default.
 pub mod address_components;
 pub mod addresses;
+pub mod catalog;
+#[cfg(feature = "gui")]
 pub mod controls;
 pub mod convert;
+pub mod diff;
+#[cfg(feature = "gui")]
+pub mod dock;
 pub mod identifier;
+pub mod labels;
+pub mod layout;
+pub mod map;
+pub mod mapped;
+pub mod measure;
+pub mod merge;
+#[cfg(feature = "gui")]
 pub mod observer;
 pub mod parcels;
+#[cfg(feature = "rpg")]
 pub mod rpg;
+#[cfg(feature = "gui")]
 pub mod run;
+#[cfg(feature = "gui")]
 pub mod run_ui;
+pub mod search;
+#[cfg(feature = "gui")]
 pub mod state;
+pub mod summary;
+pub mod symbology;
+#[cfg(feature = "gui")]
 pub mod tab;
 pub mod table;
+pub mod tiles;
 pub mod utils;
 
 pub mod prelude {
@@ -19,17 +45,54 @@ pub mod prelude {
         deserialize_mixed_subaddress_type, AddressStatus, StreetNamePostType,
         StreetNamePreDirectional, SubaddressType,
     };
-    pub use crate::addresses::{AddressPoint, AddressPoints};
+    pub use crate::addresses::{AddressColumns, AddressPoint, AddressPoints};
+    pub use crate::catalog::Catalog;
+    #[cfg(feature = "gui")]
     pub use crate::controls::{
         Act, Action, AppAct, Binding, ChoiceMap, Choices, Command, CommandMode, CommandOptions,
         CommandRow, CommandTable, CommandView, EguiAct, Leaf, Modifiers, NamedAct, Node, Tree,
         KEY_BINDINGS, MOUSE_BINDINGS,
     };
     pub use crate::convert::Convert;
+    #[cfg(feature = "gui")]
+    pub use crate::diff::DiffView;
+    pub use crate::diff::{Change, ChangeKind, ChangeLog, ChangeRow, ChangeRows};
+    pub use crate::labels::{declutter, interior_point, largest_ring, LabelBox};
+    pub use crate::layout::{
+        nice_scale, text_width, wrap, Anchor, Layout, Mark, Page, PageSize, PrintLayer, CREDIT,
+    };
+    pub use crate::map::{extent, LayerData, LayerKind, LayerStack, MapCamera, MapLayer, Pick};
+    #[cfg(feature = "gui")]
+    pub use crate::map::{MapEdit, MapTool, MapView, SpatialSelection};
+    pub use crate::mapped::{is_mapped, save_mapped, Mapped, Records};
+    pub use crate::measure::{format_area, format_length, MeasureKind, Measurement};
+    #[cfg(feature = "gui")]
+    pub use crate::merge::MergeView;
+    pub use crate::merge::{Conflict, ConflictKind, Merge, MergeSide};
     pub use crate::parcels::{Parcel, Parcels};
+    #[cfg(feature = "gui")]
     pub use crate::run::App;
+    #[cfg(feature = "gui")]
     pub use crate::run_ui::{Card, Panel, SearchConfig, UiState};
+    pub use crate::search::{score_cells, search_rows, Matcher, SearchMatch, SearchMode};
+    #[cfg(feature = "gui")]
     pub use crate::state::{EguiState, Lens, State, WgpuFrame};
-    pub use crate::table::{Columnar, Filtration, TableConfig, TableView, Tabular};
-    pub use crate::utils::{from_csv, load_bin, point_bounds, save, to_csv};
+    pub use crate::summary::{ColumnStats, Group, Summary};
+    pub use crate::symbology::{
+        Attributed, Category, Class, HexColor, LabelStyle, LayerStyle, Rule, Style, Symbology,
+    };
+    #[cfg(feature = "gui")]
+    pub use crate::table::TableView;
+    pub use crate::table::{
+        apply_edits, distinct_values, insert_rows, remove_rows, sort_by_cell, BulkEdit, BulkScope,
+        Cell, ColumnConfig, ColumnFilter, ColumnFilters, Columnar, Edit, EditError, EditHistory,
+        EditKind, Filtration, SelectMode, SortKey, SortSpec, TableConfig, TableLayouts, Tabular,
+    };
+    #[cfg(feature = "gui")]
+    pub use crate::tiles::Tileset;
+    pub use crate::tiles::{vector_paths, TileFormat, TileId, TilePath, TileSource};
+    pub use crate::utils::{
+        from_csv, load_bin, lonlat_to_mercator, mercator_area, mercator_distance,
+        mercator_to_lonlat, point_bounds, point_in_ring, save, to_csv,
+    };
 }
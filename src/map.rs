@@ -0,0 +1,2386 @@
+//! The `map` module displays address points, parcels and other layers on a map rendered by
+//! `galileo`.
+//!
+//! The [`MapCamera`] holds the center and resolution of the map in EPSG:3857 and converts between
+//! screen and map coordinates, so overlays drawn by `egui` line up with the features drawn by
+//! `galileo`.  The [`MapView`] tab renders the map to a texture before each frame and displays
+//! it within the dock, above the basemap tiles named in the [`Catalog`].  The [`LayerStack`]
+//! holds the order and display settings of the layers, and is saved with the session.
+use crate::addresses::MatchPoint;
+#[cfg(feature = "gui")]
+use crate::addresses::{AddressColumns, AddressSymbol};
+#[cfg(feature = "gui")]
+use crate::labels::{declutter, LabelBox};
+use crate::labels::{interior_point, largest_ring, MAX_LABELS};
+use crate::prelude::{
+    from_csv, lonlat_to_mercator, point_in_ring, AddressPoint, AddressPoints, Attributed, Catalog,
+    Cell, Columnar, Parcel, Parcels,
+};
+#[cfg(feature = "gui")]
+use crate::prelude::{mercator_to_lonlat, EguiAct, TileId, TilePath, TileSource, Tileset};
+#[cfg(feature = "gui")]
+use crate::prelude::{
+    AddressStatus, LayerStyle, Layout, MeasureKind, Measurement, PageSize, PrintLayer, SelectMode,
+    Symbology,
+};
+#[cfg(feature = "gui")]
+use crate::symbology::{swatch, SYMBOLOGY_PATH};
+use crate::tiles::TILE_SIZE;
+use crate::utils::EARTH_RADIUS;
+use address::prelude::MatchRecord;
+use derive_more::{Deref, DerefMut};
+#[cfg(feature = "gui")]
+use galileo::layer::feature_layer::{symbol::Symbol, Feature};
+#[cfg(feature = "gui")]
+use galileo::render::render_bundle::RenderPrimitive;
+#[cfg(feature = "gui")]
+use galileo_types::cartesian::CartesianPoint3d;
+use galileo_types::cartesian::{CartesianPoint2d, Point2d, Rect};
+use galileo_types::geometry::CartesianGeometry2d;
+#[cfg(feature = "gui")]
+use galileo_types::geometry::Geom;
+#[cfg(feature = "gui")]
+use galileo_types::impls::{Contour, Polygon};
+#[cfg(feature = "gui")]
+use num_traits::AsPrimitive;
+use polite::Polite;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "gui")]
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+#[cfg(feature = "gui")]
+use std::sync::Mutex;
+#[cfg(feature = "gui")]
+use strum::IntoEnumIterator;
+use uuid::Uuid;
+
+/// Least resolution of the camera in meters per screen point.
+pub const MIN_RESOLUTION: f64 = 0.01;
+/// Greatest resolution of the camera in meters per screen point, enough to show the whole world.
+pub const MAX_RESOLUTION: f64 = 160_000.0;
+/// Fraction of the viewport moved by each pan act.
+pub const PAN_STEP: f64 = 0.25;
+/// Factor by which each zoom act changes the resolution.
+pub const ZOOM_STEP: f64 = 2.0;
+/// Greatest zoom level of a layer, about that of [`MIN_RESOLUTION`].
+pub const MAX_ZOOM: f64 = 24.0;
+/// Greatest number of decoded basemap tiles kept between frames.
+pub const TILE_CACHE: usize = 512;
+/// Distance in screen points within which a click identifies an address point.
+pub const PICK_RADIUS: f64 = 8.0;
+/// Default path of printed maps, before the extension.
+pub const LAYOUT_PATH: &str = "data/map";
+
+/// The `MapCamera` struct locates the viewport of a map.  Screen coordinates are in points from
+/// the top left corner of the viewport, and map coordinates are in EPSG:3857.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MapCamera {
+    /// Map coordinates at the center of the viewport.
+    pub center: Point2d,
+    /// Meters per screen point.
+    pub resolution: f64,
+    /// Width of the viewport in points.
+    pub width: f64,
+    /// Height of the viewport in points.
+    pub height: f64,
+}
+
+impl Default for MapCamera {
+    fn default() -> Self {
+        Self {
+            center: Point2d::new(0.0, 0.0),
+            resolution: 1.0,
+            width: 0.0,
+            height: 0.0,
+        }
+    }
+}
+
+impl MapCamera {
+    pub fn new(center: Point2d, resolution: f64) -> Self {
+        Self {
+            center,
+            resolution: resolution.clamp(MIN_RESOLUTION, MAX_RESOLUTION),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the size of the viewport in points.
+    pub fn with_size(mut self, width: f64, height: f64) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Returns the map coordinates under the screen position `(x, y)`.
+    pub fn to_map(&self, x: f64, y: f64) -> Point2d {
+        Point2d::new(
+            self.center.x() + (x - self.width / 2.0) * self.resolution,
+            self.center.y() - (y - self.height / 2.0) * self.resolution,
+        )
+    }
+
+    /// Returns the screen position of the map coordinates `point`.
+    pub fn to_screen(&self, point: &Point2d) -> (f64, f64) {
+        (
+            (point.x() - self.center.x()) / self.resolution + self.width / 2.0,
+            (self.center.y() - point.y()) / self.resolution + self.height / 2.0,
+        )
+    }
+
+    /// Returns the zoom level of the camera, on the scale of web map tiles where each level
+    /// halves the resolution.
+    pub fn zoom_level(&self) -> f64 {
+        (2.0 * std::f64::consts::PI * EARTH_RADIUS / (TILE_SIZE * self.resolution)).log2()
+    }
+
+    /// Returns the extent of the map within the viewport.
+    pub fn bounds(&self) -> Rect {
+        let min = self.to_map(0.0, self.height);
+        let max = self.to_map(self.width, 0.0);
+        Rect::new(min.x(), min.y(), max.x(), max.y())
+    }
+
+    /// Moves the map by `dx` and `dy` screen points, as when dragging it with the mouse.
+    pub fn pan(&mut self, dx: f64, dy: f64) {
+        self.center = Point2d::new(
+            self.center.x() - dx * self.resolution,
+            self.center.y() + dy * self.resolution,
+        );
+    }
+
+    /// Zooms in by `factor`, or out if `factor` is less than one, keeping the map coordinates
+    /// under the screen position `(x, y)` in place.
+    pub fn zoom(&mut self, factor: f64, x: f64, y: f64) {
+        if factor <= 0.0 {
+            return;
+        }
+        let anchor = self.to_map(x, y);
+        self.resolution = (self.resolution / factor).clamp(MIN_RESOLUTION, MAX_RESOLUTION);
+        self.center = Point2d::new(
+            anchor.x() - (x - self.width / 2.0) * self.resolution,
+            anchor.y() + (y - self.height / 2.0) * self.resolution,
+        );
+    }
+
+    /// Centers the viewport on `bounds` at the resolution that shows all of it.
+    pub fn fit(&mut self, bounds: &Rect) {
+        self.center = Point2d::new(
+            (bounds.x_min() + bounds.x_max()) / 2.0,
+            (bounds.y_min() + bounds.y_max()) / 2.0,
+        );
+        if self.width > 0.0 && self.height > 0.0 {
+            let resolution = ((bounds.x_max() - bounds.x_min()) / self.width)
+                .max((bounds.y_max() - bounds.y_min()) / self.height)
+                * 1.1;
+            self.resolution = resolution.clamp(MIN_RESOLUTION, MAX_RESOLUTION);
+        }
+    }
+}
+
+/// The `extent` function returns the bounds of `points`, or `None` if there are none.
+pub fn extent<I: IntoIterator<Item = Point2d>>(points: I) -> Option<Rect> {
+    let mut points = points.into_iter();
+    let first = points.next()?;
+    let (mut x_min, mut y_min, mut x_max, mut y_max) = (first.x(), first.y(), first.x(), first.y());
+    for point in points {
+        x_min = x_min.min(point.x());
+        y_min = y_min.min(point.y());
+        x_max = x_max.max(point.x());
+        y_max = y_max.max(point.y());
+    }
+    Some(Rect::new(x_min, y_min, x_max, y_max))
+}
+
+/// Returns the corners of each rectangle in `bounds`, for passing to [`extent`].
+fn corners<'a, I: IntoIterator<Item = &'a Rect>>(bounds: I) -> impl Iterator<Item = Point2d> {
+    bounds
+        .into_iter()
+        .flat_map(|v| {
+            [
+                Point2d::new(v.x_min(), v.y_min()),
+                Point2d::new(v.x_max(), v.y_max()),
+            ]
+        })
+        .collect::<Vec<Point2d>>()
+        .into_iter()
+}
+
+/// The `LayerKind` enum is the type of data drawn by a [`MapLayer`].
+#[derive(
+    Debug,
+    Default,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    Deserialize,
+    Serialize,
+    strum_macros::Display,
+    strum_macros::EnumIter,
+)]
+pub enum LayerKind {
+    /// An address snapshot.
+    #[default]
+    Addresses,
+    /// A parcel snapshot.
+    Parcels,
+    /// A CSV file of match records.
+    #[strum(serialize = "Match points")]
+    Matches,
+    /// A GeoJSON file of parcel polygons.
+    Imported,
+}
+
+impl LayerKind {
+    /// Names of the columns of the features, for symbology rules.
+    pub fn columns(&self) -> Vec<String> {
+        match self {
+            Self::Addresses => <AddressPoint as Attributed>::columns(),
+            Self::Parcels | Self::Imported => <Parcel as Attributed>::columns(),
+            Self::Matches => <MatchPoint as Attributed>::columns(),
+        }
+    }
+
+    /// Returns `true` for layers of points, which draw as circles.
+    pub fn is_point(&self) -> bool {
+        matches!(self, Self::Addresses | Self::Matches)
+    }
+}
+
+/// The `MapLayer` struct holds the display settings of one layer of a map.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct MapLayer {
+    pub name: String,
+    pub kind: LayerKind,
+    /// Path to the data of the layer.
+    pub path: String,
+    pub visible: bool,
+    /// Opacity from 0.0 to 1.0.
+    pub opacity: f32,
+    /// Least zoom level at which the layer is drawn.
+    pub min_zoom: f64,
+    /// Greatest zoom level at which the layer is drawn.
+    pub max_zoom: f64,
+}
+
+impl MapLayer {
+    pub fn new(name: &str, kind: LayerKind, path: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            kind,
+            path: path.to_string(),
+            visible: true,
+            opacity: 1.0,
+            min_zoom: 0.0,
+            max_zoom: MAX_ZOOM,
+        }
+    }
+
+    /// Returns `true` if the layer is drawn at `zoom`.
+    pub fn shown_at(&self, zoom: f64) -> bool {
+        self.visible && self.opacity > 0.0 && zoom >= self.min_zoom && zoom <= self.max_zoom
+    }
+
+    /// Key of the layer data in the cache of a map view.
+    pub fn source(&self) -> (LayerKind, String) {
+        (self.kind, self.path.clone())
+    }
+}
+
+/// The `LayerStack` struct holds the layers of a map, topmost first.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize, Deref, DerefMut)]
+pub struct LayerStack(Vec<MapLayer>);
+
+impl LayerStack {
+    /// Returns the address layer above the parcel layer, using the paths in `catalog`.
+    pub fn from_catalog(catalog: &Catalog) -> Self {
+        Self(vec![
+            MapLayer::new("Addresses", LayerKind::Addresses, &catalog.addresses),
+            MapLayer::new("Parcels", LayerKind::Parcels, &catalog.parcels),
+        ])
+    }
+
+    /// Moves the layer at `index` to the position of the layer at `target`.
+    pub fn move_layer(&mut self, index: usize, target: usize) {
+        if index < self.len() && target < self.len() {
+            let layer = self.remove(index);
+            self.insert(target, layer);
+        }
+    }
+
+    /// Returns the layers drawn at `zoom`, bottom first in drawing order.
+    pub fn shown(&self, zoom: f64) -> Vec<&MapLayer> {
+        self.iter().rev().filter(|v| v.shown_at(zoom)).collect()
+    }
+}
+
+/// The `Pick` struct describes a feature identified by clicking the map.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pick {
+    /// Name of the layer holding the feature.
+    pub layer: String,
+    /// Id of an address point, matching its row in the address table.
+    pub id: Option<Uuid>,
+    /// Attribute names and values of the feature.
+    pub attributes: Vec<(String, String)>,
+    /// Location of a point, or the center of the bounds of a parcel, in EPSG:3857.
+    pub location: Point2d,
+    /// Outer rings of a parcel in EPSG:3857, empty for points.
+    pub rings: Vec<Vec<Point2d>>,
+}
+
+impl Pick {
+    fn address(address: &AddressPoint) -> Self {
+        Self {
+            layer: String::new(),
+            id: Some(address.id),
+            attributes: AddressPoint::names()
+                .into_iter()
+                .zip(address.values())
+                .collect(),
+            location: mercator(address),
+            rings: Vec::new(),
+        }
+    }
+
+    fn parcel(parcel: &Parcel) -> Self {
+        let bounds = &parcel.bounds;
+        Self {
+            layer: String::new(),
+            id: None,
+            attributes: vec![
+                (
+                    "Owner".to_string(),
+                    parcel.owner.name.clone().unwrap_or_default(),
+                ),
+                ("Map number".to_string(), parcel.owner.id.clone()),
+            ],
+            location: Point2d::new(
+                (bounds.x_min() + bounds.x_max()) / 2.0,
+                (bounds.y_min() + bounds.y_max()) / 2.0,
+            ),
+            rings: parcel
+                .geometry
+                .parts
+                .iter()
+                .map(|v| v.outer_contour.points.clone())
+                .collect(),
+        }
+    }
+}
+
+/// Location of `address` in EPSG:3857, as stored with the record.
+fn mercator(address: &AddressPoint) -> Point2d {
+    address.point
+}
+
+/// The `LayerData` enum holds the features read for a [`MapLayer`].  The features sit behind an
+/// [`Arc`], so map tabs sharing a layer and the `galileo` layers drawing it hold one copy.
+#[derive(Debug, Clone)]
+pub enum LayerData {
+    Addresses(Arc<Vec<AddressPoint>>),
+    /// Parcels, from a snapshot or imported from GeoJSON.
+    Parcels(Arc<Vec<Parcel>>),
+    Matches(Arc<Vec<MatchPoint>>),
+}
+
+impl LayerData {
+    /// Reads the data of `layer`.
+    pub fn load(layer: &MapLayer) -> Polite<Self> {
+        let data = match layer.kind {
+            LayerKind::Addresses => {
+                Self::Addresses(AddressPoints::load(&layer.path)?.records.into_vec().into())
+            }
+            LayerKind::Parcels => {
+                Self::Parcels(Parcels::load(&layer.path)?.records.into_vec().into())
+            }
+            LayerKind::Imported => Self::Parcels(
+                Parcels::from_geojson(&layer.path)?
+                    .records
+                    .into_vec()
+                    .into(),
+            ),
+            LayerKind::Matches => {
+                let records = from_csv::<MatchRecord, _>(&layer.path)?;
+                Self::Matches(Arc::new(records.iter().map(MatchPoint::from).collect()))
+            }
+        };
+        Ok(data)
+    }
+
+    /// Number of features in the layer.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Addresses(v) => v.len(),
+            Self::Parcels(v) => v.len(),
+            Self::Matches(v) => v.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the bounds of the features in EPSG:3857, from the parcel bounds or the extent of
+    /// the points.
+    pub fn extent(&self) -> Option<Rect> {
+        use galileo_types::geo::GeoPoint;
+        match self {
+            Self::Addresses(v) => extent(v.iter().map(mercator)),
+            Self::Parcels(v) => extent(corners(v.iter().map(|v| &v.bounds))),
+            Self::Matches(v) => extent(v.iter().map(|v| lonlat_to_mercator(v.lon(), v.lat()))),
+        }
+    }
+
+    /// Returns the feature at `point` in EPSG:3857: the nearest address point within
+    /// `tolerance` meters, or the parcel containing the point.  Match points are not picked,
+    /// since they carry no attributes to show.
+    pub fn pick(&self, point: &Point2d, tolerance: f64) -> Option<Pick> {
+        match self {
+            Self::Addresses(v) => v
+                .iter()
+                .map(|v| (v, mercator(v)))
+                .map(|(v, p)| (v, (p.x() - point.x()).hypot(p.y() - point.y())))
+                .filter(|(_, distance)| *distance <= tolerance)
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(v, _)| Pick::address(v)),
+            Self::Parcels(v) => v
+                .iter()
+                .rev()
+                .find(|v| v.is_point_inside(point, tolerance))
+                .map(Pick::parcel),
+            Self::Matches(_) => None,
+        }
+    }
+
+    /// Returns the value of `column` for each feature.
+    pub fn values(&self, column: &str) -> Vec<Cell> {
+        match self {
+            Self::Addresses(v) => v.iter().map(|v| v.attribute(column)).collect(),
+            Self::Parcels(v) => v.iter().map(|v| v.attribute(column)).collect(),
+            Self::Matches(v) => v.iter().map(|v| v.attribute(column)).collect(),
+        }
+    }
+
+    /// Returns the text of `column` for each feature labeled within `bounds`, with the location
+    /// of its label in EPSG:3857: the point itself, or an interior point of the largest part of
+    /// a parcel.  Features with an empty value are not labeled.  Returns at most [`MAX_LABELS`].
+    pub fn labels(&self, column: &str, bounds: &Rect) -> Vec<(Point2d, String)> {
+        use galileo_types::geo::GeoPoint;
+        let inside = |p: &Point2d| {
+            p.x() >= bounds.x_min()
+                && p.x() <= bounds.x_max()
+                && p.y() >= bounds.y_min()
+                && p.y() <= bounds.y_max()
+        };
+        let label = |p: Point2d, cell: Cell| match cell.is_empty() || !inside(&p) {
+            true => None,
+            false => Some((p, cell.to_string())),
+        };
+        match self {
+            Self::Addresses(v) => v
+                .iter()
+                .filter_map(|v| label(mercator(v), v.attribute(column)))
+                .take(MAX_LABELS)
+                .collect(),
+            Self::Matches(v) => v
+                .iter()
+                .filter_map(|v| {
+                    let p = lonlat_to_mercator(v.lon(), v.lat());
+                    label(p, v.attribute(column))
+                })
+                .take(MAX_LABELS)
+                .collect(),
+            Self::Parcels(v) => v
+                .iter()
+                .filter(|v| {
+                    v.bounds.x_min() <= bounds.x_max()
+                        && v.bounds.x_max() >= bounds.x_min()
+                        && v.bounds.y_min() <= bounds.y_max()
+                        && v.bounds.y_max() >= bounds.y_min()
+                })
+                .filter_map(|v| {
+                    let rings = v
+                        .geometry
+                        .parts
+                        .iter()
+                        .map(|v| v.outer_contour.points.clone())
+                        .collect::<Vec<Vec<Point2d>>>();
+                    let p = interior_point(largest_ring(&rings)?)?;
+                    label(p, v.attribute(column))
+                })
+                .take(MAX_LABELS)
+                .collect(),
+        }
+    }
+
+    /// Returns the locations in EPSG:3857 of the address points with an id in `ids`.
+    pub fn locate(&self, ids: &HashSet<Uuid>) -> Vec<Point2d> {
+        match self {
+            Self::Addresses(v) => v
+                .iter()
+                .filter(|v| ids.contains(&v.id))
+                .map(mercator)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the ids of the address points inside any of the polygons `shapes` in EPSG:3857.
+    pub fn within(&self, shapes: &[Vec<Point2d>]) -> Vec<Uuid> {
+        let bounds = shapes
+            .iter()
+            .filter_map(|v| extent(v.iter().copied()).map(|bounds| (bounds, v)))
+            .collect::<Vec<(Rect, &Vec<Point2d>)>>();
+        let inside = |p: &Point2d| {
+            bounds.iter().any(|(bounds, shape)| {
+                p.x() >= bounds.x_min()
+                    && p.x() <= bounds.x_max()
+                    && p.y() >= bounds.y_min()
+                    && p.y() <= bounds.y_max()
+                    && point_in_ring(p, shape)
+            })
+        };
+        match self {
+            Self::Addresses(v) => v
+                .iter()
+                .filter(|v| inside(&mercator(v)))
+                .map(|v| v.id)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the outer rings of each parcel with its label point, an interior point of its
+    /// largest part, inside the polygon `shape` in EPSG:3857.  A parcel straddling the edge of
+    /// the shape is selected if its label point lies within, however much of its area does.
+    pub fn parcels_within(&self, shape: &[Point2d]) -> Vec<Vec<Vec<Point2d>>> {
+        let Some(bounds) = extent(shape.iter().copied()) else {
+            return Vec::new();
+        };
+        match self {
+            Self::Parcels(v) => v
+                .iter()
+                .filter(|v| {
+                    v.bounds.x_min() <= bounds.x_max()
+                        && v.bounds.x_max() >= bounds.x_min()
+                        && v.bounds.y_min() <= bounds.y_max()
+                        && v.bounds.y_max() >= bounds.y_min()
+                })
+                .map(|v| {
+                    v.geometry
+                        .parts
+                        .iter()
+                        .map(|v| v.outer_contour.points.clone())
+                        .collect::<Vec<Vec<Point2d>>>()
+                })
+                .filter(|rings| {
+                    largest_ring(rings)
+                        .and_then(|v| interior_point(v))
+                        .is_some_and(|p| point_in_ring(&p, shape))
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the parcel vertex nearest to `point` in EPSG:3857, if one lies within
+    /// `tolerance` meters.  Point layers have no vertices to snap to.
+    pub fn snap(&self, point: &Point2d, tolerance: f64) -> Option<Point2d> {
+        match self {
+            Self::Parcels(v) => v
+                .iter()
+                .filter(|v| {
+                    v.bounds.x_min() - tolerance <= point.x()
+                        && v.bounds.x_max() + tolerance >= point.x()
+                        && v.bounds.y_min() - tolerance <= point.y()
+                        && v.bounds.y_max() + tolerance >= point.y()
+                })
+                .flat_map(|v| v.geometry.parts.iter())
+                .flat_map(|v| v.outer_contour.points.iter())
+                .map(|p| (p, (p.x() - point.x()).hypot(p.y() - point.y())))
+                .filter(|(_, distance)| *distance <= tolerance)
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(p, _)| *p),
+            _ => None,
+        }
+    }
+
+    /// Returns an interior point of the largest part of the parcel containing `point` in
+    /// EPSG:3857, where its label is drawn.  Point layers have no interiors.
+    pub fn interior(&self, point: &Point2d) -> Option<Point2d> {
+        match self {
+            Self::Parcels(v) => {
+                let parcel = v.iter().rev().find(|v| v.is_point_inside(point, 0.0))?;
+                let rings = parcel
+                    .geometry
+                    .parts
+                    .iter()
+                    .map(|v| v.outer_contour.points.clone())
+                    .collect::<Vec<Vec<Point2d>>>();
+                interior_point(largest_ring(&rings)?)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns a `galileo` layer drawing the features in `style` with `opacity`.  The layer
+    /// holds handles to the features rather than a copy of them.
+    #[cfg(feature = "gui")]
+    pub fn layer(&self, style: &LayerStyle, opacity: f32) -> Box<dyn galileo::layer::Layer> {
+        use crate::addresses::MatchSymbol;
+        use crate::parcels::ParcelSymbol;
+        use galileo::layer::FeatureLayer;
+        use galileo_types::geo::Crs;
+
+        let style = style.clone();
+        match self {
+            Self::Addresses(v) => Box::new(FeatureLayer::new(
+                SharedFeature::all(v),
+                SharedSymbol(AddressSymbol { style, opacity }),
+                Crs::WGS84,
+            )),
+            Self::Matches(v) => Box::new(FeatureLayer::new(
+                SharedFeature::all(v),
+                SharedSymbol(MatchSymbol { style, opacity }),
+                Crs::WGS84,
+            )),
+            Self::Parcels(v) => Box::new(FeatureLayer::new(
+                SharedFeature::all(v),
+                SharedSymbol(ParcelSymbol { style, opacity }),
+                Crs::EPSG3857,
+            )),
+        }
+    }
+}
+
+/// The `SharedFeature` struct is a handle to one feature of a [`LayerData`], given to a
+/// `galileo` layer in place of a copy of the feature.
+#[cfg(feature = "gui")]
+#[derive(Debug, Clone)]
+pub struct SharedFeature<T> {
+    records: Arc<Vec<T>>,
+    index: usize,
+}
+
+#[cfg(feature = "gui")]
+impl<T> SharedFeature<T> {
+    /// Returns a handle to each feature in `records`.
+    pub fn all(records: &Arc<Vec<T>>) -> Vec<Self> {
+        (0..records.len())
+            .map(|index| Self {
+                records: records.clone(),
+                index,
+            })
+            .collect()
+    }
+
+    /// Returns the feature behind the handle.
+    pub fn record(&self) -> &T {
+        &self.records[self.index]
+    }
+}
+
+#[cfg(feature = "gui")]
+impl<T: Feature> Feature for SharedFeature<T> {
+    type Geom = T::Geom;
+
+    fn geometry(&self) -> &Self::Geom {
+        self.record().geometry()
+    }
+}
+
+/// The `SharedSymbol` struct draws each [`SharedFeature`] with the symbol of the feature behind
+/// it.
+#[cfg(feature = "gui")]
+pub struct SharedSymbol<S>(pub S);
+
+#[cfg(feature = "gui")]
+impl<T, S: Symbol<T>> Symbol<SharedFeature<T>> for SharedSymbol<S> {
+    fn render<'a, N, P>(
+        &self,
+        feature: &SharedFeature<T>,
+        geometry: &'a Geom<P>,
+        min_resolution: f64,
+    ) -> Vec<RenderPrimitive<'a, N, P, Contour<P>, Polygon<P>>>
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N> + Clone,
+    {
+        self.0.render(feature.record(), geometry, min_resolution)
+    }
+}
+
+/// The `Repaint` struct asks `egui` for a new frame when a `galileo` layer needs to redraw.
+#[cfg(feature = "gui")]
+struct Repaint(egui::Context);
+
+#[cfg(feature = "gui")]
+impl galileo::messenger::Messenger for Repaint {
+    fn request_redraw(&self) {
+        self.0.request_repaint();
+    }
+}
+
+/// The `Basemap` struct draws the tiles of a [`Tileset`] beneath the map, decoding each tile the
+/// first time it comes into view.
+#[cfg(feature = "gui")]
+pub struct Basemap {
+    tileset: Tileset,
+    raster: HashMap<TileId, Option<egui::TextureHandle>>,
+    vector: HashMap<TileId, Option<Vec<TilePath>>>,
+}
+
+#[cfg(feature = "gui")]
+impl Basemap {
+    pub fn new(tileset: Tileset) -> Self {
+        Self {
+            tileset,
+            raster: HashMap::new(),
+            vector: HashMap::new(),
+        }
+    }
+
+    fn raster(tileset: &Tileset, ctx: &egui::Context, id: &TileId) -> Option<egui::TextureHandle> {
+        let data = match tileset.tile(id) {
+            Ok(data) => data?,
+            Err(e) => {
+                tracing::warn!("Could not read tile {:?}: {}", id, e.to_string());
+                return None;
+            }
+        };
+        let image = match image::load_from_memory(&data) {
+            Ok(image) => image.to_rgba8(),
+            Err(e) => {
+                tracing::warn!("Could not decode tile {:?}: {}", id, e.to_string());
+                return None;
+            }
+        };
+        let size = [image.width() as usize, image.height() as usize];
+        let image = egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw());
+        Some(ctx.load_texture(
+            format!("tile {}/{}/{}", id.z, id.x, id.y),
+            image,
+            egui::TextureOptions::LINEAR,
+        ))
+    }
+
+    fn vector(tileset: &Tileset, id: &TileId) -> Option<Vec<TilePath>> {
+        let data = match tileset.tile(id) {
+            Ok(data) => data?,
+            Err(e) => {
+                tracing::warn!("Could not read tile {:?}: {}", id, e.to_string());
+                return None;
+            }
+        };
+        match crate::tiles::vector_paths(&data) {
+            Ok(paths) => Some(paths),
+            Err(e) => {
+                tracing::warn!("Could not decode tile {:?}: {}", id, e.to_string());
+                None
+            }
+        }
+    }
+
+    /// Draws the tiles covering the viewport of `camera` within `rect`, at the zoom level
+    /// matching the pixel density of the screen.
+    pub fn paint(&mut self, ui: &egui::Ui, rect: egui::Rect, camera: &MapCamera, scale: f32) {
+        if self.raster.len() + self.vector.len() > TILE_CACHE {
+            self.raster.clear();
+            self.vector.clear();
+        }
+        let z = TileId::zoom_for(
+            camera.resolution / scale as f64,
+            self.tileset.min_zoom,
+            self.tileset.max_zoom,
+        );
+        let painter = ui.painter_at(rect);
+        let stroke = egui::Stroke::new(1.0, ui.visuals().weak_text_color());
+        let tileset = &self.tileset;
+        for id in TileId::covering(&camera.bounds(), z) {
+            let bounds = id.bounds();
+            let (left, top) = camera.to_screen(&Point2d::new(bounds.x_min(), bounds.y_max()));
+            let (right, bottom) = camera.to_screen(&Point2d::new(bounds.x_max(), bounds.y_min()));
+            let tile = egui::Rect::from_min_max(
+                rect.min + egui::vec2(left as f32, top as f32),
+                rect.min + egui::vec2(right as f32, bottom as f32),
+            );
+            if tileset.format.is_vector() {
+                let paths = self
+                    .vector
+                    .entry(id)
+                    .or_insert_with(|| Self::vector(tileset, &id));
+                for path in paths.iter().flatten() {
+                    let points = path
+                        .points
+                        .iter()
+                        .map(|(x, y)| tile.min + egui::vec2(x * tile.width(), y * tile.height()))
+                        .collect::<Vec<egui::Pos2>>();
+                    match path.closed {
+                        true => painter.add(egui::Shape::closed_line(points, stroke)),
+                        false => painter.add(egui::Shape::line(points, stroke)),
+                    };
+                }
+            } else {
+                let texture = self
+                    .raster
+                    .entry(id)
+                    .or_insert_with(|| Self::raster(tileset, ui.ctx(), &id));
+                if let Some(texture) = texture {
+                    let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+                    painter.image(texture.id(), tile, uv, egui::Color32::WHITE);
+                }
+            }
+        }
+    }
+}
+
+/// The `Canvas` struct holds the `galileo` map, the data of its layers and the texture it renders
+/// to.
+#[cfg(feature = "gui")]
+#[derive(Default)]
+pub struct Canvas {
+    map: Option<galileo::Map>,
+    repaint: Option<egui::Context>,
+    data: HashMap<(LayerKind, String), LayerData>,
+    // Layers that could not be read, so they are not read again every frame.
+    failed: HashSet<(LayerKind, String)>,
+    // Layers whose data was given by the application rather than read from their file, kept
+    // when the map reloads.
+    shared: HashSet<(LayerKind, String)>,
+    // Layers of the current map, bottom first, as the key of their data, their opacity and
+    // their style.
+    built: Vec<((LayerKind, String), f32, LayerStyle)>,
+    basemap: Option<Basemap>,
+    renderer: Option<galileo::render::WgpuRenderer>,
+    texture: Option<egui::TextureId>,
+    // Camera and pixel density of the last render.
+    rendered: Option<(MapCamera, f32)>,
+}
+
+#[cfg(feature = "gui")]
+impl Canvas {
+    /// Rebuilds the map if the layers drawn at the zoom of `camera` or their styles in
+    /// `symbology` have changed.
+    fn build(&mut self, layers: &LayerStack, camera: &MapCamera, symbology: &Symbology) {
+        let shown = layers
+            .shown(camera.zoom_level())
+            .into_iter()
+            .filter(|v| self.data.contains_key(&v.source()))
+            .map(|v| (v.source(), v.opacity, symbology.style(v.kind).clone()))
+            .collect::<Vec<((LayerKind, String), f32, LayerStyle)>>();
+        if self.map.is_some() && shown == self.built {
+            return;
+        }
+        let layers = shown
+            .iter()
+            .map(|(source, opacity, style)| self.data[source].layer(style, *opacity))
+            .collect::<Vec<Box<dyn galileo::layer::Layer>>>();
+        let view = galileo::MapView::new_projected(&camera.center, camera.resolution);
+        self.map = Some(galileo::Map::new(
+            view,
+            layers,
+            self.repaint.clone().map(Repaint),
+        ));
+        self.built = shown;
+        self.rendered = None;
+    }
+}
+
+/// The `MapCanvas` struct shares the [`Canvas`] of a [`MapView`] between the tab, which draws
+/// the texture, and the render loop, which updates it.
+#[cfg(feature = "gui")]
+#[derive(Clone, Default)]
+pub struct MapCanvas(Arc<Mutex<Canvas>>);
+
+#[cfg(feature = "gui")]
+impl std::fmt::Debug for MapCanvas {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MapCanvas").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "gui")]
+impl PartialEq for MapCanvas {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// Payload of a layer dragged within the layer panel, holding its index in the [`LayerStack`].
+#[cfg(feature = "gui")]
+struct LayerDrag(usize);
+
+/// The `MapTool` enum is the action taken by clicking or dragging on a [`MapView`].
+#[cfg(feature = "gui")]
+#[derive(
+    Debug,
+    Default,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    Deserialize,
+    Serialize,
+    strum_macros::Display,
+    strum_macros::EnumIter,
+)]
+pub enum MapTool {
+    /// Click a feature to identify it.
+    #[default]
+    Identify,
+    /// Click out a line to measure its length.
+    Distance,
+    /// Click out a polygon to measure its area.
+    Area,
+    /// Drag a rectangle to select the features inside it.
+    Rectangle,
+    /// Drag a freehand shape to select the features inside it.
+    Lasso,
+    /// Click to place a new address point.
+    Add,
+    /// Drag an address point to move it.
+    Move,
+    /// Click an address point to delete it.
+    Delete,
+}
+
+#[cfg(feature = "gui")]
+impl MapTool {
+    /// Returns the quantity measured by the tool, if it measures.
+    pub fn measure(&self) -> Option<MeasureKind> {
+        match self {
+            Self::Distance => Some(MeasureKind::Distance),
+            Self::Area => Some(MeasureKind::Area),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if dragging with the tool draws a selection rather than panning.
+    pub fn selects(&self) -> bool {
+        matches!(self, Self::Rectangle | Self::Lasso)
+    }
+
+    /// Returns `true` if the tool changes the address points.
+    pub fn edits(&self) -> bool {
+        matches!(self, Self::Add | Self::Move | Self::Delete)
+    }
+}
+
+/// The `SpatialSelection` struct holds the address points selected by a shape drawn on a map,
+/// for the address table to select or check.
+#[cfg(feature = "gui")]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SpatialSelection {
+    pub ids: HashSet<Uuid>,
+    pub mode: SelectMode,
+    /// Checks the boxes of the rows instead of selecting them.
+    pub check: bool,
+}
+
+/// The `MapEdit` enum is a change to the address points made with the editing tools of a map.
+/// The address table applies the change, so it passes through the same validation and undo
+/// history as edits made in the table.
+#[cfg(feature = "gui")]
+#[derive(Debug, Clone, PartialEq)]
+pub enum MapEdit {
+    /// A new address point.
+    Add(AddressPoint),
+    /// An address point moved to `location`, written as latitude and longitude like the
+    /// location column of the address table.
+    Move { id: Uuid, location: String },
+    /// An address point to delete.
+    Delete(Uuid),
+}
+
+/// The `MapView` struct is the map tab.  Address points and parcels are drawn in the styles of
+/// the [`Symbology`], edited in the style panel, along with any match points or imported polygons
+/// added in the layer panel.  Drag to pan, scroll to zoom, or use the navigation acts.  Click a feature to
+/// identify it and select its row in the address table, or pick a measuring tool to click out a
+/// distance or area, double-clicking the last point.  The rectangle and lasso tools select the
+/// address points, or the parcels and the address points within them, inside a shape dragged on
+/// the map, holding shift to add to the selection or command to remove from it.  The editing
+/// tools add, move and delete the address points of the address table, optionally snapping
+/// them to the interior of the parcel beneath.
+#[cfg(feature = "gui")]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MapView {
+    /// Layers of the map, topmost first.
+    pub layers: LayerStack,
+    /// Source of basemap tiles.  Without one, the map draws on a blank background.
+    pub basemap: Option<TileSource>,
+    /// Styles of the features of each kind of layer.
+    pub symbology: Symbology,
+    /// Viewport of the map.
+    pub camera: MapCamera,
+    /// Status line reporting the result of the last operation.
+    pub status: String,
+    /// Unique identifier assigned to the hosting tab.
+    pub identifier: Option<String>,
+    /// Set once the layers have been read.
+    pub loaded: bool,
+    /// Shows the layer panel.
+    pub layer_panel: bool,
+    /// Shows the style panel.
+    pub style_panel: bool,
+    /// Shows the print layout panel, and the extent of the layout on the map.
+    pub layout_panel: bool,
+    /// Settings of the printed map.
+    pub layout: Layout,
+    /// Path of the printed map, written as SVG or PDF.
+    pub layout_path: String,
+    /// Shows the legend.
+    pub legend: bool,
+    /// Shows the labels set in the symbology.
+    pub labels: bool,
+    /// Moves the camera to the features selected in the address table.
+    pub follow: bool,
+    /// Feature identified by the last click on the map.
+    pub identified: Option<Pick>,
+    /// Tool used by clicks and drags on the map.
+    pub tool: MapTool,
+    /// Snaps measured points to nearby parcel vertices.
+    pub snap: bool,
+    /// Distance or area being measured, kept after it is finished until cleared.
+    pub measurement: Option<Measurement>,
+    /// Kind of feature selected by the rectangle and lasso tools, addresses or parcels.
+    pub select_kind: LayerKind,
+    /// Checks the boxes of the rows selected on the map instead of selecting them.
+    pub check: bool,
+    /// Path of the address layer changed by the editing tools, read by the address table.
+    pub edit_path: String,
+    /// Places new and moved address points at the interior point of the parcel beneath them.
+    pub snap_parcels: bool,
+    /// Enables the editing tools, set while an address table is open to apply the edits.
+    pub editable: bool,
+    // Location of a new address point and the text of each column in its form.
+    draft: Option<(Point2d, Vec<String>)>,
+    // Id of the address point dragged by the move tool, with its location before and after.
+    dragging: Option<(Uuid, Point2d, Point2d)>,
+    // Edits made since the last call to `take_edits`.
+    edits: Vec<MapEdit>,
+    // Revision of the address table last copied to the edited layer.
+    synced: Option<u64>,
+    // Shape dragged by a selection tool, in EPSG:3857.
+    sketch: Vec<Point2d>,
+    // Selections made since the last call to `take_selections`.
+    selections: Vec<SpatialSelection>,
+    // Outer rings of the parcels in the last selection.
+    selected_parcels: Vec<Vec<Vec<Point2d>>>,
+    // Ids of the address points identified since the last call to `take_picks`.
+    picks: Vec<Uuid>,
+    // Ids of the address points to highlight, and their locations once read.
+    highlight: HashSet<Uuid>,
+    highlighted: Vec<Point2d>,
+    // Kind and path of the next layer added in the layer panel.
+    add_kind: LayerKind,
+    // Kind of layer edited in the style panel.
+    style_kind: LayerKind,
+    add_path: String,
+    // Fit the camera to the visible layers once the size of the viewport is known.
+    fit: bool,
+    // Latitude and longitude under the pointer in the last frame.
+    cursor: Option<(f64, f64)>,
+    // Screen points per physical pixel, from the last frame.
+    pixels_per_point: f32,
+    canvas: MapCanvas,
+}
+
+#[cfg(feature = "gui")]
+impl MapView {
+    /// Creates a map of the datasets in the [`Catalog`].
+    pub fn new() -> Self {
+        let catalog = Catalog::open();
+        Self {
+            layers: LayerStack::from_catalog(&catalog),
+            edit_path: catalog.addresses.clone(),
+            basemap: catalog.basemap,
+            symbology: Symbology::open(),
+            layout_path: LAYOUT_PATH.to_string(),
+            legend: true,
+            labels: true,
+            pixels_per_point: 1.0,
+            ..Default::default()
+        }
+    }
+
+    /// Replaces the layers of the map, as when restoring the layer panel from a saved session.
+    pub fn with_layers(mut self, layers: LayerStack) -> Self {
+        self.layers = layers;
+        self
+    }
+
+    /// Reads the data of every layer and the basemap, discarding anything read before except the
+    /// data given by [`Self::share`].  A layer that cannot be read is left off the map rather
+    /// than failing, since the others are still worth mapping.
+    pub fn load(&mut self, ctx: &egui::Context) {
+        self.loaded = true;
+        self.fit = true;
+        self.status.clear();
+        let basemap = match &self.basemap {
+            Some(source) => match Tileset::open(source) {
+                Ok(tileset) => Some(Basemap::new(tileset)),
+                Err(e) => {
+                    self.status = format!("Basemap unavailable: {}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+        if let Ok(mut canvas) = self.canvas.0.lock() {
+            canvas.repaint = Some(ctx.clone());
+            let Canvas { data, shared, .. } = &mut *canvas;
+            data.retain(|source, _| shared.contains(source));
+            canvas.failed.clear();
+            canvas.map = None;
+            canvas.basemap = basemap;
+        }
+        self.read_layers();
+        self.locate_highlight();
+    }
+
+    /// Reads the data of any layer added since the last call.
+    fn read_layers(&mut self) {
+        let Ok(mut canvas) = self.canvas.0.lock() else {
+            return;
+        };
+        for layer in self.layers.iter() {
+            let source = layer.source();
+            if canvas.data.contains_key(&source) || canvas.failed.contains(&source) {
+                continue;
+            }
+            match LayerData::load(layer) {
+                Ok(data) => {
+                    canvas.data.insert(source, data);
+                }
+                Err(e) => {
+                    self.status = format!("Could not read {}: {}", layer.name, e);
+                    canvas.failed.insert(source);
+                }
+            }
+        }
+    }
+
+    /// Uses `data` for the layers of kind `kind` with the path `path`, in place of reading their
+    /// file, as for the datasets the application has already read.  Replaces any data read
+    /// before.
+    pub fn share(&mut self, kind: LayerKind, path: &str, data: LayerData) {
+        let source = (kind, path.to_string());
+        let Ok(mut canvas) = self.canvas.0.lock() else {
+            return;
+        };
+        canvas.failed.remove(&source);
+        canvas.shared.insert(source.clone());
+        canvas.data.insert(source, data);
+        canvas.map = None;
+    }
+
+    /// Returns `true` if the layers of kind `kind` with the path `path` use data given by
+    /// [`Self::share`].
+    pub fn is_shared(&self, kind: LayerKind, path: &str) -> bool {
+        match self.canvas.0.lock() {
+            Ok(canvas) => canvas.shared.contains(&(kind, path.to_string())),
+            Err(_) => false,
+        }
+    }
+
+    /// Returns the number of features read for `layer`, if it has been read.
+    pub fn count(&self, layer: &MapLayer) -> Option<usize> {
+        let canvas = self.canvas.0.lock().ok()?;
+        canvas.data.get(&layer.source()).map(|v| v.len())
+    }
+
+    /// Returns the bounds of the features of `layers` in EPSG:3857.
+    pub fn extent<'a, I: IntoIterator<Item = &'a MapLayer>>(&self, layers: I) -> Option<Rect> {
+        let canvas = self.canvas.0.lock().ok()?;
+        let bounds = layers
+            .into_iter()
+            .filter_map(|v| canvas.data.get(&v.source()))
+            .filter_map(|v| v.extent())
+            .collect::<Vec<Rect>>();
+        extent(corners(&bounds))
+    }
+
+    /// Returns the ids of the address points identified since the last call, to select their
+    /// rows in the address table.
+    pub fn take_picks(&mut self) -> Vec<Uuid> {
+        std::mem::take(&mut self.picks)
+    }
+
+    /// Highlights the address points with an id in `ids`, as when selecting rows in the address
+    /// table.  If [`Self::follow`] is set, the camera moves to show them.
+    pub fn highlight(&mut self, ids: &HashSet<Uuid>) {
+        if *ids == self.highlight {
+            return;
+        }
+        self.highlight = ids.clone();
+        self.locate_highlight();
+        if self.follow {
+            self.follow_highlight();
+        }
+    }
+
+    /// Finds the highlighted address points in the visible layers.
+    fn locate_highlight(&mut self) {
+        let points = match self.canvas.0.lock() {
+            Ok(canvas) => self
+                .layers
+                .iter()
+                .filter(|v| v.visible)
+                .filter_map(|v| canvas.data.get(&v.source()))
+                .flat_map(|v| v.locate(&self.highlight))
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        self.highlighted = points;
+    }
+
+    /// Centers the camera on a single highlighted point, or fits it to several.
+    fn follow_highlight(&mut self) {
+        match self.highlighted.as_slice() {
+            [] => {}
+            [point] => self.camera.center = *point,
+            points => {
+                if let Some(bounds) = extent(points.iter().copied()) {
+                    self.camera.fit(&bounds);
+                }
+            }
+        }
+    }
+
+    /// Identifies the feature at `point` on the topmost layer drawn at the current zoom, and
+    /// queues the id of an address point for [`Self::take_picks`].
+    fn identify(&mut self, point: &Point2d) {
+        let tolerance = PICK_RADIUS * self.camera.resolution;
+        let zoom = self.camera.zoom_level();
+        let picked = match self.canvas.0.lock() {
+            Ok(canvas) => self
+                .layers
+                .iter()
+                .filter(|v| v.shown_at(zoom))
+                .find_map(|layer| {
+                    let pick = canvas.data.get(&layer.source())?.pick(point, tolerance)?;
+                    Some(Pick {
+                        layer: layer.name.clone(),
+                        ..pick
+                    })
+                }),
+            Err(_) => None,
+        };
+        if let Some(id) = picked.as_ref().and_then(|v| v.id) {
+            self.picks.push(id);
+        }
+        self.identified = picked;
+    }
+
+    /// Adds `point` to the measurement made with `kind`, starting a new measurement if the last
+    /// one is finished or of another kind.  With [`Self::snap`] set, the point moves to the
+    /// nearest parcel vertex within [`PICK_RADIUS`] on a layer drawn at the current zoom.
+    fn measure(&mut self, kind: MeasureKind, point: Point2d) {
+        let tolerance = PICK_RADIUS * self.camera.resolution;
+        let zoom = self.camera.zoom_level();
+        let snapped = match (self.snap, self.canvas.0.lock()) {
+            (true, Ok(canvas)) => self
+                .layers
+                .iter()
+                .filter(|v| v.shown_at(zoom))
+                .find_map(|layer| canvas.data.get(&layer.source())?.snap(&point, tolerance)),
+            _ => None,
+        };
+        let point = snapped.unwrap_or(point);
+        let measurement = match self.measurement.take() {
+            Some(v) if v.kind == kind && !v.finished => v,
+            _ => Measurement::new(kind),
+        };
+        let measurement = self.measurement.insert(measurement);
+        // The clicks of a double click land on the same spot, so skip repeated points.
+        let repeated = measurement.points.last().is_some_and(|last| {
+            (last.x() - point.x()).hypot(last.y() - point.y()) < 3.0 * self.camera.resolution
+        });
+        if !repeated {
+            measurement.push(point);
+        }
+    }
+
+    /// Returns the address point of the edited layer nearest to `point`, within [`PICK_RADIUS`],
+    /// if the layer is drawn at the current zoom.
+    fn pick_edited(&self, point: &Point2d) -> Option<Pick> {
+        let tolerance = PICK_RADIUS * self.camera.resolution;
+        let zoom = self.camera.zoom_level();
+        let source = (LayerKind::Addresses, self.edit_path.clone());
+        let canvas = self.canvas.0.lock().ok()?;
+        self.layers
+            .iter()
+            .filter(|v| v.shown_at(zoom))
+            .find(|v| v.source() == source)?;
+        canvas.data.get(&source)?.pick(point, tolerance)
+    }
+
+    /// Returns the interior point of the parcel beneath `point` on a layer drawn at the current
+    /// zoom if [`Self::snap_parcels`] is set, or `point` otherwise.
+    fn place(&self, point: Point2d) -> Point2d {
+        if !self.snap_parcels {
+            return point;
+        }
+        let zoom = self.camera.zoom_level();
+        let Ok(canvas) = self.canvas.0.lock() else {
+            return point;
+        };
+        self.layers
+            .iter()
+            .filter(|v| v.shown_at(zoom))
+            .find_map(|layer| canvas.data.get(&layer.source())?.interior(&point))
+            .unwrap_or(point)
+    }
+
+    /// Applies a click at `point` with an editing tool.  The add tool places the new address
+    /// point, keeping any values entered in its form, and the delete tool queues the address
+    /// point under the pointer for deletion.  The move tool identifies the point clicked.
+    fn edit_click(&mut self, point: Point2d) {
+        match self.tool {
+            MapTool::Add => {
+                let point = self.place(point);
+                let values = match self.draft.take() {
+                    Some((_, values)) => values,
+                    None => AddressColumns::iter()
+                        .map(|v| match v {
+                            AddressColumns::Status => AddressStatus::Pending.to_string(),
+                            _ => String::new(),
+                        })
+                        .collect(),
+                };
+                self.draft = Some((point, values));
+            }
+            MapTool::Delete => match self.pick_edited(&point).and_then(|v| v.id) {
+                Some(id) => {
+                    self.edits.push(MapEdit::Delete(id));
+                    self.identified = None;
+                    self.status = "Deleted address point.".to_string();
+                }
+                None => self.status = "No address point to delete here.".to_string(),
+            },
+            _ => self.identify(&point),
+        }
+    }
+
+    /// Queues the move of the address point `id` to `to`, unless it was dropped where it
+    /// started.
+    fn move_point(&mut self, id: Uuid, from: Point2d, to: Point2d) {
+        if (from.x() - to.x()).hypot(from.y() - to.y()) < self.camera.resolution {
+            return;
+        }
+        // Written in full, so the table keeps the location dropped rather than a rounded one.
+        let (lon, lat) = mercator_to_lonlat(&to);
+        self.edits.push(MapEdit::Move {
+            id,
+            location: format!("{lat}, {lon}"),
+        });
+        self.status = "Moved address point.".to_string();
+    }
+
+    /// Returns the edits made with the editing tools since the last call, for the address table
+    /// to apply.
+    pub fn take_edits(&mut self) -> Vec<MapEdit> {
+        std::mem::take(&mut self.edits)
+    }
+
+    /// Returns `true` if the edited address layer holds the rows of the address table as of
+    /// `revision`.
+    pub fn is_synced(&self, revision: u64) -> bool {
+        self.synced == Some(revision)
+    }
+
+    /// Shares `records`, the rows of the address table as of `revision`, as the edited address
+    /// layer, so the map shows the edits and undos made in either view without reading the
+    /// address file itself.
+    pub fn sync_addresses(&mut self, records: Arc<Vec<AddressPoint>>, revision: u64) {
+        let path = self.edit_path.clone();
+        self.share(LayerKind::Addresses, &path, LayerData::Addresses(records));
+        self.synced = Some(revision);
+        self.locate_highlight();
+    }
+
+    /// Reports `message` on the status line.
+    pub fn report(&mut self, message: &str) {
+        self.status = message.to_string();
+    }
+
+    /// Draws the address point being moved and the location of a new address point.
+    fn paint_edits(&self, ui: &egui::Ui, rect: egui::Rect) {
+        let camera = self.camera;
+        let screen = |point: &Point2d| {
+            let (x, y) = camera.to_screen(point);
+            rect.min + egui::vec2(x as f32, y as f32)
+        };
+        let painter = ui.painter_at(rect);
+        let color = ui.visuals().warn_fg_color;
+        if let Some((_, from, to)) = &self.dragging {
+            let stroke = egui::Stroke::new(1.5, color);
+            painter.add(egui::Shape::dashed_line(
+                &[screen(from), screen(to)],
+                stroke,
+                6.0,
+                4.0,
+            ));
+            painter.circle_stroke(screen(from), 6.0, egui::Stroke::new(1.0, color));
+            painter.circle_filled(screen(to), 6.0, color);
+        }
+        if let Some((point, _)) = &self.draft {
+            let center = screen(point);
+            let stroke = egui::Stroke::new(2.0, color);
+            painter.circle_stroke(center, 8.0, stroke);
+            painter.line_segment(
+                [
+                    center - egui::vec2(12.0, 0.0),
+                    center + egui::vec2(12.0, 0.0),
+                ],
+                stroke,
+            );
+            painter.line_segment(
+                [
+                    center - egui::vec2(0.0, 12.0),
+                    center + egui::vec2(0.0, 12.0),
+                ],
+                stroke,
+            );
+        }
+    }
+
+    /// Draws the labels of the layers shown at the current zoom, dropping labels that would
+    /// overlap the labels of the layers above or of features drawn earlier in the same layer.
+    fn paint_labels(&self, ui: &egui::Ui, rect: egui::Rect) {
+        let zoom = self.camera.zoom_level();
+        let bounds = self.camera.bounds();
+        let Ok(canvas) = self.canvas.0.lock() else {
+            return;
+        };
+        let painter = ui.painter_at(rect);
+        let mut boxes = Vec::new();
+        let mut labels = Vec::new();
+        for layer in self.layers.iter().filter(|v| v.shown_at(zoom)) {
+            let style = self.symbology.style(layer.kind);
+            let Some(label) = style.label.as_ref().filter(|v| v.shown_at(zoom)) else {
+                continue;
+            };
+            let Some(data) = canvas.data.get(&layer.source()) else {
+                continue;
+            };
+            let font = egui::FontId::proportional(label.size);
+            for (point, text) in data.labels(&label.column, &bounds) {
+                let galley = painter.layout_no_wrap(text, font.clone(), label.color.color32());
+                let (x, y) = self.camera.to_screen(&point);
+                let anchor = rect.min + egui::vec2(x as f32, y as f32);
+                let size = galley.size();
+                // Points are labeled to their right, and polygons at their interior point.
+                let position = match layer.kind.is_point() {
+                    true => anchor + egui::vec2(style.default.size / 2.0 + 3.0, -size.y / 2.0),
+                    false => anchor - size / 2.0,
+                };
+                boxes.push(
+                    LabelBox::new(position.x, position.y, size.x, size.y)
+                        .expand(label.halo_width + 1.0),
+                );
+                labels.push((galley, position, label));
+            }
+        }
+        drop(canvas);
+        for index in declutter(&boxes) {
+            let (galley, position, label) = &labels[index];
+            if label.halo_width > 0.0 {
+                let halo = label.halo.color32();
+                for step in 0..8 {
+                    let angle = step as f32 * std::f32::consts::FRAC_PI_4;
+                    let offset = egui::vec2(angle.cos(), angle.sin()) * label.halo_width;
+                    painter.galley_with_override_text_color(
+                        *position + offset,
+                        galley.clone(),
+                        halo,
+                    );
+                }
+            }
+            painter.galley(*position, galley.clone(), label.color.color32());
+        }
+    }
+
+    /// Draws the highlighted points and the identified feature over the map, with a popup
+    /// listing the attributes of the identified feature.
+    fn overlay(&mut self, ui: &egui::Ui, rect: egui::Rect) {
+        let camera = self.camera;
+        let screen = |point: &Point2d| {
+            let (x, y) = camera.to_screen(point);
+            rect.min + egui::vec2(x as f32, y as f32)
+        };
+        let painter = ui.painter_at(rect);
+        let stroke = egui::Stroke::new(2.0, ui.visuals().selection.bg_fill);
+        for point in &self.highlighted {
+            painter.circle_stroke(screen(point), 6.0, stroke);
+        }
+        let Some(pick) = &self.identified else {
+            return;
+        };
+        let stroke = egui::Stroke::new(2.5, ui.visuals().warn_fg_color);
+        for ring in &pick.rings {
+            let points = ring.iter().map(screen).collect::<Vec<egui::Pos2>>();
+            painter.add(egui::Shape::closed_line(points, stroke));
+        }
+        let position = screen(&pick.location);
+        if pick.rings.is_empty() {
+            painter.circle_stroke(position, 8.0, stroke);
+        }
+
+        let mut close = false;
+        egui::Area::new(ui.id().with("identified"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(position + egui::vec2(12.0, 12.0))
+            .show(ui.ctx(), |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.strong(&pick.layer);
+                        if ui.small_button("✖").clicked() {
+                            close = true;
+                        }
+                    });
+                    egui::Grid::new("attributes").striped(true).show(ui, |ui| {
+                        for (name, value) in &pick.attributes {
+                            ui.label(name);
+                            ui.label(value);
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+        if close {
+            self.identified = None;
+        }
+    }
+
+    /// Adds `point` to the shape dragged by a selection tool.  A rectangle keeps the corner
+    /// where the drag started and the corner under the pointer, while a lasso follows the
+    /// pointer, skipping moves of less than a few screen points.
+    fn sketch(&mut self, point: Point2d) {
+        match (self.tool, self.sketch.first().copied()) {
+            (MapTool::Rectangle, Some(start)) => {
+                self.sketch = vec![
+                    start,
+                    Point2d::new(point.x(), start.y()),
+                    point,
+                    Point2d::new(start.x(), point.y()),
+                ];
+            }
+            _ => {
+                let moved = match self.sketch.last() {
+                    Some(last) => {
+                        (last.x() - point.x()).hypot(last.y() - point.y())
+                            >= 3.0 * self.camera.resolution
+                    }
+                    None => true,
+                };
+                if moved {
+                    self.sketch.push(point);
+                }
+            }
+        }
+    }
+
+    /// Selects the features of [`Self::select_kind`] inside the dragged shape on the layers
+    /// drawn at the current zoom, queueing the ids of the address points for
+    /// [`Self::take_selections`].  Parcels have no table of their own, so selecting parcels
+    /// outlines them on the map and selects the address points within them.
+    fn select(&mut self, mode: SelectMode) {
+        let shape = std::mem::take(&mut self.sketch);
+        if shape.len() < 3 {
+            return;
+        }
+        let zoom = self.camera.zoom_level();
+        let Ok(canvas) = self.canvas.0.lock() else {
+            return;
+        };
+        let data = self
+            .layers
+            .iter()
+            .filter(|v| v.shown_at(zoom))
+            .filter_map(|v| canvas.data.get(&v.source()))
+            .collect::<Vec<&LayerData>>();
+        let shapes = match self.select_kind {
+            LayerKind::Parcels => {
+                let parcels = data
+                    .iter()
+                    .flat_map(|v| v.parcels_within(&shape))
+                    .collect::<Vec<Vec<Vec<Point2d>>>>();
+                let shapes = parcels.iter().flatten().cloned().collect();
+                self.status = format!("Selected {} parcels.", parcels.len());
+                self.selected_parcels = parcels;
+                shapes
+            }
+            _ => {
+                self.selected_parcels.clear();
+                vec![shape]
+            }
+        };
+        let ids = data
+            .iter()
+            .flat_map(|v| v.within(&shapes))
+            .collect::<HashSet<Uuid>>();
+        if self.select_kind != LayerKind::Parcels {
+            self.status = format!("Selected {} address points.", ids.len());
+        }
+        self.selections.push(SpatialSelection {
+            ids,
+            mode,
+            check: self.check,
+        });
+    }
+
+    /// Returns the selections made with the rectangle and lasso tools since the last call.
+    pub fn take_selections(&mut self) -> Vec<SpatialSelection> {
+        std::mem::take(&mut self.selections)
+    }
+
+    /// Draws the shape being dragged by a selection tool and the outlines of the selected
+    /// parcels.
+    fn paint_selection(&self, ui: &egui::Ui, rect: egui::Rect) {
+        let camera = self.camera;
+        let screen = |point: &Point2d| {
+            let (x, y) = camera.to_screen(point);
+            rect.min + egui::vec2(x as f32, y as f32)
+        };
+        let painter = ui.painter_at(rect);
+        let color = ui.visuals().selection.bg_fill;
+        let stroke = egui::Stroke::new(2.0, color);
+        for ring in self.selected_parcels.iter().flatten() {
+            let points = ring.iter().map(screen).collect::<Vec<egui::Pos2>>();
+            painter.add(egui::Shape::closed_line(points, stroke));
+        }
+        if self.sketch.len() > 1 {
+            let points = self.sketch.iter().map(screen).collect::<Vec<egui::Pos2>>();
+            let stroke = egui::Stroke::new(1.5, ui.visuals().selection.stroke.color);
+            painter.add(egui::Shape::dashed_line(&points, stroke, 6.0, 4.0));
+            if let (Some(first), Some(last)) = (points.first(), points.last()) {
+                painter.add(egui::Shape::dashed_line(&[*last, *first], stroke, 6.0, 4.0));
+            }
+        }
+    }
+
+    /// Draws the extent of the print layout over the map.
+    fn paint_layout(&self, ui: &egui::Ui, rect: egui::Rect) {
+        let extent = self.layout.extent();
+        let (x_min, y_min) = self
+            .camera
+            .to_screen(&Point2d::new(extent.x_min(), extent.y_max()));
+        let (x_max, y_max) = self
+            .camera
+            .to_screen(&Point2d::new(extent.x_max(), extent.y_min()));
+        let frame = egui::Rect::from_min_max(
+            rect.min + egui::vec2(x_min as f32, y_min as f32),
+            rect.min + egui::vec2(x_max as f32, y_max as f32),
+        );
+        let corners = [
+            frame.left_top(),
+            frame.right_top(),
+            frame.right_bottom(),
+            frame.left_bottom(),
+            frame.left_top(),
+        ];
+        let stroke = egui::Stroke::new(1.5, ui.visuals().warn_fg_color);
+        ui.painter_at(rect)
+            .add(egui::Shape::dashed_line(&corners, stroke, 8.0, 4.0));
+    }
+
+    /// Draws the measurement over the map, with a line to the pointer from the last point until
+    /// the measurement is finished, and its result beside the last point.
+    fn paint_measurement(&self, ui: &egui::Ui, rect: egui::Rect) {
+        let Some(measurement) = &self.measurement else {
+            return;
+        };
+        let camera = self.camera;
+        let screen = |point: &Point2d| {
+            let (x, y) = camera.to_screen(point);
+            rect.min + egui::vec2(x as f32, y as f32)
+        };
+        let painter = ui.painter_at(rect);
+        let color = ui.visuals().warn_fg_color;
+        let stroke = egui::Stroke::new(2.0, color);
+        let points = measurement
+            .points
+            .iter()
+            .map(screen)
+            .collect::<Vec<egui::Pos2>>();
+        let Some(last) = points.last().copied() else {
+            return;
+        };
+        match measurement.kind {
+            MeasureKind::Area if points.len() > 2 => {
+                painter.add(egui::Shape::closed_line(points.clone(), stroke));
+            }
+            _ => {
+                painter.add(egui::Shape::line(points.clone(), stroke));
+            }
+        }
+        for point in &points {
+            painter.circle_filled(*point, 3.5, color);
+        }
+        if !measurement.finished {
+            if let Some(hover) = ui.input(|i| i.pointer.hover_pos()) {
+                if rect.contains(hover) {
+                    let stroke = egui::Stroke::new(1.0, color);
+                    painter.line_segment([last, hover], stroke);
+                    if measurement.kind == MeasureKind::Area && points.len() > 1 {
+                        painter.line_segment([hover, points[0]], stroke);
+                    }
+                    ui.ctx().request_repaint();
+                }
+            }
+        }
+
+        let galley = painter.layout_no_wrap(
+            measurement.summary(),
+            egui::TextStyle::Body.resolve(ui.style()),
+            ui.visuals().text_color(),
+        );
+        let position = last + egui::vec2(10.0, -10.0 - galley.size().y);
+        let background = egui::Rect::from_min_size(position, galley.size()).expand(4.0);
+        painter.rect_filled(background, 3.0, ui.visuals().extreme_bg_color);
+        painter.rect_stroke(background, 3.0, egui::Stroke::new(1.0, color));
+        painter.galley(position, galley, ui.visuals().text_color());
+    }
+
+    /// Centers the camera on the features of the layer at `index`.
+    pub fn zoom_to_layer(&mut self, index: usize) {
+        if let Some(layer) = self.layers.get(index) {
+            match self.extent([layer]) {
+                Some(bounds) => self.camera.fit(&bounds),
+                None => self.status = format!("{} has no features.", layer.name),
+            }
+        }
+    }
+
+    /// Renders the map to its texture if the camera or the layers have changed since the last
+    /// render.  Called by the render loop before drawing the frame, since the tab has no access
+    /// to the device.
+    pub fn prepare(
+        &mut self,
+        device: &Arc<wgpu::Device>,
+        queue: &Arc<wgpu::Queue>,
+        renderer: &mut egui_wgpu::Renderer,
+    ) {
+        let Ok(mut canvas) = self.canvas.0.lock() else {
+            return;
+        };
+        if canvas.repaint.is_none() {
+            return;
+        }
+        canvas.build(&self.layers, &self.camera, &self.symbology);
+        let state = (self.camera, self.pixels_per_point);
+        if canvas.rendered == Some(state) {
+            return;
+        }
+        let width = (self.camera.width * self.pixels_per_point as f64).round() as u32;
+        let height = (self.camera.height * self.pixels_per_point as f64).round() as u32;
+        if width == 0 || height == 0 {
+            return;
+        }
+        let size = galileo_types::cartesian::Size::new(width, height);
+        let Canvas {
+            map,
+            renderer: target,
+            texture,
+            rendered,
+            ..
+        } = &mut *canvas;
+        let Some(map) = map else {
+            return;
+        };
+        let view = galileo::MapView::new_projected(
+            &self.camera.center,
+            self.camera.resolution / self.pixels_per_point as f64,
+        )
+        .with_size(size.cast());
+        map.set_view(view);
+        map.load_layers();
+
+        let target = target.get_or_insert_with(|| {
+            galileo::render::WgpuRenderer::new_with_device_and_texture_rt(
+                device.clone(),
+                queue.clone(),
+                size,
+            )
+        });
+        target.resize(size);
+        if let Err(e) = target.render(map) {
+            tracing::warn!("Could not render map: {:?}", e);
+            return;
+        }
+        let Some(view) = target.get_target_texture_view() else {
+            return;
+        };
+        match texture {
+            Some(id) => renderer.update_egui_texture_from_wgpu_texture(
+                device,
+                &view,
+                wgpu::FilterMode::Linear,
+                *id,
+            ),
+            None => {
+                *texture =
+                    Some(renderer.register_native_texture(device, &view, wgpu::FilterMode::Linear))
+            }
+        }
+        *rendered = Some(state);
+    }
+
+    /// Pans the map for the directional acts and zooms for [`EguiAct::Next`] and
+    /// [`EguiAct::Previous`].  Returns `false` for acts the map does not handle.
+    pub fn act(&mut self, act: &EguiAct) -> bool {
+        let (width, height) = (self.camera.width, self.camera.height);
+        match act {
+            EguiAct::Left => self.camera.pan(width * PAN_STEP, 0.0),
+            EguiAct::Right => self.camera.pan(-width * PAN_STEP, 0.0),
+            EguiAct::Up => self.camera.pan(0.0, height * PAN_STEP),
+            EguiAct::Down => self.camera.pan(0.0, -height * PAN_STEP),
+            EguiAct::Next => self.camera.zoom(ZOOM_STEP, width / 2.0, height / 2.0),
+            EguiAct::Previous => self.camera.zoom(1.0 / ZOOM_STEP, width / 2.0, height / 2.0),
+            _ => return false,
+        }
+        true
+    }
+
+    /// The `layer_panel` method lists the layers of the map, topmost first.  Drag a layer by its
+    /// handle to change the drawing order.
+    fn layer_panel(&mut self, ui: &mut egui::Ui) {
+        let zoom = self.camera.zoom_level();
+        ui.label(format!("Zoom {zoom:.1}"));
+        let counts = self
+            .layers
+            .iter()
+            .map(|v| self.count(v))
+            .collect::<Vec<Option<usize>>>();
+        let mut moved = None;
+        let mut removed = None;
+        let mut zoom_to = None;
+        for (index, layer) in self.layers.iter_mut().enumerate() {
+            let id = ui.id().with(("layer", index));
+            let response = ui
+                .horizontal(|ui| {
+                    ui.dnd_drag_source(id, LayerDrag(index), |ui| {
+                        ui.label("☰");
+                    });
+                    ui.checkbox(&mut layer.visible, "");
+                    let mut text = egui::RichText::new(&layer.name);
+                    if !layer.shown_at(zoom) {
+                        text = text.weak();
+                    }
+                    let hover = match counts[index] {
+                        Some(count) => format!("{}\n{} features", layer.path, count),
+                        None => format!("{}\nNot read", layer.path),
+                    };
+                    ui.label(text).on_hover_text(hover);
+                })
+                .response;
+            if let Some(dragged) = response.dnd_release_payload::<LayerDrag>() {
+                moved = Some((dragged.0, index));
+            }
+            egui::CollapsingHeader::new("Settings")
+                .id_source(id.with("settings"))
+                .show(ui, |ui| {
+                    ui.add(
+                        egui::Slider::new(&mut layer.opacity, 0.0..=1.0)
+                            .step_by(0.05)
+                            .text("Opacity"),
+                    );
+                    ui.horizontal(|ui| {
+                        ui.label("Zoom");
+                        ui.add(
+                            egui::DragValue::new(&mut layer.min_zoom)
+                                .clamp_range(0.0..=layer.max_zoom)
+                                .speed(0.1),
+                        );
+                        ui.label("to");
+                        ui.add(
+                            egui::DragValue::new(&mut layer.max_zoom)
+                                .clamp_range(layer.min_zoom..=MAX_ZOOM)
+                                .speed(0.1),
+                        );
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.button("Zoom to layer").clicked() {
+                            zoom_to = Some(index);
+                        }
+                        if ui.button("Remove").clicked() {
+                            removed = Some(index);
+                        }
+                    });
+                });
+        }
+        if let Some((index, target)) = moved {
+            self.layers.move_layer(index, target);
+        }
+        if let Some(index) = zoom_to {
+            self.zoom_to_layer(index);
+        }
+        if let Some(index) = removed {
+            self.layers.remove(index);
+        }
+
+        ui.separator();
+        ui.label("Add layer");
+        egui::ComboBox::from_id_source(ui.id().with("add_kind"))
+            .selected_text(self.add_kind.to_string())
+            .show_ui(ui, |ui| {
+                for kind in LayerKind::iter() {
+                    ui.selectable_value(&mut self.add_kind, kind, kind.to_string());
+                }
+            });
+        ui.text_edit_singleline(&mut self.add_path);
+        if ui.button("Add").clicked() && !self.add_path.is_empty() {
+            let name = std::path::Path::new(&self.add_path)
+                .file_stem()
+                .map(|v| v.to_string_lossy().to_string())
+                .unwrap_or_else(|| self.add_path.clone());
+            self.layers
+                .insert(0, MapLayer::new(&name, self.add_kind, &self.add_path));
+            self.add_path.clear();
+            self.read_layers();
+            self.locate_highlight();
+        }
+    }
+
+    /// Returns the values of `column` in the layers of kind `kind` that have been read.
+    fn values(&self, kind: LayerKind, column: &str) -> Vec<Cell> {
+        let Ok(canvas) = self.canvas.0.lock() else {
+            return Vec::new();
+        };
+        self.layers
+            .iter()
+            .filter(|v| v.kind == kind)
+            .filter_map(|v| canvas.data.get(&v.source()))
+            .flat_map(|v| v.values(column))
+            .collect()
+    }
+
+    /// The `style_panel` method edits the symbology of one kind of layer at a time.  Saving
+    /// writes the symbology of every kind to [`SYMBOLOGY_PATH`].
+    fn style_panel(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source(ui.id().with("style_kind"))
+                .selected_text(self.style_kind.to_string())
+                .show_ui(ui, |ui| {
+                    for kind in LayerKind::iter() {
+                        ui.selectable_value(&mut self.style_kind, kind, kind.to_string());
+                    }
+                });
+            if ui.button("Save").clicked() {
+                self.status = match self.symbology.save(SYMBOLOGY_PATH) {
+                    Ok(()) => format!("Saved symbology to {SYMBOLOGY_PATH}."),
+                    Err(e) => format!("Could not save symbology: {}", e),
+                };
+            }
+            if ui.button("Reload").clicked() {
+                self.symbology = match Symbology::load(SYMBOLOGY_PATH) {
+                    Ok(symbology) => symbology,
+                    Err(e) => {
+                        self.status = format!("Could not read symbology: {}", e);
+                        self.symbology.clone()
+                    }
+                };
+            }
+            if ui.button("Defaults").clicked() {
+                *self.symbology.style_mut(self.style_kind) =
+                    Symbology::default().style(self.style_kind).clone();
+            }
+        });
+        ui.separator();
+        let kind = self.style_kind;
+        let columns = kind.columns();
+        let mut style = self.symbology.style(kind).clone();
+        ui.push_id(kind.to_string(), |ui| {
+            style.editor(ui, &columns, |column| self.values(kind, column));
+        });
+        if style != *self.symbology.style(kind) {
+            *self.symbology.style_mut(kind) = style;
+        }
+    }
+
+    /// The `layout_panel` method edits the print layout and exports the printed map.
+    fn layout_panel(&mut self, ui: &mut egui::Ui) {
+        let layout = &mut self.layout;
+        egui::Grid::new("layout").num_columns(2).show(ui, |ui| {
+            ui.label("Title");
+            ui.text_edit_singleline(&mut layout.title);
+            ui.end_row();
+            ui.label("Page");
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_source("page")
+                    .selected_text(layout.page.to_string())
+                    .show_ui(ui, |ui| {
+                        for page in PageSize::iter() {
+                            ui.selectable_value(&mut layout.page, page, page.to_string());
+                        }
+                    });
+                ui.checkbox(&mut layout.landscape, "Landscape");
+            });
+            ui.end_row();
+            ui.label("Scale 1:");
+            ui.add(
+                egui::DragValue::new(&mut layout.scale)
+                    .clamp_range(100.0..=1_000_000.0)
+                    .speed(100.0),
+            );
+            ui.end_row();
+            ui.label("Show");
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut layout.legend, "Legend");
+                ui.checkbox(&mut layout.scale_bar, "Scale bar");
+                ui.checkbox(&mut layout.north_arrow, "North arrow");
+            });
+            ui.end_row();
+            ui.label("Credit");
+            ui.text_edit_multiline(&mut layout.credit);
+            ui.end_row();
+            ui.label("Path");
+            ui.text_edit_singleline(&mut self.layout_path);
+            ui.end_row();
+        });
+        ui.horizontal(|ui| {
+            if ui.button("Use view").clicked() {
+                self.layout
+                    .view(&self.camera.center, self.camera.resolution);
+            }
+            if ui.button("Export SVG").clicked() {
+                self.export_layout(false);
+            }
+            if ui.button("Export PDF").clicked() {
+                self.export_layout(true);
+            }
+        });
+    }
+
+    /// Writes the printed map of the layers drawn at the scale of the layout, as PDF if `pdf` is
+    /// set or SVG otherwise, to [`Self::layout_path`] with the extension of the format.
+    pub fn export_layout(&mut self, pdf: bool) {
+        let zoom = self.layout.zoom_level();
+        let page = match self.canvas.0.lock() {
+            Ok(canvas) => {
+                let layers = self
+                    .layers
+                    .iter()
+                    .filter(|v| v.shown_at(zoom))
+                    .filter_map(|layer| {
+                        let data = canvas.data.get(&layer.source())?;
+                        Some(PrintLayer::new(
+                            layer,
+                            data,
+                            self.symbology.style(layer.kind),
+                        ))
+                    })
+                    .collect::<Vec<PrintLayer>>();
+                self.layout.compose(&layers)
+            }
+            Err(_) => return,
+        };
+        let extension = if pdf { "pdf" } else { "svg" };
+        let path = std::path::Path::new(&self.layout_path).with_extension(extension);
+        let saved = match pdf {
+            true => page.save_pdf(&path),
+            false => page.save_svg(&path),
+        };
+        self.status = match saved {
+            Ok(()) => format!("Printed map to {}.", path.display()),
+            Err(e) => format!("Could not print map: {}", e),
+        };
+    }
+
+    /// The `draft_panel` method edits the components of a new address point placed with the add
+    /// tool, starting with a status of pending.  Saving queues the point for the address table,
+    /// which validates it again before adding it.
+    fn draft_panel(&mut self, ui: &mut egui::Ui) {
+        let Some((point, values)) = &mut self.draft else {
+            return;
+        };
+        let (lon, lat) = mercator_to_lonlat(point);
+        ui.strong("New address");
+        ui.label(format!("{lat:.6}, {lon:.6}"));
+        egui::Grid::new("draft").num_columns(2).show(ui, |ui| {
+            for (column, value) in AddressColumns::iter().zip(values.iter_mut()) {
+                if matches!(column, AddressColumns::Label | AddressColumns::Location) {
+                    continue;
+                }
+                ui.label(column.to_string());
+                ui.text_edit_singleline(value);
+                ui.end_row();
+            }
+        });
+        let mut save = false;
+        let mut cancel = false;
+        ui.horizontal(|ui| {
+            save = ui.button("Save").clicked();
+            cancel = ui.button("Cancel").clicked();
+        });
+        if save {
+            match AddressPoint::pending(values, lat, lon) {
+                Ok(address) => {
+                    self.status = format!("Added {}.", address.address.label());
+                    self.edits.push(MapEdit::Add(address));
+                    self.draft = None;
+                }
+                Err(e) => self.status = e.to_string(),
+            }
+        } else if cancel {
+            self.draft = None;
+        }
+    }
+
+    /// The `legend` method lists the styles of the layers drawn at the current zoom, topmost
+    /// first.
+    fn legend(&self, ui: &mut egui::Ui) {
+        let zoom = self.camera.zoom_level();
+        for layer in self.layers.iter().filter(|v| v.shown_at(zoom)) {
+            ui.strong(&layer.name);
+            for (label, mut style) in self.symbology.style(layer.kind).legend() {
+                style.opacity *= layer.opacity;
+                ui.horizontal(|ui| {
+                    swatch(ui, &style, layer.kind.is_point());
+                    ui.label(label);
+                });
+            }
+        }
+    }
+
+    /// The `view` method displays the map within an [`egui::Ui`].
+    pub fn view(&mut self, ui: &mut egui::Ui) {
+        if !self.loaded {
+            self.load(ui.ctx());
+        }
+        ui.horizontal(|ui| {
+            ui.toggle_value(&mut self.layer_panel, "Layers");
+            ui.toggle_value(&mut self.style_panel, "Style");
+            if ui.toggle_value(&mut self.layout_panel, "Print").clicked() && self.layout_panel {
+                self.layout
+                    .view(&self.camera.center, self.camera.resolution);
+            }
+            ui.toggle_value(&mut self.legend, "Legend");
+            ui.toggle_value(&mut self.labels, "Labels");
+            if ui.button("Reload").clicked() {
+                self.load(ui.ctx());
+            }
+            if ui.button("Fit").clicked() {
+                self.fit = true;
+            }
+            ui.toggle_value(&mut self.follow, "Follow selection");
+            ui.separator();
+            let tool = self.tool;
+            for tool in MapTool::iter() {
+                ui.add_enabled_ui(self.editable || !tool.edits(), |ui| {
+                    ui.selectable_value(&mut self.tool, tool, tool.to_string())
+                        .on_disabled_hover_text("Open the address table to edit address points.");
+                });
+            }
+            // Leave the editing tools once the address table closes.
+            if self.tool.edits() && !self.editable {
+                self.tool = MapTool::default();
+            }
+            if self.tool != tool {
+                self.measurement = None;
+                self.sketch.clear();
+                self.draft = None;
+                self.dragging = None;
+            }
+            if self.tool.measure().is_some() {
+                ui.checkbox(&mut self.snap, "Snap");
+                if ui.button("Clear").clicked() {
+                    self.measurement = None;
+                }
+            }
+            if self.tool.selects() {
+                egui::ComboBox::from_id_source(ui.id().with("select kind"))
+                    .selected_text(self.select_kind.to_string())
+                    .show_ui(ui, |ui| {
+                        for kind in [LayerKind::Addresses, LayerKind::Parcels] {
+                            ui.selectable_value(&mut self.select_kind, kind, kind.to_string());
+                        }
+                    });
+                ui.checkbox(&mut self.check, "Check rows");
+                if ui.button("Clear").clicked() {
+                    self.selected_parcels.clear();
+                    self.selections.push(SpatialSelection {
+                        check: self.check,
+                        ..Default::default()
+                    });
+                }
+            }
+            if self.tool.edits() {
+                ui.checkbox(&mut self.snap_parcels, "Snap to parcels");
+            }
+            ui.label(&self.status);
+            if let Some((lat, lon)) = self.cursor {
+                ui.label(format!("{lat:.5}, {lon:.5}"));
+            }
+        });
+        if self.layer_panel {
+            egui::SidePanel::left(ui.id().with("layers"))
+                .resizable(true)
+                .show_inside(ui, |ui| {
+                    egui::ScrollArea::vertical().show(ui, |ui| self.layer_panel(ui));
+                });
+        }
+        if self.layout_panel {
+            egui::SidePanel::right(ui.id().with("layout"))
+                .resizable(true)
+                .show_inside(ui, |ui| {
+                    egui::ScrollArea::both().show(ui, |ui| self.layout_panel(ui));
+                });
+        }
+        if self.draft.is_some() {
+            egui::SidePanel::right(ui.id().with("draft"))
+                .resizable(true)
+                .show_inside(ui, |ui| {
+                    egui::ScrollArea::vertical().show(ui, |ui| self.draft_panel(ui));
+                });
+        }
+        if self.style_panel {
+            egui::SidePanel::right(ui.id().with("style"))
+                .resizable(true)
+                .show_inside(ui, |ui| {
+                    egui::ScrollArea::both().show(ui, |ui| self.style_panel(ui));
+                });
+        }
+
+        let (rect, response) =
+            ui.allocate_exact_size(ui.available_size(), egui::Sense::click_and_drag());
+        self.pixels_per_point = ui.ctx().pixels_per_point();
+        self.camera.width = rect.width() as f64;
+        self.camera.height = rect.height() as f64;
+        if self.fit {
+            let visible = self.layers.iter().filter(|v| v.visible);
+            if let Some(bounds) = self.extent(visible) {
+                self.camera.fit(&bounds);
+            }
+            self.fit = false;
+        }
+        let before = self.camera;
+
+        let primary = egui::PointerButton::Primary;
+        if self.tool == MapTool::Move && response.drag_started_by(primary) {
+            // Pick from where the button was pressed, since the pointer has moved by now.
+            let origin = ui.input(|i| i.pointer.press_origin());
+            if let Some(position) = origin.or(response.interact_pointer_pos()) {
+                let offset = position - rect.min;
+                let point = self.camera.to_map(offset.x as f64, offset.y as f64);
+                self.dragging = self
+                    .pick_edited(&point)
+                    .and_then(|v| Some((v.id?, v.location, v.location)));
+            }
+        }
+        if self.dragging.is_some() && response.dragged_by(primary) {
+            if let Some(position) = response.interact_pointer_pos() {
+                let offset = position - rect.min;
+                let point = self.place(self.camera.to_map(offset.x as f64, offset.y as f64));
+                if let Some(dragging) = &mut self.dragging {
+                    dragging.2 = point;
+                }
+            }
+        } else if self.tool.selects() && response.dragged_by(primary) {
+            if response.drag_started_by(primary) {
+                self.sketch.clear();
+            }
+            if let Some(position) = response.interact_pointer_pos() {
+                let offset = position - rect.min;
+                let point = self.camera.to_map(offset.x as f64, offset.y as f64);
+                self.sketch(point);
+            }
+        } else if response.dragged() {
+            let delta = response.drag_delta();
+            self.camera.pan(delta.x as f64, delta.y as f64);
+        }
+        if response.drag_stopped() {
+            if let Some((id, from, to)) = self.dragging.take() {
+                self.move_point(id, from, to);
+            }
+        }
+        if response.drag_stopped() && !self.sketch.is_empty() {
+            let mode = ui.input(|i| SelectMode::from_modifiers(&i.modifiers));
+            self.select(mode);
+        }
+        if response.clicked() {
+            if let Some(position) = response.interact_pointer_pos() {
+                let offset = position - rect.min;
+                let point = self.camera.to_map(offset.x as f64, offset.y as f64);
+                match self.tool.measure() {
+                    Some(kind) => self.measure(kind, point),
+                    None if self.tool.edits() => self.edit_click(point),
+                    None => self.identify(&point),
+                }
+            }
+        }
+        if let Some(position) = response.hover_pos() {
+            let offset = position - rect.min;
+            let (scroll, pinch) = ui.input(|i| (i.smooth_scroll_delta.y, i.zoom_delta()));
+            let mut factor = pinch as f64 * (scroll as f64 / 200.0).exp();
+            if response.double_clicked() {
+                match (self.tool.measure(), &mut self.measurement) {
+                    (Some(_), Some(measurement)) => measurement.finished = true,
+                    (Some(_), None) => {}
+                    (None, _) => factor *= ZOOM_STEP,
+                }
+            }
+            if factor != 1.0 {
+                self.camera.zoom(factor, offset.x as f64, offset.y as f64);
+            }
+            let point = self.camera.to_map(offset.x as f64, offset.y as f64);
+            let (lon, lat) = mercator_to_lonlat(&point);
+            self.cursor = Some((lat, lon));
+        }
+        if self.camera != before {
+            ui.ctx().request_repaint();
+        }
+
+        ui.painter()
+            .rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+        let texture = match self.canvas.0.lock() {
+            Ok(mut canvas) => {
+                if let Some(basemap) = &mut canvas.basemap {
+                    basemap.paint(ui, rect, &self.camera, self.pixels_per_point);
+                }
+                canvas.texture
+            }
+            Err(_) => None,
+        };
+        match texture {
+            Some(id) => {
+                let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+                ui.painter().image(id, rect, uv, egui::Color32::WHITE);
+                if self.labels {
+                    self.paint_labels(ui, rect);
+                }
+                self.overlay(ui, rect);
+                self.paint_measurement(ui, rect);
+                self.paint_selection(ui, rect);
+                self.paint_edits(ui, rect);
+                if self.layout_panel {
+                    self.paint_layout(ui, rect);
+                }
+                if self.legend {
+                    egui::Area::new(ui.id().with("legend"))
+                        .fixed_pos(rect.left_bottom() + egui::vec2(8.0, -8.0))
+                        .pivot(egui::Align2::LEFT_BOTTOM)
+                        .show(ui.ctx(), |ui| {
+                            egui::Frame::popup(ui.style()).show(ui, |ui| self.legend(ui));
+                        });
+                }
+            }
+            None => {
+                ui.painter().text(
+                    rect.center(),
+                    egui::Align2::CENTER_CENTER,
+                    "Loading map...",
+                    egui::TextStyle::Body.resolve(ui.style()),
+                    ui.visuals().weak_text_color(),
+                );
+                ui.ctx().request_repaint();
+            }
+        }
+    }
+}
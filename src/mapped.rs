@@ -0,0 +1,413 @@
+//! The `mapped` module provides an indexed binary format for large datasets that can be
+//! memory-mapped and decoded one record at a time.
+//!
+//! The file layout is:
+//!
+//! * The [`MAGIC`] bytes and the format [`VERSION`] as a little-endian `u32`.
+//! * The number of records as a little-endian `u64`.
+//! * A table of `count + 1` little-endian `u64` offsets, relative to the start of the record
+//!   section.  Record `i` occupies the bytes between offsets `i` and `i + 1`.
+//! * The record section, holding each record encoded with `bincode`.
+//!
+//! Opening a file maps it into memory and reads only the header, so the cost of opening does not
+//! depend on the size of the dataset.  Records are decoded when requested, and the operating
+//! system pages in the bytes backing them.  Datasets hold their records in [`Records`], which
+//! decodes a mapped record the first time a table or map reads it, and decodes the rest only when
+//! an operation needs every record.
+use crate::utils::replace_file;
+use memmap2::Mmap;
+use polite::Polite;
+use rayon::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::io::Write;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut, Range};
+use std::sync::{Arc, OnceLock};
+use std::{fs, io, path};
+use tracing::info;
+
+/// Leading bytes identifying a mapped dataset.
+pub const MAGIC: [u8; 4] = *b"WHMY";
+/// Version of the mapped file layout.
+pub const VERSION: u32 = 1;
+/// Length of the fixed header in bytes: magic, version and record count.
+const HEADER: usize = 16;
+
+/// The `save_mapped` function writes `records` to a file at location `path` in the mapped format.
+/// The file is replaced as by [`replace_file`], so datasets still mapping the old file are not
+/// disturbed.
+pub fn save_mapped<T: Serialize, P: AsRef<path::Path>>(records: &[T], path: P) -> Polite<()> {
+    info!("Serializing {} records to mapped binary.", records.len());
+    let mut offsets = Vec::with_capacity(records.len() + 1);
+    let mut body = Vec::new();
+    offsets.push(0u64);
+    for record in records {
+        bincode::serialize_into(&mut body, record)?;
+        offsets.push(body.len() as u64);
+    }
+    replace_file(path, |writer| {
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&(records.len() as u64).to_le_bytes())?;
+        for offset in offsets {
+            writer.write_all(&offset.to_le_bytes())?;
+        }
+        writer.write_all(&body)?;
+        Ok(())
+    })
+}
+
+/// The `is_mapped` function returns `true` if the file at location `path` begins with the
+/// [`MAGIC`] bytes of the mapped format.
+pub fn is_mapped<P: AsRef<path::Path>>(path: P) -> Polite<bool> {
+    let mut file = fs::File::open(path)?;
+    let mut magic = [0u8; 4];
+    match io::Read::read_exact(&mut file, &mut magic) {
+        Ok(()) => Ok(magic == MAGIC),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// The `Mapped` struct is a read-only view of a dataset of type `T` saved with [`save_mapped`].
+/// The underlying memory map and the cache of decoded records are shared, so cloning a `Mapped`
+/// is cheap.
+pub struct Mapped<T> {
+    map: Arc<Mmap>,
+    count: usize,
+    // Records decoded by `row`, boxed so the empty cache costs little per record.
+    cache: Arc<[OnceLock<Box<T>>]>,
+    phantom: PhantomData<T>,
+}
+
+// Implemented by hand so that cloning does not require `T: Clone`.
+impl<T> Clone for Mapped<T> {
+    fn clone(&self) -> Self {
+        Self {
+            map: Arc::clone(&self.map),
+            count: self.count,
+            cache: Arc::clone(&self.cache),
+            phantom: PhantomData,
+        }
+    }
+}
+
+// Implemented by hand so that debug output does not list the cache.
+impl<T> std::fmt::Debug for Mapped<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Mapped")
+            .field("count", &self.count)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: DeserializeOwned + Send> Mapped<T> {
+    /// The `open` method maps the file at location `path` into memory and validates the header
+    /// and offset table.  Does not decode any records.
+    pub fn open<P: AsRef<path::Path>>(path: P) -> Polite<Self> {
+        info!("Mapping binary into memory.");
+        let file = fs::File::open(path)?;
+        // Safety: the map is read-only.  Modifying the file while it is mapped is undefined
+        // behavior, so `save_mapped` and `utils::save` write a temporary file and rename it over
+        // the old one, which leaves existing maps reading the old contents.
+        let map = unsafe { Mmap::map(&file)? };
+        if map.len() < HEADER || map[0..4] != MAGIC {
+            return Err(invalid("File is not a mapped dataset.").into());
+        }
+        let version = u32::from_le_bytes([map[4], map[5], map[6], map[7]]);
+        if version != VERSION {
+            return Err(invalid(&format!("Unsupported mapped version {version}.")).into());
+        }
+        let mut count = [0u8; 8];
+        count.copy_from_slice(&map[8..HEADER]);
+        let count = u64::from_le_bytes(count) as usize;
+        // The offset table must fit in the file before we read the final offset from it.
+        let body = count
+            .checked_add(1)
+            .and_then(|v| v.checked_mul(8))
+            .and_then(|v| v.checked_add(HEADER));
+        match body {
+            Some(body) if body <= map.len() => {}
+            _ => return Err(invalid("Mapped dataset is truncated.").into()),
+        }
+        let mapped = Self {
+            map: Arc::new(map),
+            count,
+            cache: (0..count).map(|_| OnceLock::new()).collect(),
+            phantom: PhantomData,
+        };
+        let end = mapped
+            .offset(count)
+            .and_then(|v| v.checked_add(mapped.body()));
+        match end {
+            Some(end) if end <= mapped.map.len() => {}
+            _ => return Err(invalid("Mapped dataset is truncated.").into()),
+        }
+        info!("Records mapped: {}", count);
+        Ok(mapped)
+    }
+
+    /// Number of records in the dataset.
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Position of the record section within the map.
+    fn body(&self) -> usize {
+        HEADER + (self.count + 1) * 8
+    }
+
+    /// Reads entry `index` from the offset table.  Returns `None` if the entry lies outside the
+    /// file or does not fit in a `usize`.
+    fn offset(&self, index: usize) -> Option<usize> {
+        let start = index.checked_mul(8)?.checked_add(HEADER)?;
+        let bytes = self.map.get(start..start.checked_add(8)?)?;
+        let mut entry = [0u8; 8];
+        entry.copy_from_slice(bytes);
+        usize::try_from(u64::from_le_bytes(entry)).ok()
+    }
+
+    /// The `bytes` method returns the encoded bytes of the record at `index`, without decoding
+    /// them.  Returns `None` if `index` is out of bounds or the offsets are inconsistent, as in a
+    /// corrupt file.
+    pub fn bytes(&self, index: usize) -> Option<&[u8]> {
+        if index >= self.count {
+            return None;
+        }
+        let body = self.body();
+        let start = body.checked_add(self.offset(index)?)?;
+        let end = body.checked_add(self.offset(index.checked_add(1)?)?)?;
+        if start > end {
+            return None;
+        }
+        self.map.get(start..end)
+    }
+
+    /// The `get` method decodes the record at `index`.
+    pub fn get(&self, index: usize) -> Polite<T> {
+        match self.bytes(index) {
+            Some(bytes) => Ok(bincode::deserialize(bytes)?),
+            None => Err(invalid(&format!("Record {index} out of bounds.")).into()),
+        }
+    }
+
+    /// The `range` method decodes the records in `range`, for paging a window of the dataset into
+    /// a table or map.  The range is clamped to the length of the dataset.
+    pub fn range(&self, range: Range<usize>) -> Polite<Vec<T>> {
+        let end = range.end.min(self.count);
+        let start = range.start.min(end);
+        (start..end)
+            .into_par_iter()
+            .map(|i| self.get(i))
+            .collect::<Polite<Vec<T>>>()
+    }
+
+    /// The `records` method decodes every record in the dataset in parallel.
+    pub fn records(&self) -> Polite<Vec<T>> {
+        self.range(0..self.count)
+    }
+
+    /// The `row` method returns the record at `index`, decoding it on first use and keeping it
+    /// for later reads.  Returns `None` if `index` is out of bounds or the record is corrupt.
+    pub fn row(&self, index: usize) -> Option<&T> {
+        let cell = self.cache.get(index)?;
+        if let Some(record) = cell.get() {
+            return Some(record);
+        }
+        match self.get(index) {
+            Ok(record) => Some(cell.get_or_init(|| Box::new(record))),
+            Err(e) => {
+                tracing::warn!("Could not decode record {}: {}", index, e);
+                None
+            }
+        }
+    }
+
+    /// The `iter` method returns an iterator that decodes each record as it is visited.
+    pub fn iter(&self) -> impl Iterator<Item = Polite<T>> + '_ {
+        (0..self.count).map(|i| self.get(i))
+    }
+}
+
+/// The `Records` struct holds the records of a dataset, either in memory or backed by a [`Mapped`]
+/// file.  Reading the count with [`Records::len`] or a single record with [`Records::row`] decodes
+/// at most that record, so a table can show the rows in view of a large dataset without decoding
+/// the rest.  Dereferencing to the `Vec` of records decodes every record once, for operations
+/// that need them all, such as sorting, searching or drawing a whole layer.  Mutable access
+/// detaches the records from the file, since the file can no longer describe them.
+pub struct Records<T> {
+    mapped: Option<Mapped<T>>,
+    all: OnceLock<Vec<T>>,
+}
+
+impl<T: DeserializeOwned + Send> Records<T> {
+    /// Number of records, read from the header of a mapped file without decoding them.
+    pub fn len(&self) -> usize {
+        match (self.all.get(), &self.mapped) {
+            (Some(all), _) => all.len(),
+            (None, Some(mapped)) => mapped.len(),
+            (None, None) => 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The `row` method returns the record at `index`, decoding only that record if the records
+    /// are backed by a file.
+    pub fn row(&self, index: usize) -> Option<&T> {
+        match (self.all.get(), &self.mapped) {
+            (Some(all), _) => all.get(index),
+            (None, Some(mapped)) => mapped.row(index),
+            (None, None) => None,
+        }
+    }
+
+    /// Returns `true` once every record has been decoded into memory.
+    pub fn is_decoded(&self) -> bool {
+        self.mapped.is_none() || self.all.get().is_some()
+    }
+
+    /// Decodes every record, skipping any that are corrupt.
+    fn decode(&self) -> Vec<T> {
+        let Some(mapped) = &self.mapped else {
+            return Vec::new();
+        };
+        info!("Decoding {} mapped records.", mapped.len());
+        let records = (0..mapped.len())
+            .into_par_iter()
+            .filter_map(|i| mapped.get(i).ok())
+            .collect::<Vec<T>>();
+        if records.len() < mapped.len() {
+            tracing::warn!("Skipped {} corrupt records.", mapped.len() - records.len());
+        }
+        records
+    }
+
+    /// Returns the records as a `Vec`, decoding any still in the file.
+    pub fn into_vec(mut self) -> Vec<T> {
+        match self.all.take() {
+            Some(all) => all,
+            None => self.decode(),
+        }
+    }
+}
+
+impl<T: DeserializeOwned + Send> Deref for Records<T> {
+    type Target = Vec<T>;
+
+    fn deref(&self) -> &Vec<T> {
+        self.all.get_or_init(|| self.decode())
+    }
+}
+
+impl<T: DeserializeOwned + Send> DerefMut for Records<T> {
+    fn deref_mut(&mut self) -> &mut Vec<T> {
+        if self.all.get().is_none() {
+            self.all = OnceLock::from(self.decode());
+        }
+        self.mapped = None;
+        match self.all.get_mut() {
+            Some(all) => all,
+            None => unreachable!("records were decoded above"),
+        }
+    }
+}
+
+impl<'a, T: DeserializeOwned + Send> IntoIterator for &'a Records<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T> Default for Records<T> {
+    fn default() -> Self {
+        Self {
+            mapped: None,
+            all: OnceLock::new(),
+        }
+    }
+}
+
+impl<T: Clone> Clone for Records<T> {
+    fn clone(&self) -> Self {
+        Self {
+            mapped: self.mapped.clone(),
+            all: self.all.clone(),
+        }
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for Records<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.all.get() {
+            Some(all) => all.fmt(f),
+            None => f
+                .debug_struct("Records")
+                .field("mapped", &self.mapped)
+                .finish(),
+        }
+    }
+}
+
+impl<T> From<Vec<T>> for Records<T> {
+    fn from(records: Vec<T>) -> Self {
+        Self {
+            mapped: None,
+            all: OnceLock::from(records),
+        }
+    }
+}
+
+impl<T> From<Mapped<T>> for Records<T> {
+    fn from(mapped: Mapped<T>) -> Self {
+        Self {
+            mapped: Some(mapped),
+            all: OnceLock::new(),
+        }
+    }
+}
+
+impl<T: DeserializeOwned + Send + PartialEq> PartialEq for Records<T> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: DeserializeOwned + Send + PartialEq> PartialEq<Vec<T>> for Records<T> {
+    fn eq(&self, other: &Vec<T>) -> bool {
+        **self == *other
+    }
+}
+
+impl<T: DeserializeOwned + Send + PartialOrd> PartialOrd for Records<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+// Records serialize as a plain sequence, so `bincode` files are unchanged.
+impl<T: DeserializeOwned + Send + Serialize> Serialize for Records<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (**self).serialize(serializer)
+    }
+}
+
+impl<'de, T: DeserializeOwned> Deserialize<'de> for Records<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<T>::deserialize(deserializer).map(Self::from)
+    }
+}
@@ -0,0 +1,112 @@
+//! The `measure` module measures distances and areas drawn on the map.
+//!
+//! Map coordinates are in EPSG:3857, where planar lengths are stretched by the scale factor
+//! `1 / cos(lat)`, more than a third at our latitude.  Measurements use [`mercator_distance`] and
+//! [`mercator_area`] to correct for the scale, and report lengths in feet and miles and areas in
+//! square feet and acres.
+use crate::prelude::{mercator_area, mercator_distance};
+use galileo_types::cartesian::Point2d;
+use serde::{Deserialize, Serialize};
+
+/// Length of the international foot in meters.
+pub const METERS_PER_FOOT: f64 = 0.3048;
+pub const FEET_PER_MILE: f64 = 5280.0;
+pub const SQUARE_FEET_PER_ACRE: f64 = 43_560.0;
+
+/// The `MeasureKind` enum is the quantity measured by a [`Measurement`].
+#[derive(
+    Debug,
+    Default,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    Deserialize,
+    Serialize,
+    strum_macros::Display,
+    strum_macros::EnumIter,
+)]
+pub enum MeasureKind {
+    /// Length of a polyline.
+    #[default]
+    Distance,
+    /// Area of a polygon.
+    Area,
+}
+
+/// The `Measurement` struct holds the points of a distance or area measured on the map, in
+/// EPSG:3857.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    pub kind: MeasureKind,
+    pub points: Vec<Point2d>,
+    /// Set once the last point is placed.  The next point starts a new measurement.
+    pub finished: bool,
+}
+
+impl Measurement {
+    pub fn new(kind: MeasureKind) -> Self {
+        Self {
+            kind,
+            points: Vec::new(),
+            finished: false,
+        }
+    }
+
+    pub fn push(&mut self, point: Point2d) {
+        self.points.push(point);
+    }
+
+    /// Ground length in meters along the points, including the closing side of an area.
+    pub fn length(&self) -> f64 {
+        let open = self
+            .points
+            .windows(2)
+            .map(|v| mercator_distance(&v[0], &v[1]))
+            .sum::<f64>();
+        match (self.kind, self.points.first(), self.points.last()) {
+            (MeasureKind::Area, Some(first), Some(last)) if self.points.len() > 2 => {
+                open + mercator_distance(last, first)
+            }
+            _ => open,
+        }
+    }
+
+    /// Ground area in square meters enclosed by the points of an area measurement.
+    pub fn area(&self) -> f64 {
+        match self.kind {
+            MeasureKind::Area => mercator_area(&self.points),
+            MeasureKind::Distance => 0.0,
+        }
+    }
+
+    /// Returns the result of the measurement for display, with the length, or the area and
+    /// perimeter.
+    pub fn summary(&self) -> String {
+        match self.kind {
+            MeasureKind::Distance => format_length(self.length()),
+            MeasureKind::Area => format!(
+                "{}, perimeter {}",
+                format_area(self.area()),
+                format_length(self.length())
+            ),
+        }
+    }
+}
+
+/// The `format_length` function writes `meters` in feet and miles.
+pub fn format_length(meters: f64) -> String {
+    let feet = meters / METERS_PER_FOOT;
+    format!("{:.1} ft ({:.3} mi)", feet, feet / FEET_PER_MILE)
+}
+
+/// The `format_area` function writes `square_meters` in square feet and acres.
+pub fn format_area(square_meters: f64) -> String {
+    let square_feet = square_meters / (METERS_PER_FOOT * METERS_PER_FOOT);
+    format!(
+        "{:.0} sq ft ({:.3} ac)",
+        square_feet,
+        square_feet / SQUARE_FEET_PER_ACRE
+    )
+}
@@ -0,0 +1,560 @@
+//! The `merge` module reconciles two edited copies of a dataset against their common base.
+//! Changes made on one side only are applied automatically, and changes that collide are held as
+//! a [`Conflict`] until the user picks a side.
+use crate::prelude::{AddressPoint, AddressPoints};
+use galileo_types::cartesian::Point2d;
+use galileo_types::geo::impls::GeoPoint2d;
+use galileo_types::geo::{GeoPoint, NewGeoPoint};
+use polite::Polite;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io;
+use strum::EnumIter;
+#[cfg(feature = "gui")]
+use strum::IntoEnumIterator;
+use uuid::Uuid;
+
+/// The `MergeSide` enum names the three snapshots taking part in a merge.
+#[derive(
+    Debug,
+    Default,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    EnumIter,
+    Serialize,
+    Deserialize,
+)]
+pub enum MergeSide {
+    /// The common snapshot both copies were taken from.
+    #[default]
+    Base,
+    /// The first edited copy.
+    Ours,
+    /// The second edited copy.
+    Theirs,
+}
+
+impl fmt::Display for MergeSide {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Base => write!(f, "Base"),
+            Self::Ours => write!(f, "Ours"),
+            Self::Theirs => write!(f, "Theirs"),
+        }
+    }
+}
+
+/// The `ConflictKind` enum classifies a [`Conflict`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, EnumIter, Serialize, Deserialize)]
+pub enum ConflictKind {
+    /// Both copies edited the same field to different values.
+    #[default]
+    Field,
+    /// One copy deleted the record while the other modified it.
+    DeleteModify,
+}
+
+impl fmt::Display for ConflictKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Field => write!(f, "Field"),
+            Self::DeleteModify => write!(f, "Deleted vs. modified"),
+        }
+    }
+}
+
+/// The `Conflict` struct records a change that cannot be applied without input from the user.
+/// A missing value means the field or record is absent from that snapshot.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Conflict {
+    /// Stable id of the record in conflict.
+    pub id: Uuid,
+    /// Human readable label for the record.
+    pub label: String,
+    pub kind: ConflictKind,
+    /// Path to the field within the serialized record.  Empty for a whole record.
+    pub path: Vec<String>,
+    pub base: Option<Value>,
+    pub ours: Option<Value>,
+    pub theirs: Option<Value>,
+    /// Side chosen by the user, if resolved.
+    pub resolution: Option<MergeSide>,
+}
+
+impl Conflict {
+    /// Dotted name of the field in conflict, or "Record" for a whole record.
+    pub fn field(&self) -> String {
+        if self.path.is_empty() {
+            "Record".to_string()
+        } else {
+            self.path.join(".")
+        }
+    }
+
+    /// Returns the value held by `side`.
+    pub fn value(&self, side: &MergeSide) -> Option<&Value> {
+        match side {
+            MergeSide::Base => self.base.as_ref(),
+            MergeSide::Ours => self.ours.as_ref(),
+            MergeSide::Theirs => self.theirs.as_ref(),
+        }
+    }
+
+    /// Formats the value held by `side` for display.
+    pub fn text(&self, side: &MergeSide) -> String {
+        match self.value(side) {
+            Some(Value::String(value)) => value.clone(),
+            Some(Value::Null) => String::new(),
+            Some(value) => value.to_string(),
+            None => "(deleted)".to_string(),
+        }
+    }
+}
+
+/// Key holding the [`Location`] of a record within its serialized form during a merge.
+const LOCATION: &str = "location";
+
+/// The `Location` struct holds the fields of an [`AddressPoint`] that describe where it lies.  A
+/// merge treats them as one value, so that a merged record never takes the projected point from
+/// one move and the coordinates from another.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Location {
+    point: Point2d,
+    geo_point: GeoPoint2d,
+    x: f64,
+    y: f64,
+    latitude: f64,
+    longitude: f64,
+}
+
+impl Location {
+    fn of(record: &AddressPoint) -> Self {
+        Self {
+            point: record.point,
+            geo_point: GeoPoint2d::latlon(record.geo_point.lat(), record.geo_point.lon()),
+            x: record.address.x,
+            y: record.address.y,
+            latitude: record.address.latitude,
+            longitude: record.address.longitude,
+        }
+    }
+
+    /// Writes the location to `record` verbatim.
+    fn apply(&self, record: &mut AddressPoint) {
+        record.point = self.point;
+        record.geo_point = GeoPoint2d::latlon(self.geo_point.lat(), self.geo_point.lon());
+        record.address.x = self.x;
+        record.address.y = self.y;
+        record.address.latitude = self.latitude;
+        record.address.longitude = self.longitude;
+    }
+}
+
+/// The `Merge` struct holds the result of a three-way merge of [`AddressPoints`].  Records are
+/// paired by stable id.  Fields changed in only one copy, or changed the same way in both, are
+/// applied automatically.  The location fields of a record merge as a single field, named
+/// "location" in a [`Conflict`].  The merged snapshot is available from [`Merge::resolve`] once every
+/// conflict has a resolution.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Merge {
+    /// Merged records in serialized form, holding the base value wherever a conflict remains.
+    records: Vec<(Uuid, Option<Value>)>,
+    /// Changes that need a decision from the user.
+    pub conflicts: Vec<Conflict>,
+    /// Number of records changed by the merge without conflict.
+    pub applied: usize,
+}
+
+impl Merge {
+    /// Merges the edited snapshots `ours` and `theirs` against their common `base`.
+    pub fn addresses(
+        base: &AddressPoints,
+        ours: &AddressPoints,
+        theirs: &AddressPoints,
+    ) -> Polite<Self> {
+        let base_map = Self::address_map(base)?;
+        let ours_map = Self::address_map(ours)?;
+        let theirs_map = Self::address_map(theirs)?;
+        let mut labels = HashMap::new();
+        for record in theirs
+            .records
+            .iter()
+            .chain(ours.records.iter())
+            .chain(base.records.iter())
+        {
+            labels.insert(record.id, record.address.label());
+        }
+
+        // Keep the order of the base snapshot, followed by records added in each copy.
+        let mut ids = Vec::new();
+        let mut seen = HashSet::new();
+        for record in base
+            .records
+            .iter()
+            .chain(ours.records.iter())
+            .chain(theirs.records.iter())
+        {
+            if seen.insert(record.id) {
+                ids.push(record.id);
+            }
+        }
+
+        let mut merge = Self::default();
+        for id in ids {
+            let b = base_map.get(&id);
+            let o = ours_map.get(&id);
+            let t = theirs_map.get(&id);
+            let mut conflicts = Vec::new();
+            let value = Self::merge_value(&mut Vec::new(), b, o, t, &mut conflicts);
+            if conflicts.is_empty() && value.as_ref() != b {
+                merge.applied += 1;
+            }
+            let label = labels.get(&id).cloned().unwrap_or_default();
+            for mut conflict in conflicts {
+                conflict.id = id;
+                conflict.label = label.clone();
+                if conflict.path.is_empty() {
+                    conflict.kind = ConflictKind::DeleteModify;
+                }
+                merge.conflicts.push(conflict);
+            }
+            merge.records.push((id, value));
+        }
+        tracing::info!(
+            "Merge applied {} changes with {} conflicts.",
+            merge.applied,
+            merge.conflicts.len()
+        );
+        Ok(merge)
+    }
+
+    /// Serializes each record for merging.  The location fields are cleared and held together
+    /// under the [`LOCATION`] key, so that they merge as one value.
+    fn address_map(points: &AddressPoints) -> Polite<HashMap<Uuid, Value>> {
+        let mut map = HashMap::new();
+        for record in &points.records {
+            let location = serde_json::to_value(Location::of(record)).map_err(io::Error::from)?;
+            let mut cleared = record.clone();
+            cleared.locate(0.0, 0.0);
+            let mut value = serde_json::to_value(&cleared).map_err(io::Error::from)?;
+            if let Value::Object(fields) = &mut value {
+                fields.insert(LOCATION.to_string(), location);
+            }
+            map.insert(record.id, value);
+        }
+        Ok(map)
+    }
+
+    /// Merges a single value.  Objects present on all sides are merged key by key, so that edits
+    /// to different fields of the same record do not collide, except for the location of a
+    /// record, which merges whole.  Conflicts are pushed to `conflicts` and the base value is
+    /// kept in their place.
+    fn merge_value(
+        path: &mut Vec<String>,
+        base: Option<&Value>,
+        ours: Option<&Value>,
+        theirs: Option<&Value>,
+        conflicts: &mut Vec<Conflict>,
+    ) -> Option<Value> {
+        if ours == theirs {
+            return ours.cloned();
+        }
+        if ours == base {
+            return theirs.cloned();
+        }
+        if theirs == base {
+            return ours.cloned();
+        }
+        let atomic = matches!(path.as_slice(), [key] if key == LOCATION);
+        if let (Some(Value::Object(o)), Some(Value::Object(t)), false) = (ours, theirs, atomic) {
+            let b = match base {
+                Some(Value::Object(b)) => Some(b),
+                None => None,
+                _ => {
+                    conflicts.push(Self::conflict(path, base, ours, theirs));
+                    return base.cloned();
+                }
+            };
+            let mut keys = Vec::new();
+            for key in b
+                .iter()
+                .flat_map(|v| v.keys())
+                .chain(o.keys())
+                .chain(t.keys())
+            {
+                if !keys.contains(key) {
+                    keys.push(key.clone());
+                }
+            }
+            let mut merged = serde_json::Map::new();
+            for key in keys {
+                path.push(key.clone());
+                let value = Self::merge_value(
+                    path,
+                    b.and_then(|v| v.get(&key)),
+                    o.get(&key),
+                    t.get(&key),
+                    conflicts,
+                );
+                path.pop();
+                if let Some(value) = value {
+                    merged.insert(key, value);
+                }
+            }
+            return Some(Value::Object(merged));
+        }
+        conflicts.push(Self::conflict(path, base, ours, theirs));
+        base.cloned()
+    }
+
+    fn conflict(
+        path: &[String],
+        base: Option<&Value>,
+        ours: Option<&Value>,
+        theirs: Option<&Value>,
+    ) -> Conflict {
+        Conflict {
+            path: path.to_vec(),
+            base: base.cloned(),
+            ours: ours.cloned(),
+            theirs: theirs.cloned(),
+            ..Default::default()
+        }
+    }
+
+    /// Number of conflicts without a resolution.
+    pub fn unresolved(&self) -> usize {
+        self.conflicts
+            .iter()
+            .filter(|v| v.resolution.is_none())
+            .count()
+    }
+
+    /// Resolves every conflict in favor of `side`.
+    pub fn resolve_all(&mut self, side: MergeSide) {
+        self.conflicts
+            .iter_mut()
+            .for_each(|v| v.resolution = Some(side));
+    }
+
+    /// Applies the chosen resolution of each conflict and returns the merged snapshot.  Fails if
+    /// any conflict is unresolved, or if a resolved record no longer forms a valid address point.
+    pub fn resolve(&self) -> Polite<AddressPoints> {
+        let unresolved = self.unresolved();
+        if unresolved > 0 {
+            let message = format!("{} conflicts remain unresolved.", unresolved);
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, message).into());
+        }
+        let mut values = self
+            .records
+            .iter()
+            .cloned()
+            .collect::<HashMap<Uuid, Option<Value>>>();
+        for conflict in &self.conflicts {
+            if let Some(side) = &conflict.resolution {
+                if let Some(record) = values.get_mut(&conflict.id) {
+                    Self::set_path(record, &conflict.path, conflict.value(side).cloned());
+                }
+            }
+        }
+        let mut records = Vec::new();
+        for (id, _) in &self.records {
+            if let Some(Some(mut value)) = values.remove(id) {
+                let location = match &mut value {
+                    Value::Object(fields) => fields.remove(LOCATION),
+                    _ => None,
+                };
+                let mut point: AddressPoint =
+                    serde_json::from_value(value).map_err(io::Error::from)?;
+                if let Some(location) = location {
+                    let location: Location =
+                        serde_json::from_value(location).map_err(io::Error::from)?;
+                    location.apply(&mut point);
+                }
+                records.push(point);
+            }
+        }
+        Ok(AddressPoints {
+            records: records.into(),
+        })
+    }
+
+    /// Sets the value at `path` within `record`, removing it if `value` is `None`.
+    fn set_path(record: &mut Option<Value>, path: &[String], value: Option<Value>) {
+        let Some((last, parents)) = path.split_last() else {
+            *record = value;
+            return;
+        };
+        let mut current = match record {
+            Some(current) => current,
+            None => return,
+        };
+        for key in parents {
+            match current.get_mut(key) {
+                Some(next) => current = next,
+                None => return,
+            }
+        }
+        if let Value::Object(map) = current {
+            match value {
+                Some(value) => {
+                    map.insert(last.clone(), value);
+                }
+                None => {
+                    map.remove(last);
+                }
+            }
+        }
+    }
+}
+
+impl AddressPoints {
+    /// Merges `ours` and `theirs` against `self` as the common base.
+    /// Wraps [`Merge::addresses`].
+    pub fn merge(&self, ours: &AddressPoints, theirs: &AddressPoints) -> Polite<Merge> {
+        Merge::addresses(self, ours, theirs)
+    }
+}
+
+/// The `MergeView` struct is the merge tool tab.  The user picks a base snapshot and two edited
+/// copies, resolves any conflicts, and saves the result as a new snapshot.
+#[cfg(feature = "gui")]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MergeView {
+    /// Path to the common base snapshot.
+    pub base: String,
+    /// Path to the first edited copy.
+    pub ours: String,
+    /// Path to the second edited copy.
+    pub theirs: String,
+    /// Path for the merged snapshot.
+    pub output: String,
+    /// Result of the last merge.
+    pub merge: Option<Merge>,
+    /// Status line reporting the result of the last operation.
+    pub status: String,
+    /// Unique identifier assigned to the hosting tab.
+    pub identifier: Option<String>,
+}
+
+#[cfg(feature = "gui")]
+impl MergeView {
+    pub fn new() -> Self {
+        Self {
+            base: "data/addresses.data".to_string(),
+            ours: "data/addresses_ours.data".to_string(),
+            theirs: "data/addresses_theirs.data".to_string(),
+            output: "data/addresses_merged.data".to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Loads the three snapshots and merges them.
+    pub fn load(&mut self) -> Polite<()> {
+        let base = AddressPoints::load(&self.base)?;
+        let ours = AddressPoints::load(&self.ours)?;
+        let theirs = AddressPoints::load(&self.theirs)?;
+        self.merge = Some(base.merge(&ours, &theirs)?);
+        Ok(())
+    }
+
+    /// Resolves the merge and saves the result to `output`.
+    pub fn save(&self) -> Polite<usize> {
+        match &self.merge {
+            Some(merge) => {
+                let merged = merge.resolve()?;
+                merged.save(&self.output)?;
+                Ok(merged.records.len())
+            }
+            None => Ok(0),
+        }
+    }
+
+    /// The `view` method displays the merge tool within an [`egui::Ui`].
+    pub fn view(&mut self, ui: &mut egui::Ui) {
+        egui::Grid::new("merge_paths")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Base");
+                ui.text_edit_singleline(&mut self.base);
+                ui.end_row();
+                ui.label("Ours");
+                ui.text_edit_singleline(&mut self.ours);
+                ui.end_row();
+                ui.label("Theirs");
+                ui.text_edit_singleline(&mut self.theirs);
+                ui.end_row();
+            });
+        if ui.button("Merge").clicked() {
+            self.status = match self.load() {
+                Ok(()) => "Merge complete.".to_string(),
+                Err(e) => format!("Could not merge snapshots: {}", e),
+            };
+        }
+
+        if let Some(merge) = &mut self.merge {
+            ui.label(format!(
+                "Changes applied: {}  Conflicts: {}  Unresolved: {}",
+                merge.applied,
+                merge.conflicts.len(),
+                merge.unresolved()
+            ));
+            if !merge.conflicts.is_empty() {
+                ui.horizontal(|ui| {
+                    for side in MergeSide::iter() {
+                        if ui.button(format!("Use {} for all", side)).clicked() {
+                            merge.resolve_all(side);
+                        }
+                    }
+                });
+                egui::ScrollArea::vertical()
+                    .max_height(ui.available_height() - 60.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("merge_conflicts")
+                            .striped(true)
+                            .num_columns(6)
+                            .show(ui, |ui| {
+                                ui.strong("Record");
+                                ui.strong("Field");
+                                ui.strong("Conflict");
+                                for side in MergeSide::iter() {
+                                    ui.strong(side.to_string());
+                                }
+                                ui.end_row();
+                                for conflict in merge.conflicts.iter_mut() {
+                                    ui.label(&conflict.label);
+                                    ui.label(conflict.field());
+                                    ui.label(conflict.kind.to_string());
+                                    for side in MergeSide::iter() {
+                                        let text = conflict.text(&side);
+                                        ui.radio_value(&mut conflict.resolution, Some(side), text);
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            }
+            ui.horizontal(|ui| {
+                ui.label("Output");
+                ui.text_edit_singleline(&mut self.output);
+            });
+        }
+
+        if self.merge.is_some() && ui.button("Save").clicked() {
+            self.status = match self.save() {
+                Ok(count) => format!("Saved {} records to {}.", count, self.output),
+                Err(e) => format!("Could not save merge: {}", e),
+            };
+        }
+        if !self.status.is_empty() {
+            ui.label(&self.status);
+        }
+    }
+}
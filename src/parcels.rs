@@ -1,12 +1,28 @@
+use crate::mapped;
 use crate::prelude::*;
+#[cfg(feature = "gui")]
+use crate::symbology::LayerStyle;
+#[cfg(feature = "gui")]
+use galileo::layer::feature_layer::symbol::{SimplePolygonSymbol, Symbol};
+#[cfg(feature = "gui")]
 use galileo::layer::feature_layer::Feature;
+#[cfg(feature = "gui")]
+use galileo::render::render_bundle::RenderPrimitive;
+#[cfg(feature = "gui")]
+use galileo_types::cartesian::CartesianPoint3d;
 use galileo_types::cartesian::{CartesianPoint2d, Point2d, Rect};
 use galileo_types::geometry::CartesianGeometry2d;
+#[cfg(feature = "gui")]
+use galileo_types::geometry::Geom;
+#[cfg(feature = "gui")]
+use galileo_types::impls::Polygon as GalileoPolygon;
 use galileo_types::impls::{Contour, MultiPolygon};
 use geo::geometry::Geometry;
 use geojson::FeatureReader;
 use indicatif::ParallelProgressIterator;
 use indicatif::ProgressBar;
+#[cfg(feature = "gui")]
+use num_traits::AsPrimitive;
 use num_traits::Num;
 use polite::{FauxPas, Polite};
 use proj::Transform;
@@ -60,7 +76,7 @@ impl TryFrom<shapefile::dbase::Record> for Owner {
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Parcel {
     pub owner: Owner,
     pub geometry: MultiPolygon<Point2d>,
@@ -142,6 +158,7 @@ impl CartesianGeometry2d<Point2d> for Parcel {
     }
 }
 
+#[cfg(feature = "gui")]
 impl Feature for Parcel {
     type Geom = Self;
 
@@ -150,9 +167,51 @@ impl Feature for Parcel {
     }
 }
 
+impl Attributed for Parcel {
+    fn columns() -> Vec<String> {
+        vec!["Owner".to_string(), "Map number".to_string()]
+    }
+
+    fn attribute(&self, column: &str) -> Cell {
+        match column.to_lowercase().as_str() {
+            "owner" => self.owner.name.clone().map(Cell::Text).into(),
+            "map number" => Cell::Text(self.owner.id.clone()),
+            _ => Cell::Empty,
+        }
+    }
+}
+
+/// The `ParcelSymbol` struct draws parcels as polygons styled by the symbology rules.
+#[cfg(feature = "gui")]
+pub struct ParcelSymbol {
+    pub style: LayerStyle,
+    pub opacity: f32,
+}
+
+#[cfg(feature = "gui")]
+impl Symbol<Parcel> for ParcelSymbol {
+    fn render<'a, N, P>(
+        &self,
+        feature: &Parcel,
+        geometry: &'a Geom<P>,
+        min_resolution: f64,
+    ) -> Vec<RenderPrimitive<'a, N, P, Contour<P>, GalileoPolygon<P>>>
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N> + Clone,
+    {
+        let style = self.style.style_for(feature);
+        let opacity = style.opacity * self.opacity;
+        SimplePolygonSymbol::new(style.fill.color(opacity))
+            .with_stroke_color(style.stroke.color(opacity))
+            .with_stroke_width(style.stroke_width as f64)
+            .render(feature, geometry, min_resolution)
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Parcels {
-    pub records: Vec<Parcel>,
+    pub records: Records<Parcel>,
 }
 
 impl Parcels {
@@ -175,7 +234,9 @@ impl Parcels {
         }
         info!("Records dropped: {}.", dropped);
 
-        Ok(Parcels { records })
+        Ok(Parcels {
+            records: records.into(),
+        })
     }
 
     // pub fn from_shp<P: AsRef<Path>>(path: P, transform: Option<&str>) -> Polite<Self> {
@@ -203,10 +264,27 @@ impl Parcels {
         Ok(())
     }
 
+    /// Loads parcels from a file at location `path`.  Keeps files in the [`mapped`] format
+    /// behind a memory map, decoding parcels as they are read, and otherwise falls back to a
+    /// plain `bincode` file.
     pub fn load<P: AsRef<Path>>(path: P) -> Polite<Self> {
+        if mapped::is_mapped(&path)? {
+            let records = Self::open_mapped(path)?.into();
+            return Ok(Self { records });
+        }
         info!("Deserializing from binary.");
         let vec: Vec<u8> = std::fs::read(path)?;
         let parcels: Parcels = bincode::deserialize(&vec[..])?;
         Ok(parcels)
     }
+
+    /// Writes the parcels to a file at location `path` in the [`mapped`] format.
+    pub fn save_mapped<P: AsRef<Path>>(&self, path: P) -> Polite<()> {
+        mapped::save_mapped(&self.records, path)
+    }
+
+    /// Maps a file written by [`Parcels::save_mapped`] into memory without decoding the records.
+    pub fn open_mapped<P: AsRef<Path>>(path: P) -> Polite<Mapped<Parcel>> {
+        Mapped::open(path)
+    }
 }
@@ -1,5 +1,5 @@
 use crate::{
-    rpg::players::tab::TabView,
+    dock::TabView,
     table::{Columnar, Filtration, TableView, Tabular},
 };
 use derive_more::Display;
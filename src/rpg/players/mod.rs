@@ -1,5 +1,4 @@
 mod eponym;
 pub mod paeva;
-pub mod tab;
 
 pub use eponym::Players;
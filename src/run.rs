@@ -26,6 +26,9 @@ impl App {
         let mut state = State::new(Arc::clone(&window)).await;
         if let Ok(lens) = Lens::load("data/state.data") {
             state.lens = lens.clone();
+            state.lens.restore_layouts();
+            state.tab.restore_layouts(&state.lens.layouts);
+            state.tab.restore_layers(state.lens.map_layers.clone());
             // state.tab = egui_dock::DockState::new(vec![tab::Tab::new(lens)]);
         } else {
             tracing::info!("Could not read state from storage.");
@@ -145,7 +148,11 @@ impl App {
             match act {
                 // dispatch to the appropriate handler
                 Act::App(v) => self.state.act(v),
-                Act::Egui(v) => self.state.lens.act(v),
+                Act::Egui(v) => {
+                    if !self.state.tab.navigate(v) {
+                        self.state.lens.act(v)
+                    }
+                }
                 Act::Named(v) => {
                     tracing::trace!("{:#?}", &v);
                     match v {
@@ -166,6 +173,13 @@ impl App {
 
     pub fn close_requested(&mut self) {
         tracing::info!("Close requested.");
+        self.state.lens.store_layouts();
+        self.state.tab.store_layouts(&mut self.state.lens.layouts);
+        self.state.tab.store_layers();
+        self.state.lens.map_layers = self.state.tab.layers().clone();
+        if let Err(e) = self.state.lens.save_edits() {
+            tracing::warn!("Unable to save address edits: {}", e.to_string());
+        }
         let state = self.state();
         if state.lens.save("data/state.data").is_ok() {
             tracing::info!("State saved from ref.");
@@ -0,0 +1,192 @@
+//! The `search` module matches search terms against the text of table cells.
+//!
+//! Substring, whole word and regex searches compile to a [`Regex`], so each reports the byte
+//! ranges of its matches for highlighting.  Fuzzy searches match the characters of the term in
+//! order, allowing gaps, and score each match so that rows can be ranked.
+use crate::prelude::{Cell, Columnar};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// The `SearchMode` enum selects how a search term matches the text of a cell.
+#[derive(
+    Debug,
+    Default,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Deserialize,
+    Serialize,
+    strum_macros::Display,
+    strum_macros::EnumIter,
+)]
+pub enum SearchMode {
+    /// Matches the term anywhere in the text.
+    #[default]
+    Substring,
+    /// Matches the term only between word boundaries.
+    #[strum(serialize = "Whole word")]
+    WholeWord,
+    /// Treats the term as a regular expression.
+    Regex,
+    /// Matches the characters of the term in order, allowing gaps.  Rows rank by match score.
+    Fuzzy,
+}
+
+/// The `SearchMatch` struct holds the score of a match and the byte ranges of the matched text.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SearchMatch {
+    /// Higher scores are better matches.  Only fuzzy matches have varying scores.
+    pub score: i64,
+    pub ranges: Vec<Range<usize>>,
+}
+
+/// The `Matcher` struct is a search term compiled for a [`SearchMode`].
+#[derive(Debug, Clone)]
+pub struct Matcher {
+    mode: SearchMode,
+    case_sensitive: bool,
+    pattern: Vec<char>,
+    regex: Option<Regex>,
+}
+
+impl Matcher {
+    /// Compiles `fragment` for searching in `mode`.  Returns an error if the mode is
+    /// [`SearchMode::Regex`] and `fragment` is not a valid regular expression.
+    pub fn new(
+        fragment: &str,
+        mode: SearchMode,
+        case_sensitive: bool,
+    ) -> Result<Self, regex::Error> {
+        let source = match mode {
+            SearchMode::Substring => Some(regex::escape(fragment)),
+            SearchMode::WholeWord => Some(format!(r"\b{}\b", regex::escape(fragment))),
+            SearchMode::Regex => Some(fragment.to_string()),
+            SearchMode::Fuzzy => None,
+        };
+        let regex = match source {
+            Some(source) => Some(
+                RegexBuilder::new(&source)
+                    .case_insensitive(!case_sensitive)
+                    .build()?,
+            ),
+            None => None,
+        };
+        Ok(Self {
+            mode,
+            case_sensitive,
+            pattern: fragment.chars().filter(|c| !c.is_whitespace()).collect(),
+            regex,
+        })
+    }
+
+    pub fn mode(&self) -> SearchMode {
+        self.mode
+    }
+
+    /// Returns the match of the term in `text`, if any.
+    pub fn find(&self, text: &str) -> Option<SearchMatch> {
+        match &self.regex {
+            Some(regex) => {
+                let ranges = regex
+                    .find_iter(text)
+                    .filter(|v| !v.is_empty())
+                    .map(|v| v.range())
+                    .collect::<Vec<Range<usize>>>();
+                match ranges.is_empty() {
+                    true => None,
+                    false => Some(SearchMatch { score: 0, ranges }),
+                }
+            }
+            None => self.fuzzy(text),
+        }
+    }
+
+    /// Returns `true` if the term matches `text`.
+    pub fn is_match(&self, text: &str) -> bool {
+        match &self.regex {
+            Some(regex) => regex.is_match(text),
+            None => self.fuzzy(text).is_some(),
+        }
+    }
+
+    fn same(&self, a: char, b: char) -> bool {
+        match self.case_sensitive {
+            true => a == b,
+            false => a.to_lowercase().eq(b.to_lowercase()),
+        }
+    }
+
+    /// Matches the characters of the term in order.  Each matched character scores a point, with
+    /// bonuses for runs of consecutive characters and for characters at the start of a word, and
+    /// a penalty for the gap since the previous matched character.
+    fn fuzzy(&self, text: &str) -> Option<SearchMatch> {
+        if self.pattern.is_empty() {
+            return None;
+        }
+        let mut result = SearchMatch::default();
+        let mut next = 0;
+        let mut last: Option<usize> = None;
+        let mut previous: Option<char> = None;
+        for (position, (byte, c)) in text.char_indices().enumerate() {
+            if next == self.pattern.len() {
+                break;
+            }
+            if self.same(c, self.pattern[next]) {
+                result.score += 1;
+                match last {
+                    Some(index) if index + 1 == position => {
+                        result.score += 5;
+                        if let Some(range) = result.ranges.last_mut() {
+                            range.end = byte + c.len_utf8();
+                        }
+                    }
+                    _ => {
+                        if let Some(index) = last {
+                            result.score -= (position - index - 1).min(5) as i64;
+                        }
+                        result.ranges.push(byte..byte + c.len_utf8());
+                    }
+                }
+                if previous.map(|v| !v.is_alphanumeric()).unwrap_or(true) {
+                    result.score += 3;
+                }
+                last = Some(position);
+                next += 1;
+            }
+            previous = Some(c);
+        }
+        match next == self.pattern.len() {
+            true => Some(result),
+            false => None,
+        }
+    }
+}
+
+/// The `score_cells` function returns the best score of `matcher` among `cells`, or `None` if
+/// no cell matches.
+pub fn score_cells(cells: &[Cell], matcher: &Matcher) -> Option<i64> {
+    cells
+        .iter()
+        .filter_map(|v| matcher.find(&v.to_string()))
+        .map(|v| v.score)
+        .max()
+}
+
+/// The `search_rows` function returns the rows in `rows` with a cell matching `matcher`.  Fuzzy
+/// searches order the rows by the best score among their cells, keeping the original order
+/// between rows with equal scores.
+pub fn search_rows<T: Columnar>(rows: Vec<T>, matcher: &Matcher) -> Vec<T> {
+    let mut scored = rows
+        .into_iter()
+        .filter_map(|row| score_cells(&row.cells(), matcher).map(|score| (score, row)))
+        .collect::<Vec<(i64, T)>>();
+    if matcher.mode() == SearchMode::Fuzzy {
+        scored.sort_by(|(a, _), (b, _)| b.cmp(a));
+    }
+    scored.into_iter().map(|(_, row)| row).collect()
+}
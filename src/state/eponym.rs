@@ -1,8 +1,10 @@
 use crate::controls::command;
-use crate::prelude::{Action, AppAct, EguiState, Lens, WgpuFrame, KEY_BINDINGS, MOUSE_BINDINGS};
+use crate::dock;
+use crate::prelude::{
+    Action, AddressColumns, AppAct, EguiState, Lens, MapEdit, WgpuFrame, KEY_BINDINGS,
+    MOUSE_BINDINGS,
+};
 use crate::table::Tabular;
-// use crate::tab;
-use crate::rpg::players::tab;
 use std::{iter, sync::Arc};
 use winit::dpi::{PhysicalPosition, PhysicalSize};
 use winit::event::*;
@@ -19,7 +21,7 @@ pub struct State {
     pub window: Arc<Window>,
     pub egui_state: EguiState,
     pub lens: Lens,
-    pub tab: tab::TabState,
+    pub tab: dock::TabState,
     pub modifiers: ModifiersState,
     pub theme: Theme,
     /// Cursor position over the window.
@@ -99,7 +101,7 @@ impl State {
         let command = command::CommandMode::new();
         tracing::trace!("Commands: {:#?}", &command);
 
-        let tab = tab::TabState::new();
+        let tab = dock::TabState::new();
 
         Self {
             surface,
@@ -168,6 +170,10 @@ impl State {
                 size: self.size,
             };
 
+            self.link_edits();
+            self.link_selection();
+            self.tab
+                .prepare(&self.device, &self.queue, &mut self.egui_state.renderer);
             self.egui_state
                 .render(&mut wgpu_frame, |ui| self.tab.run_ui(ui));
         }
@@ -178,6 +184,59 @@ impl State {
 
         Ok(())
     }
+
+    /// Applies the edits made with the editing tools of the map tabs to the address table, then
+    /// copies the rows of the table back to the maps, along with the parcels.  Edits the table
+    /// rejects are reported on the status line of the maps.
+    pub fn link_edits(&mut self) {
+        if let Some(parcels) = &self.lens.parcels {
+            self.tab.share_parcels(parcels, &self.lens.catalog.parcels);
+        }
+        // Without an address table to apply them, leave any edits queued and disable the tools.
+        let Some(table) = &mut self.lens.address_table else {
+            self.tab.set_editable(false);
+            return;
+        };
+        self.tab.set_editable(true);
+        let edits = self.tab.take_edits();
+        for edit in edits {
+            let result = match edit {
+                MapEdit::Add(address) => table.insert_rows(&[address]),
+                MapEdit::Move { id, location } => {
+                    table.edit(&[(id, AddressColumns::Location as usize, location)])
+                }
+                MapEdit::Delete(id) => table.remove_rows(&[id]),
+            };
+            if let Err(e) = result {
+                self.tab.report(&format!("Edit rejected: {}", e));
+            }
+        }
+        self.tab.sync_addresses(&table.data, table.data_revision());
+    }
+
+    /// Selects the rows of address points identified or selected on the map tabs in the address
+    /// table, and highlights the rows selected or checked in the table on the maps.  Points whose
+    /// rows are hidden by the search or filters of the table are reported on the status line.
+    pub fn link_selection(&mut self) {
+        let picks = self.tab.take_picks();
+        let selections = self.tab.take_selections();
+        if let Some(table) = &mut self.lens.address_table {
+            for id in &picks {
+                table.focus_row(id);
+            }
+            for selection in &selections {
+                let (chosen, skipped) =
+                    table.select_rows(&selection.ids, selection.mode, selection.check);
+                if skipped > 0 {
+                    self.tab.report(&format!(
+                        "Selected {chosen} rows.  {skipped} more are hidden by the table search or filters."
+                    ));
+                }
+            }
+            self.tab.highlight(&table.marked());
+        }
+    }
+
     /// /// Process the key binding.
     /// pub fn process_key_binding(key: &str, mods: &ModifiersState) -> Option<Action> {
     ///     KEY_BINDINGS.iter().find_map(|binding| {
@@ -1,17 +1,22 @@
+use crate::mapped;
 use crate::prelude::{
-    load_bin, save, AddressPoint, AddressPoints, CommandMode, CommandTable, CommandView, EguiAct,
-    Panel, Parcels, TableConfig, TableView, Tree,
+    load_bin, save, AddressPoint, AddressPoints, Catalog, CommandMode, CommandTable, CommandView,
+    EguiAct, LayerStack, Panel, Parcels, TableConfig, TableLayouts, TableView, Tree,
 };
 use derive_more::{Deref, DerefMut};
 use egui::{Context, Id, TextStyle};
 use polite::Polite;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct Lens {
     pub addresses: Option<AddressPoints>,
+    /// Locations of the datasets read at startup.
+    #[serde(skip, default = "Catalog::open")]
+    pub catalog: Catalog,
     pub address_table: Option<TableView<AddressPoints, AddressPoint, String>>,
     pub counter: i32,
     /// Command view window.
@@ -19,6 +24,10 @@ pub struct Lens {
     pub focus_tree: Tree,
     pub focus_counter: bool,
     pub focus_parcels: bool,
+    /// Column settings of each table, saved with the session.
+    pub layouts: TableLayouts,
+    /// Layer panel of each map tab, by tab identifier, saved with the session.
+    pub map_layers: HashMap<String, LayerStack>,
     pub panel: Option<Panel<AddressPoint>>,
     pub parcels: Option<Arc<Parcels>>,
     pub enter: Option<()>,
@@ -35,9 +44,10 @@ impl Lens {
         //     }
         // };
 
+        let catalog = Catalog::open();
         let mut panel = None;
         let mut address_table = None;
-        let addresses = match AddressPoints::load("data/addresses.data") {
+        let addresses = match AddressPoints::load(&catalog.addresses) {
             Ok(data) => {
                 // panel = Some(Panel::new(data.records.clone()));
                 let config = TableConfig::new()
@@ -45,8 +55,13 @@ impl Lens {
                     .resizable()
                     .with_search()
                     .striped()
-                    .with_slider();
-                address_table = Some(TableView::with_config(data.clone(), config));
+                    .with_slider()
+                    .with_chooser()
+                    .with_filter_row()
+                    .with_editing();
+                let mut table = TableView::with_config(data.clone(), config);
+                table.with_name("addresses");
+                address_table = Some(table);
                 tracing::info!("Records read: {}", data.records.len());
                 Some(data)
             }
@@ -56,7 +71,7 @@ impl Lens {
             }
         };
 
-        let parcels = match Parcels::load("data/parcels.data") {
+        let parcels = match Parcels::load(&catalog.parcels) {
             Ok(data) => Some(Arc::new(data)),
             Err(_) => None,
         };
@@ -67,18 +82,53 @@ impl Lens {
 
         Self {
             addresses,
+            catalog,
             address_table,
             counter: Default::default(),
             command_view,
             focus_tree: Tree::new(),
             focus_counter: true,
             focus_parcels: true,
+            layouts: TableLayouts::default(),
+            map_layers: HashMap::new(),
             panel,
             parcels,
             enter: None,
         }
     }
 
+    /// Saves the column settings of each table to `layouts`, before saving the session.
+    pub fn store_layouts(&mut self) {
+        if let Some(table) = &self.address_table {
+            table.store_layout(&mut self.layouts);
+        }
+    }
+
+    /// Saves edits made in the address table to the address dataset, if there are any.  Keeps
+    /// the format of the dataset on disk, so a mapped dataset is saved mapped.
+    pub fn save_edits(&mut self) -> Polite<()> {
+        if let Some(table) = &mut self.address_table {
+            if table.dirty {
+                let path = &self.catalog.addresses;
+                match mapped::is_mapped(path).unwrap_or(false) {
+                    true => table.data.save_mapped(path)?,
+                    false => table.data.save(path)?,
+                }
+                self.addresses = Some(table.data.clone());
+                table.dirty = false;
+                tracing::info!("Address edits saved.");
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the column settings saved in `layouts` to each table, after loading the session.
+    pub fn restore_layouts(&mut self) {
+        if let Some(table) = &mut self.address_table {
+            table.restore_layout(&self.layouts);
+        }
+    }
+
     pub fn in_focus(&mut self, id: Id) -> bool {
         if let Some(focus) = self.focus_tree.select {
             focus == id
@@ -115,6 +165,16 @@ impl Lens {
                     table.select_previous();
                 }
             }
+            EguiAct::ExtendNextRow => {
+                if let Some(table) = &mut self.address_table {
+                    table.extend_next();
+                }
+            }
+            EguiAct::ExtendPreviousRow => {
+                if let Some(table) = &mut self.address_table {
+                    table.extend_previous();
+                }
+            }
             EguiAct::FocusedLeaf => {
                 tracing::info!("Taking no action.");
             }
@@ -296,7 +356,10 @@ impl Lens {
                     num_rows,
                     |ui, row_range| {
                         for row in row_range {
-                            let record = &data.records[row].owner;
+                            let Some(parcel) = data.records.row(row) else {
+                                continue;
+                            };
+                            let record = &parcel.owner;
                             let name = if let Some(val) = &record.name {
                                 val.clone()
                             } else {
@@ -0,0 +1,178 @@
+//! The `summary` module groups the rows of a table by the values of one or more columns, and
+//! computes counts and ranges for each group.
+use crate::prelude::{Cell, Columnar};
+use polite::Polite;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::Path;
+
+/// The `ColumnStats` struct holds statistics for one column within a [`Group`].
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ColumnStats {
+    /// Number of distinct non-empty values.
+    pub distinct: usize,
+    /// Least value of a numeric column.
+    pub min: Option<f64>,
+    /// Greatest value of a numeric column.
+    pub max: Option<f64>,
+}
+
+/// The `Group` struct holds the rows sharing the same values in the grouped columns.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Group {
+    /// Values of the grouped columns, in the order of [`Summary::by`].
+    pub keys: Vec<Cell>,
+    /// Number of rows in the group.
+    pub count: usize,
+    /// Statistics of each column in the table, by column index.
+    pub stats: Vec<ColumnStats>,
+}
+
+/// The `Summary` struct groups rows by the columns in `by`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Summary {
+    /// Indices of the grouped columns.
+    pub by: Vec<usize>,
+    /// Names of every column in the table.
+    pub headers: Vec<String>,
+    /// Indices of the columns holding numbers, which report a minimum and maximum.
+    pub numeric: Vec<usize>,
+    /// Groups in order of their keys.
+    pub groups: Vec<Group>,
+}
+
+impl Summary {
+    /// Groups `rows` by the columns in `by`.  Each column in `headers` reports a count of its
+    /// distinct values, and columns holding numbers also report a minimum and maximum.
+    pub fn new<'a, T: Columnar + 'a, I: IntoIterator<Item = &'a T>>(
+        rows: I,
+        by: &[usize],
+        headers: Vec<String>,
+    ) -> Self {
+        let width = headers.len();
+        // Group key and distinct values of each column, by the display text of the key.
+        let mut groups: HashMap<Vec<String>, (Group, Vec<HashSet<String>>)> = HashMap::new();
+        let mut numeric = vec![false; width];
+        for row in rows {
+            let cells = row.cells();
+            let keys = by
+                .iter()
+                .map(|v| cells.get(*v).cloned().unwrap_or_default())
+                .collect::<Vec<Cell>>();
+            let text = keys.iter().map(|v| v.to_string()).collect::<Vec<String>>();
+            let (group, values) = groups.entry(text).or_insert_with(|| {
+                let group = Group {
+                    keys,
+                    count: 0,
+                    stats: vec![ColumnStats::default(); width],
+                };
+                (group, vec![HashSet::new(); width])
+            });
+            group.count += 1;
+            for (column, cell) in cells.iter().enumerate().take(width) {
+                if cell.is_empty() {
+                    continue;
+                }
+                values[column].insert(cell.to_string());
+                if let Some(value) = cell.as_f64() {
+                    numeric[column] = true;
+                    let stats = &mut group.stats[column];
+                    stats.min = Some(stats.min.map_or(value, |v| v.min(value)));
+                    stats.max = Some(stats.max.map_or(value, |v| v.max(value)));
+                }
+            }
+        }
+        let mut groups = groups
+            .into_values()
+            .map(|(mut group, values)| {
+                for (stats, values) in group.stats.iter_mut().zip(values) {
+                    stats.distinct = values.len();
+                }
+                group
+            })
+            .collect::<Vec<Group>>();
+        groups.sort_by(|a, b| {
+            a.keys
+                .iter()
+                .zip(&b.keys)
+                .map(|(a, b)| a.compare(b))
+                .find(|v| *v != Ordering::Equal)
+                .unwrap_or(Ordering::Equal)
+        });
+        Self {
+            by: by.to_vec(),
+            headers,
+            numeric: numeric
+                .into_iter()
+                .enumerate()
+                .filter(|(_, v)| *v)
+                .map(|(i, _)| i)
+                .collect(),
+            groups,
+        }
+    }
+
+    /// Total number of rows across the groups.
+    pub fn count(&self) -> usize {
+        self.groups.iter().map(|v| v.count).sum()
+    }
+
+    /// Names of the columns written by [`Summary::to_csv`].
+    pub fn columns(&self) -> Vec<String> {
+        let mut columns = self
+            .by
+            .iter()
+            .map(|v| self.headers.get(*v).cloned().unwrap_or_default())
+            .collect::<Vec<String>>();
+        columns.push("Count".to_string());
+        for (column, header) in self.headers.iter().enumerate() {
+            if self.by.contains(&column) {
+                continue;
+            }
+            columns.push(format!("{header} Distinct"));
+            if self.numeric.contains(&column) {
+                columns.push(format!("{header} Min"));
+                columns.push(format!("{header} Max"));
+            }
+        }
+        columns
+    }
+
+    /// Values of `group` in the order of [`Summary::columns`].
+    pub fn values(&self, group: &Group) -> Vec<String> {
+        let mut values = group
+            .keys
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<String>>();
+        values.push(group.count.to_string());
+        for (column, stats) in group.stats.iter().enumerate() {
+            if self.by.contains(&column) {
+                continue;
+            }
+            values.push(stats.distinct.to_string());
+            if self.numeric.contains(&column) {
+                let text = |v: Option<f64>| v.map(|v| v.to_string()).unwrap_or_default();
+                values.push(text(stats.min));
+                values.push(text(stats.max));
+            }
+        }
+        values
+    }
+
+    /// Writes the summary to a CSV file at `path`, with one record per group.
+    pub fn to_csv<P: AsRef<Path>>(&self, path: P) -> Polite<()> {
+        let mut writer = csv::Writer::from_path(path).map_err(io::Error::from)?;
+        writer
+            .write_record(self.columns())
+            .map_err(io::Error::from)?;
+        for group in &self.groups {
+            writer
+                .write_record(self.values(group))
+                .map_err(io::Error::from)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
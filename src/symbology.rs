@@ -0,0 +1,778 @@
+//! The `symbology` module holds the rules for styling map features, read from a TOML file.
+//!
+//! Each [`LayerKind`] has a [`LayerStyle`], with a default [`Style`] and an optional [`Rule`]
+//! choosing the style of each feature from the value of one of its columns.  A categorized rule
+//! matches values exactly, and a graduated rule matches numeric values within a range.  The same
+//! rules draw the features and the entries of the map legend.  An optional [`LabelStyle`] sets
+//! the text labels of the features.
+use crate::map::MAX_ZOOM;
+use crate::prelude::{Cell, LayerKind};
+use polite::Polite;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::{fmt, fs, io};
+
+/// Default location of the symbology config.
+pub const SYMBOLOGY_PATH: &str = "data/symbology.toml";
+
+/// Colors given to the categories created by [`Rule::categorize`], in order.
+pub const PALETTE: [&str; 10] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#7f7f7f",
+    "#bcbd22", "#17becf",
+];
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// The `HexColor` struct is an RGBA color, written in TOML as `#rrggbb` or `#rrggbbaa`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct HexColor(pub [u8; 4]);
+
+impl HexColor {
+    pub const TRANSPARENT: Self = Self([0, 0, 0, 0]);
+
+    pub fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self([r, g, b, 255])
+    }
+
+    /// Returns the color `t` of the way from `self` to `other`, for ramps between two colors.
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let mut rgba = [0; 4];
+        for (i, v) in rgba.iter_mut().enumerate() {
+            *v = (self.0[i] as f32 + (other.0[i] as f32 - self.0[i] as f32) * t).round() as u8;
+        }
+        Self(rgba)
+    }
+
+    /// Returns the color with its alpha scaled by `opacity`.
+    pub fn with_opacity(&self, opacity: f32) -> Self {
+        let [r, g, b, a] = self.0;
+        Self([r, g, b, (a as f32 * opacity.clamp(0.0, 1.0)).round() as u8])
+    }
+
+    /// Returns the color as an `egui` color.
+    #[cfg(feature = "gui")]
+    pub fn color32(&self) -> egui::Color32 {
+        let [r, g, b, a] = self.0;
+        egui::Color32::from_rgba_unmultiplied(r, g, b, a)
+    }
+
+    /// Returns the color as a `galileo` color with its alpha scaled by `opacity`.
+    #[cfg(feature = "gui")]
+    pub fn color(&self, opacity: f32) -> galileo::Color {
+        let [r, g, b, a] = self.with_opacity(opacity).0;
+        galileo::Color::rgba(r, g, b, a)
+    }
+}
+
+impl std::str::FromStr for HexColor {
+    type Err = io::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let hex = value.trim().trim_start_matches('#');
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|v| u8::from_str_radix(v, 16).ok())
+                .ok_or_else(|| invalid(format!("{value} is not a hex color.")))
+        };
+        match hex.len() {
+            6 => Ok(Self([channel(0)?, channel(2)?, channel(4)?, 255])),
+            8 => Ok(Self([channel(0)?, channel(2)?, channel(4)?, channel(6)?])),
+            _ => Err(invalid(format!("{value} is not a hex color."))),
+        }
+    }
+}
+
+impl TryFrom<String> for HexColor {
+    type Error = io::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<HexColor> for String {
+    fn from(color: HexColor) -> Self {
+        color.to_string()
+    }
+}
+
+impl fmt::Display for HexColor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let [r, g, b, a] = self.0;
+        match a {
+            255 => write!(f, "#{r:02x}{g:02x}{b:02x}"),
+            _ => write!(f, "#{r:02x}{g:02x}{b:02x}{a:02x}"),
+        }
+    }
+}
+
+/// The `Style` struct sets how a feature is drawn.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Style {
+    /// Color of points and polygon interiors.
+    pub fill: HexColor,
+    /// Color of point outlines and polygon edges.
+    pub stroke: HexColor,
+    /// Width of the stroke in pixels.  Zero draws no stroke.
+    pub stroke_width: f32,
+    /// Diameter of points in pixels.
+    pub size: f32,
+    /// Opacity from 0.0 to 1.0, applied with the opacity of the layer.
+    pub opacity: f32,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            fill: HexColor::rgb(0, 0, 255),
+            stroke: HexColor::TRANSPARENT,
+            stroke_width: 0.0,
+            size: 7.0,
+            opacity: 1.0,
+        }
+    }
+}
+
+impl Style {
+    pub fn fill(fill: &str) -> Self {
+        Self {
+            fill: fill.parse().unwrap_or(HexColor::TRANSPARENT),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_stroke(mut self, stroke: &str, width: f32) -> Self {
+        self.stroke = stroke.parse().unwrap_or(HexColor::TRANSPARENT);
+        self.stroke_width = width;
+        self
+    }
+}
+
+/// The `Category` struct styles the features with one value of the rule column.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Category {
+    pub value: String,
+    /// Text shown in the legend.  Defaults to the value.
+    #[serde(default)]
+    pub label: String,
+    #[serde(flatten)]
+    pub style: Style,
+}
+
+/// The `Class` struct styles the features with a value of the rule column from `min` to `max`,
+/// inclusive.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Class {
+    pub min: f64,
+    pub max: f64,
+    /// Text shown in the legend.  Defaults to the range.
+    #[serde(default)]
+    pub label: String,
+    #[serde(flatten)]
+    pub style: Style,
+}
+
+/// The `Rule` enum chooses the style of a feature from the value of `column`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Rule {
+    /// Styles each listed value of `column`.
+    Categorized {
+        column: String,
+        categories: Vec<Category>,
+    },
+    /// Styles ranges of the numeric values of `column`.
+    Graduated { column: String, classes: Vec<Class> },
+}
+
+impl Rule {
+    /// Returns a categorized rule on `column` giving each distinct value of `values` a color
+    /// from the [`PALETTE`], based on `style`.
+    pub fn categorize<I: IntoIterator<Item = Cell>>(
+        column: &str,
+        values: I,
+        style: &Style,
+    ) -> Self {
+        let mut distinct = values
+            .into_iter()
+            .filter(|v| !v.is_empty())
+            .map(|v| v.to_string())
+            .collect::<Vec<String>>();
+        distinct.sort();
+        distinct.dedup();
+        let categories = distinct
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| Category {
+                value,
+                label: String::new(),
+                style: Style {
+                    fill: PALETTE[i % PALETTE.len()]
+                        .parse()
+                        .unwrap_or(HexColor::TRANSPARENT),
+                    ..style.clone()
+                },
+            })
+            .collect();
+        Self::Categorized {
+            column: column.to_string(),
+            categories,
+        }
+    }
+
+    /// Returns a graduated rule on `column` dividing the range of the numeric `values` into
+    /// `count` classes of equal width, shaded from the fill of `style` to `to`.
+    pub fn graduate<I: IntoIterator<Item = Cell>>(
+        column: &str,
+        values: I,
+        count: usize,
+        style: &Style,
+        to: HexColor,
+    ) -> Self {
+        let values = values
+            .into_iter()
+            .filter_map(|v| numeric(&v))
+            .collect::<Vec<f64>>();
+        let count = count.max(1);
+        let mut classes = Vec::new();
+        if let (Some(min), Some(max)) = (
+            values.iter().copied().reduce(f64::min),
+            values.iter().copied().reduce(f64::max),
+        ) {
+            let width = (max - min) / count as f64;
+            for i in 0..count {
+                let t = match count {
+                    1 => 1.0,
+                    _ => i as f32 / (count - 1) as f32,
+                };
+                classes.push(Class {
+                    min: min + width * i as f64,
+                    max: match i + 1 == count {
+                        true => max,
+                        false => min + width * (i + 1) as f64,
+                    },
+                    label: String::new(),
+                    style: Style {
+                        fill: style.fill.lerp(&to, t),
+                        ..style.clone()
+                    },
+                });
+            }
+        }
+        Self::Graduated {
+            column: column.to_string(),
+            classes,
+        }
+    }
+
+    pub fn column(&self) -> &str {
+        match self {
+            Self::Categorized { column, .. } | Self::Graduated { column, .. } => column,
+        }
+    }
+}
+
+/// Returns the value of a numeric cell, or of a text cell holding a number.
+fn numeric(cell: &Cell) -> Option<f64> {
+    cell.as_f64()
+        .or_else(|| cell.to_string().trim().parse::<f64>().ok())
+}
+
+/// The `Attributed` trait looks up the values of a feature by column name, so rules can style
+/// features on any of their columns.
+pub trait Attributed {
+    /// Names of the columns of the feature.
+    fn columns() -> Vec<String>;
+    /// Returns the value of `column`, or [`Cell::Empty`] if the feature has no such column.
+    fn attribute(&self, column: &str) -> Cell;
+}
+
+/// The `LabelStyle` struct sets the text drawn by each feature and the zoom levels showing it.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LabelStyle {
+    /// Column holding the text of the label.
+    pub column: String,
+    /// Least zoom level at which labels are drawn.
+    pub min_zoom: f64,
+    /// Greatest zoom level at which labels are drawn.
+    pub max_zoom: f64,
+    /// Height of the text in points.
+    pub size: f32,
+    pub color: HexColor,
+    /// Color of the outline around the text, keeping it legible over the features beneath.
+    pub halo: HexColor,
+    /// Width of the halo in points.  Zero draws no halo.
+    pub halo_width: f32,
+}
+
+impl Default for LabelStyle {
+    fn default() -> Self {
+        Self {
+            column: String::new(),
+            min_zoom: 17.0,
+            max_zoom: MAX_ZOOM,
+            size: 12.0,
+            color: HexColor::rgb(0x20, 0x20, 0x20),
+            halo: HexColor::rgb(0xff, 0xff, 0xff),
+            halo_width: 1.5,
+        }
+    }
+}
+
+impl LabelStyle {
+    /// Labels features with the values of `column`, shown from `min_zoom`.
+    pub fn new(column: &str, min_zoom: f64) -> Self {
+        Self {
+            column: column.to_string(),
+            min_zoom,
+            ..Default::default()
+        }
+    }
+
+    /// Returns `true` if labels are drawn at `zoom`.
+    pub fn shown_at(&self, zoom: f64) -> bool {
+        zoom >= self.min_zoom && zoom <= self.max_zoom
+    }
+}
+
+/// The `LayerStyle` struct holds the symbology of one kind of layer.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LayerStyle {
+    /// Style of the features matched by no rule.
+    pub default: Style,
+    pub rule: Option<Rule>,
+    /// Labels of the features, if any.
+    pub label: Option<LabelStyle>,
+}
+
+impl LayerStyle {
+    /// Returns the style of `feature`.
+    pub fn style_for<T: Attributed>(&self, feature: &T) -> &Style {
+        match &self.rule {
+            Some(Rule::Categorized { column, categories }) => {
+                let value = feature.attribute(column).to_string();
+                categories
+                    .iter()
+                    .find(|v| v.value == value)
+                    .map(|v| &v.style)
+                    .unwrap_or(&self.default)
+            }
+            Some(Rule::Graduated { column, classes }) => numeric(&feature.attribute(column))
+                .and_then(|value| classes.iter().find(|v| value >= v.min && value <= v.max))
+                .map(|v| &v.style)
+                .unwrap_or(&self.default),
+            None => &self.default,
+        }
+    }
+
+    /// Returns the label and style of each entry in the legend, starting with the rule entries.
+    /// The default style is labeled `All other values` when a rule is set, so the entry is not
+    /// mistaken for a category named `Other`.
+    pub fn legend(&self) -> Vec<(String, Style)> {
+        let mut entries = match &self.rule {
+            Some(Rule::Categorized { categories, .. }) => categories
+                .iter()
+                .map(|v| match v.label.is_empty() {
+                    true => (v.value.clone(), v.style.clone()),
+                    false => (v.label.clone(), v.style.clone()),
+                })
+                .collect(),
+            Some(Rule::Graduated { classes, .. }) => classes
+                .iter()
+                .map(|v| match v.label.is_empty() {
+                    true => (format!("{} to {}", v.min, v.max), v.style.clone()),
+                    false => (v.label.clone(), v.style.clone()),
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+        let label = match self.rule {
+            Some(_) => "All other values",
+            None => "",
+        };
+        entries.push((label.to_string(), self.default.clone()));
+        entries
+    }
+}
+
+/// The `Symbology` struct holds the [`LayerStyle`] of each [`LayerKind`].
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Symbology {
+    pub addresses: LayerStyle,
+    pub parcels: LayerStyle,
+    pub matches: LayerStyle,
+    pub imported: LayerStyle,
+}
+
+impl Default for Symbology {
+    /// Address and match points colored by status, and outlined parcels.  Addresses are labeled
+    /// with their numbers and parcels with their map numbers at street level.
+    fn default() -> Self {
+        let categories = |column: &str, values: &[(&str, &str)]| {
+            Some(Rule::Categorized {
+                column: column.to_string(),
+                categories: values
+                    .iter()
+                    .map(|(value, fill)| Category {
+                        value: value.to_string(),
+                        label: String::new(),
+                        style: Style::fill(fill),
+                    })
+                    .collect(),
+            })
+        };
+        Self {
+            addresses: LayerStyle {
+                default: Style::fill("#0000ff"),
+                rule: categories(
+                    "Status",
+                    &[
+                        ("Current", "#0000ff"),
+                        ("Other", "#dbc200"),
+                        ("Pending", "#db00d4"),
+                        ("Temporary", "#db6e00"),
+                        ("Retired", "#ad0000"),
+                        ("Virtual", "#32a852"),
+                    ],
+                ),
+                label: Some(LabelStyle::new("Number", 17.0)),
+            },
+            matches: LayerStyle {
+                default: Style::fill("#0000ff"),
+                rule: categories(
+                    "Status",
+                    &[
+                        ("Matching", "#0000ff"),
+                        ("Divergent", "#dbc200"),
+                        ("Missing", "#ad0000"),
+                    ],
+                ),
+                label: None,
+            },
+            parcels: LayerStyle {
+                default: Style::fill("#e8e4d8").with_stroke("#8c8672", 1.0),
+                rule: None,
+                label: Some(LabelStyle::new("Map number", 16.0)),
+            },
+            imported: LayerStyle {
+                default: Style::fill("#c9dcea").with_stroke("#4f7c9c", 1.0),
+                rule: None,
+                label: None,
+            },
+        }
+    }
+}
+
+impl Symbology {
+    /// Reads the symbology from the TOML file at `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Polite<Self> {
+        let text = fs::read_to_string(path)?;
+        let symbology = toml::from_str(&text).map_err(|e| invalid(e.to_string()))?;
+        Ok(symbology)
+    }
+
+    /// Writes the symbology to a TOML file at `path`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Polite<()> {
+        let text = toml::to_string_pretty(self).map_err(|e| invalid(e.to_string()))?;
+        fs::write(path, text)?;
+        Ok(())
+    }
+
+    /// Reads the symbology at [`SYMBOLOGY_PATH`], or returns the default symbology if it cannot
+    /// be read.
+    pub fn open() -> Self {
+        match Self::load(SYMBOLOGY_PATH) {
+            Ok(symbology) => symbology,
+            Err(e) => {
+                tracing::info!("Using default symbology: {}", e.to_string());
+                Self::default()
+            }
+        }
+    }
+
+    pub fn style(&self, kind: LayerKind) -> &LayerStyle {
+        match kind {
+            LayerKind::Addresses => &self.addresses,
+            LayerKind::Parcels => &self.parcels,
+            LayerKind::Matches => &self.matches,
+            LayerKind::Imported => &self.imported,
+        }
+    }
+
+    pub fn style_mut(&mut self, kind: LayerKind) -> &mut LayerStyle {
+        match kind {
+            LayerKind::Addresses => &mut self.addresses,
+            LayerKind::Parcels => &mut self.parcels,
+            LayerKind::Matches => &mut self.matches,
+            LayerKind::Imported => &mut self.imported,
+        }
+    }
+}
+
+/// The `point_paint` function returns the paint of a circle drawn with `style`, with its opacity
+/// scaled by the layer `opacity`.
+#[cfg(feature = "gui")]
+pub fn point_paint<'a>(
+    style: &Style,
+    opacity: f32,
+) -> galileo::render::point_paint::PointPaint<'a> {
+    let opacity = style.opacity * opacity;
+    let paint =
+        galileo::render::point_paint::PointPaint::circle(style.fill.color(opacity), style.size);
+    match style.stroke_width > 0.0 {
+        true => paint.with_outline(style.stroke.color(opacity), style.stroke_width),
+        false => paint,
+    }
+}
+
+/// The `swatch` function draws a sample of `style` for the legend, a circle for points or a
+/// square for polygons.
+#[cfg(feature = "gui")]
+pub fn swatch(ui: &mut egui::Ui, style: &Style, point: bool) -> egui::Response {
+    let size = egui::vec2(16.0, 16.0);
+    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::hover());
+    let fill = style.fill.color32().gamma_multiply(style.opacity);
+    let stroke = egui::Stroke::new(style.stroke_width, style.stroke.color32());
+    match point {
+        true => {
+            let radius = (style.size / 2.0).clamp(2.0, 8.0);
+            ui.painter().circle(rect.center(), radius, fill, stroke);
+        }
+        false => {
+            ui.painter().rect(rect.shrink(2.0), 0.0, fill, stroke);
+        }
+    }
+    response
+}
+
+/// The `color_edit` function edits `color` with a color picker.
+#[cfg(feature = "gui")]
+fn color_edit(ui: &mut egui::Ui, color: &mut HexColor) {
+    let mut edit = color.color32();
+    if ui.color_edit_button_srgba(&mut edit).changed() {
+        *color = HexColor(edit.to_srgba_unmultiplied());
+    }
+}
+
+#[cfg(feature = "gui")]
+impl Style {
+    /// The `editor` method edits the style on one line of a grid.
+    pub fn editor(&mut self, ui: &mut egui::Ui) {
+        color_edit(ui, &mut self.fill);
+        color_edit(ui, &mut self.stroke);
+        ui.add(
+            egui::DragValue::new(&mut self.stroke_width)
+                .clamp_range(0.0..=10.0)
+                .speed(0.1),
+        );
+        ui.add(
+            egui::DragValue::new(&mut self.size)
+                .clamp_range(1.0..=40.0)
+                .speed(0.1),
+        );
+        ui.add(
+            egui::DragValue::new(&mut self.opacity)
+                .clamp_range(0.0..=1.0)
+                .speed(0.01),
+        );
+    }
+}
+
+#[cfg(feature = "gui")]
+impl LayerStyle {
+    /// The `editor` method edits the default style and the rule.  The rule column is chosen from
+    /// `columns`, and `values` returns the values of a column in the data, to classify them.
+    pub fn editor<F: Fn(&str) -> Vec<Cell>>(
+        &mut self,
+        ui: &mut egui::Ui,
+        columns: &[String],
+        values: F,
+    ) {
+        let header = |ui: &mut egui::Ui, first: &str| {
+            ui.label(first);
+            for name in ["Fill", "Stroke", "Width", "Size", "Opacity"] {
+                ui.label(name);
+            }
+            ui.end_row();
+        };
+        egui::Grid::new(ui.id().with("default"))
+            .num_columns(6)
+            .show(ui, |ui| {
+                header(ui, "");
+                ui.label("Default");
+                self.default.editor(ui);
+                ui.end_row();
+            });
+        ui.separator();
+
+        let mut kind = match &self.rule {
+            None => 0,
+            Some(Rule::Categorized { .. }) => 1,
+            Some(Rule::Graduated { .. }) => 2,
+        };
+        let mut column = self
+            .rule
+            .as_ref()
+            .map(|v| v.column().to_string())
+            .unwrap_or_else(|| columns.first().cloned().unwrap_or_default());
+        let before = (kind, column.clone());
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut kind, 0, "Single");
+            ui.selectable_value(&mut kind, 1, "Categorized");
+            ui.selectable_value(&mut kind, 2, "Graduated");
+        });
+        if kind != 0 {
+            egui::ComboBox::from_id_source(ui.id().with("column"))
+                .selected_text(&column)
+                .show_ui(ui, |ui| {
+                    for name in columns {
+                        ui.selectable_value(&mut column, name.clone(), name);
+                    }
+                });
+        }
+        let classify = kind != 0 && ui.button("Classify").clicked();
+        if (kind, column.clone()) != before || classify {
+            self.rule = match kind {
+                1 => Some(Rule::categorize(&column, values(&column), &self.default)),
+                2 => Some(Rule::graduate(
+                    &column,
+                    values(&column),
+                    5,
+                    &self.default,
+                    self.default.fill.lerp(&HexColor::rgb(0, 0, 0), 0.7),
+                )),
+                _ => None,
+            };
+        }
+
+        let mut removed = None;
+        egui::Grid::new(ui.id().with("rule"))
+            .num_columns(7)
+            .striped(true)
+            .show(ui, |ui| match &mut self.rule {
+                Some(Rule::Categorized { categories, .. }) => {
+                    header(ui, "Value");
+                    for (index, category) in categories.iter_mut().enumerate() {
+                        ui.add(egui::TextEdit::singleline(&mut category.value).desired_width(80.0));
+                        category.style.editor(ui);
+                        ui.add(
+                            egui::TextEdit::singleline(&mut category.label)
+                                .hint_text("Label")
+                                .desired_width(80.0),
+                        );
+                        if ui.small_button("✖").clicked() {
+                            removed = Some(index);
+                        }
+                        ui.end_row();
+                    }
+                }
+                Some(Rule::Graduated { classes, .. }) => {
+                    header(ui, "Range");
+                    for (index, class) in classes.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.add(egui::DragValue::new(&mut class.min));
+                            ui.add(egui::DragValue::new(&mut class.max));
+                        });
+                        class.style.editor(ui);
+                        ui.add(
+                            egui::TextEdit::singleline(&mut class.label)
+                                .hint_text("Label")
+                                .desired_width(80.0),
+                        );
+                        if ui.small_button("✖").clicked() {
+                            removed = Some(index);
+                        }
+                        ui.end_row();
+                    }
+                }
+                None => {}
+            });
+        match (&mut self.rule, removed) {
+            (Some(Rule::Categorized { categories, .. }), Some(index)) => {
+                categories.remove(index);
+            }
+            (Some(Rule::Graduated { classes, .. }), Some(index)) => {
+                classes.remove(index);
+            }
+            _ => {}
+        }
+
+        ui.separator();
+        let mut labeled = self.label.is_some();
+        if ui.checkbox(&mut labeled, "Labels").changed() {
+            self.label = match labeled {
+                true => Some(LabelStyle::new(
+                    columns.first().map(|v| v.as_str()).unwrap_or_default(),
+                    LabelStyle::default().min_zoom,
+                )),
+                false => None,
+            };
+        }
+        if let Some(label) = &mut self.label {
+            label.editor(ui, columns);
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl LabelStyle {
+    /// The `editor` method edits the label column, zoom range, text and halo.
+    pub fn editor(&mut self, ui: &mut egui::Ui, columns: &[String]) {
+        egui::Grid::new(ui.id().with("label"))
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Column");
+                egui::ComboBox::from_id_source(ui.id().with("label_column"))
+                    .selected_text(&self.column)
+                    .show_ui(ui, |ui| {
+                        for name in columns {
+                            ui.selectable_value(&mut self.column, name.clone(), name);
+                        }
+                    });
+                ui.end_row();
+                ui.label("Zoom");
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::DragValue::new(&mut self.min_zoom)
+                            .clamp_range(0.0..=self.max_zoom)
+                            .speed(0.1),
+                    );
+                    ui.label("to");
+                    ui.add(
+                        egui::DragValue::new(&mut self.max_zoom)
+                            .clamp_range(self.min_zoom..=MAX_ZOOM)
+                            .speed(0.1),
+                    );
+                });
+                ui.end_row();
+                ui.label("Text");
+                ui.horizontal(|ui| {
+                    color_edit(ui, &mut self.color);
+                    ui.add(
+                        egui::DragValue::new(&mut self.size)
+                            .clamp_range(6.0..=48.0)
+                            .speed(0.1),
+                    );
+                });
+                ui.end_row();
+                ui.label("Halo");
+                ui.horizontal(|ui| {
+                    color_edit(ui, &mut self.halo);
+                    ui.add(
+                        egui::DragValue::new(&mut self.halo_width)
+                            .clamp_range(0.0..=5.0)
+                            .speed(0.1),
+                    );
+                });
+                ui.end_row();
+            });
+    }
+}
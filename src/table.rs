@@ -1,13 +1,35 @@
+#[cfg(feature = "gui")]
 use crate::prelude::Tree;
+use crate::search::SearchMode;
+#[cfg(feature = "gui")]
+use crate::search::{score_cells, search_rows, Matcher};
+#[cfg(feature = "gui")]
+use crate::summary::{Group, Summary};
+use chrono::NaiveDate;
+use derive_more::{Deref, DerefMut};
+#[cfg(feature = "gui")]
 use egui::{Align, Layout, Sense, Slider, Ui};
+#[cfg(feature = "gui")]
 use egui_extras::{Column, TableBuilder};
+#[cfg(feature = "gui")]
 use names::Generator;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::collections::HashSet;
+#[cfg(feature = "gui")]
 use std::marker::PhantomData;
+#[cfg(feature = "gui")]
+use strum::IntoEnumIterator;
 use uuid::Uuid;
 
+/// Number of rows read to find a sample cell of each column for the filter row.
+#[cfg(feature = "gui")]
+const KIND_SAMPLE: usize = 1000;
+
 /// The `TableView` struct contains data fields to implement GUI functionality on tabular data.
+#[cfg(feature = "gui")]
 #[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
 pub struct TableView<T: Tabular<U> + Filtration<T, V> + Clone, U: Columnar, V: Default> {
     /// Title to display for the table.
@@ -26,16 +48,54 @@ pub struct TableView<T: Tabular<U> + Filtration<T, V> + Clone, U: Columnar, V: D
     pub search: String,
     /// Tracks rows selected by the user in the table.
     pub selection: HashSet<Uuid>,
+    // Row where the last range selection started.
+    #[serde(skip)]
+    anchor: Option<Uuid>,
     /// The `enter` field tracks use of the enter key.
     pub enter: Option<()>,
-    /// Tracks checked boxes for rows using `row_ids`.
+    /// Tracks checked boxes for rows by row id.
     pub checks: HashMap<Uuid, bool>,
-    /// Tracks ordering button state in headings.
-    pub ord_flags: Vec<bool>,
-    /// Set to index of ord flags to refresh ordering of rows.
-    pub set_ord: Option<usize>,
+    /// Ordered list of columns used to sort the rows, saved with the table.
+    pub sort: SortSpec,
+    /// Set to `true` to reapply `sort` to the rows in `view`.
+    pub resort: bool,
     /// Holds filter selection for the filter widget.
     pub filter: Option<V>,
+    /// Undo and redo stacks of edits made to the table.
+    pub history: EditHistory,
+    /// Set when the rows in `data` have been edited since they were last saved.
+    pub dirty: bool,
+    /// Settings of the bulk edit panel.
+    pub bulk: BulkEdit,
+    // Shows the bulk edit panel.
+    #[serde(skip)]
+    bulk_open: bool,
+    // Edits previewed for the settings in `bulk`, and the settings used.
+    #[serde(skip)]
+    bulk_preview: Option<(BulkEdit, Vec<(String, Edit)>)>,
+    /// Columns grouped in the summary panel.
+    pub group_by: Vec<usize>,
+    /// Location of the CSV file written by the summary panel.
+    pub summary_export: String,
+    // Shows the summary panel.
+    #[serde(skip)]
+    summary_open: bool,
+    // Summary of the rows in the view, with the inputs used to compute it.
+    #[serde(skip)]
+    summary: Option<(Option<ViewKey>, Vec<usize>, Summary)>,
+    /// Filters set in the filter row, by column.  Combine with the `search` term.
+    pub column_filters: ColumnFilters,
+    // Cell being edited, as row id, column and the text entered.
+    #[serde(skip)]
+    editing: Option<(Uuid, usize, String)>,
+    // Message from the last edit that failed validation.
+    #[serde(skip)]
+    edit_error: Option<String>,
+    // Set to focus the entry of the cell being edited on the next frame.
+    #[serde(skip)]
+    focus_edit: bool,
+    // Text entered in the bounds of range filters, by column.
+    range_input: HashMap<usize, (String, String)>,
     /// Row target for the slider widget.
     pub target: usize,
     /// The current row in focus.  Used to hold the current row id in the focus tree.
@@ -44,18 +104,38 @@ pub struct TableView<T: Tabular<U> + Filtration<T, V> + Clone, U: Columnar, V: D
     pub row_focus: Option<Uuid>,
     // Current index associated with the id in `row_select`.
     row_index: Option<usize>,
-    // The uuid associated with each row.
-    row_ids: Vec<Uuid>,
     // Indicates if the focus tree has been loaded.
     loaded: bool,
     // Index of leaf ids for the data in `view`.
     leaves: Vec<egui::Id>,
+    // Counts changes to the rows in `view`, to invalidate `index`.
+    #[serde(skip)]
+    revision: u64,
+    // Counts edits, insertions, removals, undos and redos of the rows in `data`.
+    #[serde(skip)]
+    data_revision: u64,
+    // Inputs used to compute `index`.
+    #[serde(skip)]
+    key: Option<ViewKey>,
+    // Positions in `view` of the rows passing the search and filters, in display order.
+    #[serde(skip)]
+    index: Vec<usize>,
+    // Sample cell of each column, used to pick the input in the filter row.
+    #[serde(skip)]
+    kinds: Vec<Cell>,
+    // Width of each column drawn in the last frame, to tell user resizes from auto-sizing.
+    #[serde(skip)]
+    widths: HashMap<usize, f32>,
     // Marker to appease the type checker.
     phantom: PhantomData<U>,
 }
 
-impl<T: Tabular<U> + Default + Filtration<T, V> + Clone, U: Columnar + Default, V: Default>
-    TableView<T, U, V>
+#[cfg(feature = "gui")]
+impl<
+        T: Tabular<U> + Default + Filtration<T, V> + Clone,
+        U: Columnar + Default + Clone,
+        V: Default,
+    > TableView<T, U, V>
 {
     /// Creates a new table view of data `data` with the default configuration.
     pub fn new(data: T) -> Self {
@@ -64,14 +144,11 @@ impl<T: Tabular<U> + Default + Filtration<T, V> + Clone, U: Columnar + Default,
         let view = data.clone();
         // Each time we create a new view, package a clone for the GIS.
         let package = Some(data.clone());
-        let cols = T::headers().len();
-        let ord_flags = vec![false; cols];
         Self {
             name: String::new(),
             data,
             view,
             package,
-            ord_flags,
             ..Default::default()
         }
     }
@@ -85,8 +162,10 @@ impl<T: Tabular<U> + Default + Filtration<T, V> + Clone, U: Columnar + Default,
         &self.view
     }
 
-    /// The `view_mut` method provides a mutable reference to the `view` field.
+    /// The `view_mut` method provides a mutable reference to the `view` field, and marks the
+    /// cached rows as stale.
     pub fn view_mut(&mut self) -> &mut T {
+        self.refresh();
         &mut self.view
     }
 
@@ -98,38 +177,179 @@ impl<T: Tabular<U> + Default + Filtration<T, V> + Clone, U: Columnar + Default,
         &mut self.checks
     }
 
-    pub fn row_ids(&self) -> &Vec<Uuid> {
-        &self.row_ids
+    /// Ids of the rows passing the search and filters, in display order.  Reads every visible
+    /// row, so prefer [`TableView::row_id`] for a single row.
+    pub fn row_ids(&self) -> Vec<Uuid> {
+        (0..self.index.len())
+            .filter_map(|v| self.row_id(v))
+            .collect()
+    }
+
+    /// Id of the visible row at display position `position`.  Reads only that row from the view.
+    pub fn row_id(&self, position: usize) -> Option<Uuid> {
+        let row = self.view.row(*self.index.get(position)?)?;
+        Some(*row.id())
+    }
+
+    /// Display position of the visible row with id `id`, if the row passes the search and
+    /// filters.
+    fn row_position(&self, id: &Uuid) -> Option<usize> {
+        (0..self.index.len()).find(|v| self.row_id(*v).as_ref() == Some(id))
     }
 
     /// Creates a new `TableView` from `data` with configuration parameters `config`.
     pub fn with_config(data: T, config: TableConfig) -> Self {
         let view = data.clone();
         let package = Some(data.clone());
-        let mut cols = T::headers().len();
-        if config.checked {
-            cols += 1;
-        }
-        let ord_flags = vec![false; cols];
         Self {
             data,
             view,
             package,
             config,
-            ord_flags,
             ..Default::default()
         }
     }
 
-    /// Inserts the row index into the `selection` hash set if not present, removes it if present.
-    fn toggle_row_selection(&mut self, row_id: &Uuid, row_response: &egui::Response) {
-        if row_response.clicked() {
-            if self.selection.contains(row_id) {
-                self.selection.remove(row_id);
-            } else {
+    /// Sorts the rows in `view` by the columns in `spec`, and keeps `spec` for display in the
+    /// headers.
+    pub fn with_sort(&mut self, spec: SortSpec) {
+        self.sort = spec;
+        self.resort = true;
+    }
+
+    /// Updates the sort specification in response to a click on the header of column `column`.
+    /// A plain click sorts by that column alone, while a shift-click adds the column as the next
+    /// key.  Clicking a column already in the specification toggles its direction.
+    fn sort_clicked(&mut self, column: usize, shift: bool) {
+        if shift {
+            self.sort.push(column);
+        } else {
+            self.sort.set(column);
+        }
+        self.resort = true;
+        tracing::info!("Sort set to {:?}", self.sort);
+    }
+
+    /// Updates the selection in response to a click on row `row_id`.  A plain click selects the
+    /// row alone, a ctrl-click toggles the row, and a shift-click selects the rows between the
+    /// last row clicked and this one.  The clicked row takes the focus.
+    pub fn click_row(&mut self, row_id: &Uuid, modifiers: &egui::Modifiers) {
+        if modifiers.shift {
+            let anchor = self.anchor.unwrap_or(*row_id);
+            self.selection = self.range(&anchor, row_id).into_iter().collect();
+        } else if modifiers.command {
+            if !self.selection.remove(row_id) {
                 self.selection.insert(*row_id);
             }
+            self.anchor = Some(*row_id);
+        } else {
+            self.selection.clear();
+            self.selection.insert(*row_id);
+            self.anchor = Some(*row_id);
+        }
+        if let Some(index) = self.row_position(row_id) {
+            self.row_index = Some(index);
+            self.row_select = Some(*row_id);
+        }
+    }
+
+    /// Ids of the visible rows from `from` to `to`, inclusive, in display order.  Empty if either
+    /// row is not visible.
+    fn range(&self, from: &Uuid, to: &Uuid) -> Vec<Uuid> {
+        let start = self.row_position(from);
+        let end = self.row_position(to);
+        match (start, end) {
+            (Some(start), Some(end)) => (start.min(end)..=start.max(end))
+                .filter_map(|v| self.row_id(v))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Selects every visible row.
+    pub fn select_all(&mut self) {
+        self.selection.extend(self.row_ids());
+    }
+
+    /// Clears the selection.
+    pub fn select_none(&mut self) {
+        self.selection.clear();
+        self.anchor = None;
+    }
+
+    /// Selects the visible rows not selected, and deselects the rest.  Selected rows hidden by
+    /// the search or filters stay selected.
+    pub fn invert_selection(&mut self) {
+        for id in self.row_ids() {
+            if !self.selection.remove(&id) {
+                self.selection.insert(id);
+            }
+        }
+    }
+
+    /// Checks the box of each selected row.
+    pub fn check_selected(&mut self) {
+        for id in &self.selection {
+            self.checks.insert(*id, true);
+        }
+    }
+
+    /// Moves the focus to the next row and extends the selection from the anchor row to it.
+    /// Stays on the last row rather than wrapping to the first.
+    pub fn extend_next(&mut self) {
+        let anchor = self.anchor.or(self.current_row());
+        if self.row_index.is_some_and(|v| v + 1 < self.index.len()) {
+            self.select_next();
+        }
+        self.extend_to(anchor);
+    }
+
+    /// Moves the focus to the previous row and extends the selection from the anchor row to it.
+    /// Stays on the first row rather than wrapping to the last.
+    pub fn extend_previous(&mut self) {
+        let anchor = self.anchor.or(self.current_row());
+        if self.row_index.is_some_and(|v| v > 0) {
+            self.select_previous();
         }
+        self.extend_to(anchor);
+    }
+
+    fn extend_to(&mut self, anchor: Option<Uuid>) {
+        if let (Some(anchor), Some(current)) = (anchor, self.current_row()) {
+            self.selection = self.range(&anchor, &current).into_iter().collect();
+            self.anchor = Some(anchor);
+        }
+    }
+
+    /// Shows the selection menu and the counts of selected, checked and visible rows.
+    fn selection_panel(&mut self, ui: &mut Ui) {
+        ui.menu_button("Select", |ui| {
+            if ui.button("All").clicked() {
+                self.select_all();
+                ui.close_menu();
+            }
+            if ui.button("None").clicked() {
+                self.select_none();
+                ui.close_menu();
+            }
+            if ui.button("Invert").clicked() {
+                self.invert_selection();
+                ui.close_menu();
+            }
+            ui.separator();
+            if ui.button("Check selected").clicked() {
+                self.check_selected();
+                ui.close_menu();
+            }
+        });
+        let checked = self.checks.values().filter(|v| **v).count();
+        ui.label(format!(
+            "{} selected, {} checked, {} of {} rows",
+            self.selection.len(),
+            checked,
+            self.index.len(),
+            self.view.len()
+        ));
     }
 
     /// Add search widget to table.
@@ -142,6 +362,28 @@ impl<T: Tabular<U> + Default + Filtration<T, V> + Clone, U: Columnar + Default,
                 if clear.clicked() {
                     self.search = Default::default();
                 };
+                egui::ComboBox::from_id_source("search_mode")
+                    .selected_text(self.config.search_mode.to_string())
+                    .show_ui(ui, |ui| {
+                        for mode in SearchMode::iter() {
+                            ui.selectable_value(
+                                &mut self.config.search_mode,
+                                mode,
+                                mode.to_string(),
+                            );
+                        }
+                    });
+                ui.toggle_value(&mut self.config.case_sensitive, "Aa")
+                    .on_hover_text("Match case.");
+                // Report a search term that does not compile as a regular expression.
+                if let Err(e) = Matcher::new(
+                    &self.search,
+                    self.config.search_mode,
+                    self.config.case_sensitive,
+                ) {
+                    ui.colored_label(ui.visuals().error_fg_color, "Invalid pattern")
+                        .on_hover_text(e.to_string());
+                }
 
                 // if !self.loaded {
                 //     let entry_id = self.tree.leaf(entry.id);
@@ -154,6 +396,57 @@ impl<T: Tabular<U> + Default + Filtration<T, V> + Clone, U: Columnar + Default,
         }
     }
 
+    /// The `column_chooser` method adds a menu to show, hide and reorder columns, if enabled in
+    /// the [`TableConfig`].
+    pub fn column_chooser(&mut self, ui: &mut Ui) {
+        if !self.config.chooser {
+            return;
+        }
+        let headers = T::headers();
+        ui.menu_button("Columns", |ui| {
+            let order = self
+                .config
+                .columns
+                .iter()
+                .map(|v| v.index)
+                .collect::<Vec<usize>>();
+            for (position, column) in order.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    let mut visible = self.config.is_visible(*column);
+                    if ui.checkbox(&mut visible, &headers[*column]).changed() {
+                        self.config.set_visible(*column, visible);
+                    }
+                    if position > 0 && ui.small_button("⏶").clicked() {
+                        self.config.move_column(*column, order[position - 1]);
+                    }
+                    if position + 1 < order.len() && ui.small_button("⏷").clicked() {
+                        self.config.move_column(*column, order[position + 1]);
+                    }
+                });
+            }
+            ui.separator();
+            if ui.button("Reset").clicked() {
+                self.config.columns.clear();
+                self.config.fit(headers.len());
+            }
+        });
+    }
+
+    /// Saves the config of the table in `layouts` under the name of the table.  Tables without a
+    /// name are not saved.
+    pub fn store_layout(&self, layouts: &mut TableLayouts) {
+        if !self.name.is_empty() {
+            layouts.insert(self.name.clone(), self.config.clone());
+        }
+    }
+
+    /// Restores the config of the table from `layouts`, if saved under the name of the table.
+    pub fn restore_layout(&mut self, layouts: &TableLayouts) {
+        if let Some(config) = layouts.get(&self.name) {
+            self.config = config.clone();
+        }
+    }
+
     /// Change table configuration to enable the search widget.
     pub fn searchable(&mut self) -> &mut Self {
         self.config.search = true;
@@ -222,49 +515,528 @@ impl<T: Tabular<U> + Default + Filtration<T, V> + Clone, U: Columnar + Default,
         self.leaves = leaves;
     }
 
-    /// UI display for the table view.
-    pub fn table(&mut self, ui: &mut Ui) {
-        // Each row contains a string value for each column in the table.
-        let mut rows = self.view.rows();
-        if !self.search.is_empty() {
-            // the subset of rows containing the search term in any column
-            rows = self.contains(&self.search);
+    /// The `edit` method writes each `(id, column, value)` in `changes` to the rows in `data`
+    /// and `view`, and records the changes as a single step on the undo stack.  The changes are
+    /// validated by [`Tabular::set_value`], and none are kept if any is invalid.
+    pub fn edit(&mut self, changes: &[(Uuid, usize, String)]) -> Result<(), EditError> {
+        let step = apply_edits(&mut self.data, changes)?;
+        self.sync_view(&step);
+        self.history.record(step);
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// The `insert_rows` method adds `rows` to the end of the table as a single step on the undo
+    /// stack.  The rows are validated by [`Tabular::insert_row`], and none are kept if any is
+    /// invalid.
+    pub fn insert_rows(&mut self, rows: &[U]) -> Result<(), EditError> {
+        let step = insert_rows(&mut self.data, rows)?;
+        self.sync_view(&step);
+        self.history.record(step);
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// The `remove_rows` method removes the rows with an id in `ids` as a single step on the undo
+    /// stack, and drops them from the selection and checks.
+    pub fn remove_rows(&mut self, ids: &[Uuid]) -> Result<(), EditError> {
+        let step = remove_rows(&mut self.data, ids)?;
+        for id in ids {
+            self.selection.remove(id);
+            self.checks.remove(id);
+        }
+        self.sync_view(&step);
+        self.history.record(step);
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Counts changes to the rows of the table, such as edits, sorts or undos, so other views
+    /// of the rows know when to update.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Counts changes to the values of the rows in `data`, such as edits or undos, but not
+    /// sorts or searches, so other views of the data know when to copy it again.
+    pub fn data_revision(&self) -> u64 {
+        self.data_revision
+    }
+
+    /// Reverses the last step of edits.  Returns the error if the step cannot be undone, and
+    /// leaves the rows unchanged.
+    pub fn undo(&mut self) -> Result<(), EditError> {
+        let before = self.history.undo.last().cloned().unwrap_or_default();
+        if !self.history.undo(&mut self.data)?.is_empty() {
+            let inverse = before
+                .iter()
+                .rev()
+                .map(|v| v.inverse())
+                .collect::<Vec<Edit>>();
+            self.sync_view(&inverse);
+            self.dirty = true;
+        }
+        Ok(())
+    }
+
+    /// Reapplies the last step of edits undone.  Returns the error if the step cannot be redone,
+    /// and leaves the rows unchanged.
+    pub fn redo(&mut self) -> Result<(), EditError> {
+        let after = self.history.redo.last().cloned().unwrap_or_default();
+        if !self.history.redo(&mut self.data)?.is_empty() {
+            self.sync_view(&after);
+            self.dirty = true;
+        }
+        Ok(())
+    }
+
+    /// Writes `edits` to `view` and `package`, which hold copies of the rows in `data`.  Rows
+    /// missing from a filtered view are skipped.  Rows added go to the end of the copies, since the
+    /// copies may be sorted in another order.
+    fn sync_view(&mut self, edits: &[Edit]) {
+        for edit in edits {
+            let edit = match edit.kind {
+                EditKind::Insert => Edit {
+                    column: usize::MAX,
+                    ..edit.clone()
+                },
+                _ => edit.clone(),
+            };
+            let _ = edit.write(&mut self.view);
+            if let Some(package) = &mut self.package {
+                let _ = edit.write(package);
+            }
+        }
+        self.data_revision += 1;
+        self.refresh();
+    }
+
+    /// Shows the undo and redo buttons, and the message from the last invalid edit.
+    fn edit_panel(&mut self, ui: &mut Ui) {
+        if !self.config.editable {
+            return;
+        }
+        if ui
+            .add_enabled(self.history.can_undo(), egui::Button::new("Undo"))
+            .clicked()
+        {
+            self.edit_error = self.undo().err().map(|e| format!("Could not undo: {}", e));
+        }
+        if ui
+            .add_enabled(self.history.can_redo(), egui::Button::new("Redo"))
+            .clicked()
+        {
+            self.edit_error = self.redo().err().map(|e| format!("Could not redo: {}", e));
+        }
+        // Errors from editing a cell show beside the cell instead.
+        if let (Some(message), None) = (&self.edit_error, &self.editing) {
+            ui.colored_label(ui.visuals().error_fg_color, message);
+        }
+        ui.toggle_value(&mut self.bulk_open, "Bulk edit");
+        if self.dirty {
+            ui.label("Unsaved changes");
+        }
+    }
+
+    /// Returns the ids of the rows checked or selected in the table.
+    pub fn marked(&self) -> HashSet<Uuid> {
+        let mut marked = self.marked_checks();
+        marked.extend(self.selection.iter().copied());
+        marked
+    }
+
+    /// The `bulk_edit` method applies the settings in `bulk` to the rows in scope, as a single
+    /// step on the undo stack.  Returns the number of rows changed.
+    pub fn bulk_edit(&mut self) -> Result<usize, EditError> {
+        let preview = self.bulk.preview(&self.data, &self.marked());
+        self.edit(&BulkEdit::changes(&preview))?;
+        Ok(preview.len())
+    }
+
+    /// The `bulk_panel` method shows the settings of the bulk edit, a preview of the rows it
+    /// changes, and a button to apply it.
+    fn bulk_panel(&mut self, ui: &mut Ui) {
+        if !self.config.editable || !self.bulk_open {
+            return;
+        }
+        let headers = T::headers();
+        ui.group(|ui| {
+            ui.horizontal(|ui| {
+                let name = headers.get(self.bulk.column).cloned().unwrap_or_default();
+                egui::ComboBox::from_id_source("bulk_column")
+                    .selected_text(name)
+                    .show_ui(ui, |ui| {
+                        for (column, header) in headers.iter().enumerate() {
+                            if T::is_editable(column) {
+                                ui.selectable_value(&mut self.bulk.column, column, header);
+                            }
+                        }
+                    });
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.bulk.value)
+                        .hint_text("New value")
+                        .desired_width(120.0),
+                );
+                let marked = self.marked().len();
+                let mut matching = matches!(self.bulk.scope, BulkScope::Matching(_));
+                ui.radio_value(&mut matching, false, format!("Marked rows ({marked})"));
+                ui.radio_value(&mut matching, true, "Rows equal to");
+                self.bulk.scope = match (matching, &self.bulk.scope) {
+                    (true, BulkScope::Matching(text)) => BulkScope::Matching(text.clone()),
+                    (true, BulkScope::Marked) => BulkScope::Matching(String::new()),
+                    (false, _) => BulkScope::Marked,
+                };
+                if let BulkScope::Matching(text) = &mut self.bulk.scope {
+                    ui.add(egui::TextEdit::singleline(text).desired_width(120.0));
+                }
+                if ui.button("Preview").clicked() {
+                    let preview = self.bulk.preview(&self.data, &self.marked());
+                    // Name each row by its first column.
+                    let names = self
+                        .data
+                        .rows_ref()
+                        .iter()
+                        .map(|row| {
+                            let name = row.cells().first().map(|v| v.to_string());
+                            (*row.id(), name.unwrap_or_default())
+                        })
+                        .collect::<HashMap<Uuid, String>>();
+                    let preview = preview
+                        .into_iter()
+                        .map(|edit| (names.get(&edit.id).cloned().unwrap_or_default(), edit))
+                        .collect();
+                    self.bulk_preview = Some((self.bulk.clone(), preview));
+                    self.edit_error = None;
+                }
+            });
+            // A preview only applies to the settings it was made with.
+            let current = matches!(&self.bulk_preview, Some((bulk, _)) if *bulk == self.bulk);
+            let Some((bulk, preview)) = self.bulk_preview.take().filter(|_| current) else {
+                ui.label("Preview the edit to review the rows it changes.");
+                return;
+            };
+            ui.label(format!("{} rows change.", preview.len()));
+            egui::ScrollArea::vertical()
+                .id_source("bulk_preview")
+                .max_height(120.0)
+                .show_rows(ui, 18.0, preview.len(), |ui, range| {
+                    for (name, edit) in &preview[range] {
+                        ui.label(format!("{}: {} → {}", name, edit.before, edit.after));
+                    }
+                });
+            let mut applied = false;
+            ui.horizontal(|ui| {
+                let apply = ui.add_enabled(!preview.is_empty(), egui::Button::new("Apply"));
+                if apply.clicked() {
+                    let edits = preview
+                        .iter()
+                        .map(|(_, v)| v.clone())
+                        .collect::<Vec<Edit>>();
+                    match self.edit(&BulkEdit::changes(&edits)) {
+                        Ok(()) => {
+                            tracing::info!("Bulk edit changed {} rows.", edits.len());
+                            self.edit_error = None;
+                            applied = true;
+                        }
+                        Err(e) => self.edit_error = Some(e.to_string()),
+                    }
+                }
+                if let Some(message) = &self.edit_error {
+                    ui.colored_label(ui.visuals().error_fg_color, message);
+                }
+            });
+            // The preview is spent once applied.
+            if !applied {
+                self.bulk_preview = Some((bulk, preview));
+            }
+        });
+    }
+
+    /// The `summarize` method groups the rows in the view that pass the search and filters by
+    /// the columns in `group_by`.  Reuses the last summary if neither has changed.
+    pub fn summarize(&mut self) -> &Summary {
+        self.update_index();
+        let current = matches!(
+            &self.summary,
+            Some((key, by, _)) if *key == self.key && *by == self.group_by
+        );
+        if !current {
+            self.summary = None;
+        }
+        let (_, _, summary) = self.summary.get_or_insert_with(|| {
+            let rows = self.view.rows_ref();
+            let summary = Summary::new(
+                self.index.iter().map(|v| &rows[*v]),
+                &self.group_by,
+                T::headers(),
+            );
+            drop(rows);
+            (self.key.clone(), self.group_by.clone(), summary)
+        });
+        summary
+    }
+
+    /// Filters the table to the rows in `group`, by setting an equals filter on each grouped
+    /// column, or an empty filter where the group key has no value.
+    pub fn filter_group(&mut self, by: &[usize], group: &Group) {
+        for (column, key) in by.iter().zip(&group.keys) {
+            let filter = match key.is_empty() || key.to_string().is_empty() {
+                true => ColumnFilter::Empty,
+                false => ColumnFilter::Equals(key.to_string()),
+            };
+            self.column_filters.insert(*column, filter);
+            self.range_input.remove(column);
         }
-        if let Some(column) = self.set_ord.take() {
-            tracing::info!("Column ordering requested for {}", column);
-            let flag = self.ord_flags[column];
-            self.view_mut().sort_by_col(column, flag);
+    }
+
+    /// The `summary_panel` method shows the columns grouped, the statistics of each group, and
+    /// a button to export them.  Clicking a group filters the table to its rows.
+    fn summary_panel(&mut self, ui: &mut Ui) {
+        if !self.summary_open {
+            return;
+        }
+        let headers = T::headers();
+        ui.group(|ui| {
+            ui.horizontal(|ui| {
+                ui.label("Group by");
+                let mut remove = None;
+                for (position, column) in self.group_by.iter().enumerate() {
+                    let name = headers.get(*column).cloned().unwrap_or_default();
+                    if ui
+                        .small_button(format!("{name} ✖"))
+                        .on_hover_text("Remove from grouping.")
+                        .clicked()
+                    {
+                        remove = Some(position);
+                    }
+                }
+                if let Some(position) = remove {
+                    self.group_by.remove(position);
+                }
+                egui::ComboBox::from_id_source("summary_group")
+                    .selected_text("Add column")
+                    .show_ui(ui, |ui| {
+                        for (column, header) in headers.iter().enumerate() {
+                            if !self.group_by.contains(&column)
+                                && ui.selectable_label(false, header).clicked()
+                            {
+                                self.group_by.push(column);
+                            }
+                        }
+                    });
+            });
+            let by = self.group_by.clone();
+            self.summarize();
+            let Some((_, _, summary)) = &self.summary else {
+                return;
+            };
+            ui.label(format!(
+                "{} groups, {} rows.",
+                summary.groups.len(),
+                summary.count()
+            ));
+            let mut chosen = None;
+            egui::ScrollArea::both()
+                .id_source("summary_groups")
+                .max_height(200.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("summary_grid")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for column in summary.columns() {
+                                ui.strong(column);
+                            }
+                            ui.end_row();
+                            for group in &summary.groups {
+                                for (position, value) in
+                                    summary.values(group).into_iter().enumerate()
+                                {
+                                    // The group keys filter the table when clicked.
+                                    if position < by.len() {
+                                        if ui
+                                            .link(value)
+                                            .on_hover_text("Show this group in the table.")
+                                            .clicked()
+                                        {
+                                            chosen = Some(group.clone());
+                                        }
+                                    } else {
+                                        ui.label(value);
+                                    }
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.summary_export)
+                        .hint_text("data/summary.csv"),
+                );
+                if ui.button("Export CSV").clicked() {
+                    let path = match self.summary_export.is_empty() {
+                        true => "data/summary.csv".to_string(),
+                        false => self.summary_export.clone(),
+                    };
+                    match summary.to_csv(&path) {
+                        Ok(()) => tracing::info!("Summary written to {}", path),
+                        Err(e) => tracing::warn!("Unable to write summary: {}", e.to_string()),
+                    }
+                }
+            });
+            if let Some(group) = chosen {
+                self.filter_group(&by, &group);
+            }
+        });
+    }
+
+    /// Marks the cached rows of the table as stale, so that search, filters and sort are
+    /// reapplied on the next frame.  Call after changing the rows in `view`.
+    pub fn refresh(&mut self) {
+        self.revision += 1;
+    }
+
+    /// The `update_index` method applies a pending sort, then recomputes the positions of the
+    /// rows in `view` that pass the search and the column filters, if the rows or any of these
+    /// have changed since the index was last built.  Fuzzy searches order the positions by match
+    /// score.  Returns `true` if the index was rebuilt, and `false` if the cached index was kept.
+    pub fn update_index(&mut self) -> bool {
+        if self.resort {
+            self.resort = false;
+            let spec = self.sort.clone();
+            self.view.sort_by_spec(&spec);
+            self.refresh();
+        }
+        let key = ViewKey {
+            revision: self.revision,
+            search: self.search.clone(),
+            mode: self.config.search_mode,
+            case_sensitive: self.config.case_sensitive,
+            filters: self.column_filters.clone(),
+        };
+        if self.key.as_ref() == Some(&key) {
+            return false;
+        }
+        // The first non-empty cell of each column determines the input in the filter row.
+        // Reads a bounded sample of rows, so a large view is not decoded to find them.
+        let mut kinds = vec![Cell::Empty; T::headers().len()];
+        for position in 0..self.view.len().min(KIND_SAMPLE) {
+            if kinds.iter().all(|v| !v.is_empty()) {
+                break;
+            }
+            let Some(row) = self.view.row(position) else {
+                break;
+            };
+            for (kind, cell) in kinds.iter_mut().zip(row.cells()) {
+                if kind.is_empty() {
+                    *kind = cell;
+                }
+            }
+        }
+        self.kinds = kinds;
+        // Without a search or filters every row is shown in order, so no row is read.
+        if key.search.is_empty() && !key.filters.is_active() {
+            self.index = (0..self.view.len()).collect();
+            self.key = Some(key);
+            return true;
+        }
+        let rows = self.view.rows_ref();
+        // A search term that does not compile matches no rows.
+        let matcher = match key.search.is_empty() {
+            true => None,
+            false => Some(Matcher::new(&key.search, key.mode, key.case_sensitive)),
+        };
+        let mut scored = rows
+            .iter()
+            .enumerate()
+            .filter_map(|(position, row)| {
+                let cells = row.cells();
+                if !key.filters.matches(&cells, key.case_sensitive) {
+                    return None;
+                }
+                match &matcher {
+                    None => Some((0, position)),
+                    Some(Ok(matcher)) => score_cells(&cells, matcher).map(|v| (v, position)),
+                    Some(Err(_)) => None,
+                }
+            })
+            .collect::<Vec<(i64, usize)>>();
+        if key.mode == SearchMode::Fuzzy && matcher.is_some() {
+            scored.sort_by(|(a, _), (b, _)| b.cmp(a));
         }
-        // Collect the ids of each row.
-        self.row_ids = rows.iter().map(|v| *v.id()).collect::<Vec<Uuid>>();
-        if !self.loaded {
-            self.leaves(rows.len());
+        let index = scored.into_iter().map(|(_, v)| v).collect::<Vec<usize>>();
+        drop(rows);
+        self.index = index;
+        self.key = Some(key);
+        true
+    }
+
+    /// UI display for the table view.
+    pub fn table(&mut self, ui: &mut Ui) {
+        self.update_index();
+        // The focus tree is built once there are rows to navigate.
+        let build = !self.loaded && !self.index.is_empty();
+        if build {
+            self.leaves(self.index.len());
         }
 
-        if !self.row_ids.is_empty() {
+        if !self.index.is_empty() {
             // Start selected at the first row.
             if let Some(index) = self.row_index {
-                self.row_select = Some(self.row_ids[index]);
+                // Filters may have removed the selected row.
+                let index = index.min(self.index.len() - 1);
+                self.row_index = Some(index);
+                self.row_select = self.row_id(index);
             } else {
                 self.row_index = Some(0);
             }
         }
         // Creates a slider.  If slider turns true, snap focus to the target row.
-        let track_item = self.slider(ui, rows.len());
+        let track_item = self.slider(ui, self.index.len());
         // Column headers for the table display.
-        let mut headers = T::headers();
-        if self.config.checked {
-            headers.insert(0, "Show".to_string());
-        }
+        let headers = T::headers();
+        self.config.fit(headers.len());
+        // Data columns to display, in display order.
+        let shown = self.config.visible_columns();
         // Create the search panel widget.
-        self.search_panel(ui);
+        ui.horizontal(|ui| {
+            self.search_panel(ui);
+            self.column_chooser(ui);
+            if self.column_filters.is_active() && ui.button("Clear filters").clicked() {
+                self.clear_filters();
+            }
+            self.edit_panel(ui);
+            ui.toggle_value(&mut self.summary_open, "Summary");
+            self.selection_panel(ui);
+        });
+        self.bulk_panel(ui);
+        self.summary_panel(ui);
+        // Highlights the search term in the cells.
+        let matcher = match self.search.is_empty() {
+            true => None,
+            false => Matcher::new(
+                &self.search,
+                self.config.search_mode,
+                self.config.case_sensitive,
+            )
+            .ok(),
+        };
         // Construct the table.
         let mut table = TableBuilder::new(ui)
             .striped(self.config.striped)
             .resizable(self.config.resizable)
             .sense(Sense::click())
-            .cell_layout(Layout::left_to_right(Align::Center))
-            .columns(Column::auto(), headers.len());
+            .cell_layout(Layout::left_to_right(Align::Center));
+        if self.config.checked {
+            table = table.column(Column::auto());
+        }
+        for column in &shown {
+            // Restore the saved width, if any.
+            table = match self.config.width(*column) {
+                Some(width) => table.column(Column::initial(width)),
+                None => table.column(Column::auto()),
+            };
+        }
         // Enable row tracking on the slider.
         if track_item {
             table = table.scroll_to_row(self.target, Some(Align::Center));
@@ -276,124 +1048,295 @@ impl<T: Tabular<U> + Default + Filtration<T, V> + Clone, U: Columnar + Default,
             }
         }
 
-        let mut id = crate::identifier::Identifier::default();
-        // ui.push_id(id.name(), |ui: &mut Ui| {
+        // Make room for the filter row under the column names.
+        let header_height = match self.config.filter_row {
+            true => 46.0,
+            false => 20.0,
+        };
+        let count = self.index.len();
+        // Edit committed in a cell, applied once the rows are back in place.
+        let mut commit = None;
         // Populate the table.
         table
             // iterate through the headers and print them in bold as the header of each column.
-            .header(20.0, |mut header| {
-                headers
-                    .iter()
-                    .enumerate()
-                    .map(|(i, v)| {
-                        header.col(|ui| {
-                            ui.push_id(id.name(), |ui| {
+            .header(header_height, |mut header| {
+                // The checked column is not part of the data, so it has no sort button.
+                if self.config.checked {
+                    header.col(|ui| {
+                        ui.strong("Show");
+                    });
+                }
+                for column in &shown {
+                    let column = *column;
+                    let (rect, response) = header.col(|ui| {
+                        ui.push_id(("header", column), |ui| {
+                            ui.vertical(|ui| {
                                 ui.horizontal(|ui| {
-                                    ui.strong(v);
-                                    // Offset the column index if the checked column is not there.
-                                    // Checked is the first column, so subtract index numbers greater
-                                    // than one by one.
-                                    // Since the "order by" check box for row zero is not visible when
-                                    // the config for checked is false, the input from the user cannot
-                                    // be zero.
-                                    let flag = if self.config.checked && i > 0 {
-                                        i - 1
-                                    } else {
-                                        // If config is checked, pass i normally.
-                                        i
-                                    };
-                                    // Flag indicates the column, while ord flag indicates the ordering
-                                    // at the column.
-                                    let symbol = match self.ord_flags[flag] {
-                                        true => "⏷",
-                                        false => "⏶",
+                                    // Drag the column name to move the column.
+                                    let drag_id = ui.id().with("column_drag");
+                                    ui.dnd_drag_source(drag_id, ColumnDrag(column), |ui| {
+                                        ui.strong(&headers[column]);
+                                    });
+                                    // Show the direction of the sort, and the priority of the
+                                    // column when sorting by more than one key.
+                                    let symbol = match self.sort.key(column) {
+                                        Some(key) if key.reverse => "⏷",
+                                        _ => "⏶",
                                     };
-                                    let ord_button = ui.button(symbol);
-                                    if ui.button(symbol).clicked {
-                                        if self.config.checked && i > 0 {
-                                            self.set_ord = Some(i - 1);
-                                            self.ord_flags[i - 1] = !self.ord_flags[i - 1];
-                                        } else {
-                                            self.set_ord = Some(i);
-                                            self.ord_flags[i] = !self.ord_flags[i];
+                                    let text = match self.sort.priority(column) {
+                                        Some(rank) if self.sort.len() > 1 => {
+                                            format!("{symbol}{rank}")
                                         }
-                                        tracing::info!("Ord flags set.");
+                                        _ => symbol.to_string(),
+                                    };
+                                    if ui
+                                        .button(text)
+                                        .on_hover_text("Shift-click to add a sort key.")
+                                        .clicked()
+                                    {
+                                        let shift = ui.input(|i| i.modifiers.shift);
+                                        self.sort_clicked(column, shift);
                                     };
                                 });
+                                if self.config.filter_row {
+                                    self.filter_cell(ui, column);
+                                }
                             });
-                        })
-                    })
-                    .for_each(drop);
+                        });
+                    });
+                    // Drop a dragged column here to move it to this position.
+                    if let Some(dragged) = response.dnd_release_payload::<ColumnDrag>() {
+                        self.config.move_column(dragged.0, column);
+                    }
+                    // Save the width only when the user resized the column since the last
+                    // frame, so auto-sized columns stay auto-sized.
+                    let width = rect.width();
+                    if let Some(previous) = self.widths.insert(column, width) {
+                        if self.config.resizable && (previous - width).abs() > f32::EPSILON {
+                            self.config.set_width(column, width);
+                        }
+                    }
+                }
             })
             .body(|body| {
-                body.rows(20., rows.len(), |mut row| {
-                    let row_index = row.index();
-                    let row_data = &rows[row_index];
+                body.rows(20., count, |mut row| {
+                    // Read only the rows in view, and copy each so the widgets can borrow self
+                    // mutably.
+                    let position = self.index[row.index()];
+                    let Some(row_data) = self.view.row(position).map(Cow::into_owned) else {
+                        return;
+                    };
                     let row_id = row_data.id();
                     row.set_selected(self.selection.contains(row_id));
-                    let columns = row_data.values();
+                    let columns = row_data.cells();
 
                     if self.config.checked {
                         // Adds a checkbox column linked to the `checks` field.
-                        if !self.checks.contains_key(row_id) {
-                            self.checks.insert(*row_id, false);
-                        }
-                        let checked = self.checks.get_mut(row_id);
-                        if let Some(check) = checked {
-                            row.col(|ui| {
-                                ui.push_id(id.name(), |ui| {
-                                    ui.checkbox(check, "");
-                                });
-                            });
-                        } else {
-                            tracing::info!("Bad checkbox reference.");
-                            row.col(|ui| {
-                                ui.push_id(id.name(), |ui| {
-                                    ui.label("No box");
-                                });
+                        let check = self.checks.entry(*row_id).or_insert(false);
+                        row.col(|ui| {
+                            ui.push_id((row_id, "check"), |ui| {
+                                ui.checkbox(check, "");
                             });
-                        }
+                        });
                     }
 
-                    columns
-                        .iter()
-                        .map(|v| {
-                            row.col(|ui| {
-                                ui.push_id(id.name(), |ui: &mut Ui| {
-                                    ui.label(v);
-                                });
-                                // ui.label(v);
+                    for column in &shown {
+                        let column = *column;
+                        let value = columns.get(column).cloned().unwrap_or_default();
+                        row.col(|ui| {
+                            ui.push_id((row_id, column), |ui: &mut Ui| {
+                                if self.edit_cell(ui, row_id, column, &mut commit) {
+                                    return;
+                                }
+                                let text = highlight(ui, &value.to_string(), matcher.as_ref());
+                                let response = if value.is_numeric() {
+                                    ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                                        ui.label(text)
+                                    })
+                                    .inner
+                                } else {
+                                    ui.label(text)
+                                };
+                                // Double click an editable cell to edit it.
+                                let response = response.interact(Sense::click());
+                                if self.config.editable
+                                    && T::is_editable(column)
+                                    && response.double_clicked()
+                                {
+                                    self.editing = Some((*row_id, column, value.to_string()));
+                                    self.edit_error = None;
+                                    self.focus_edit = true;
+                                }
                             });
-                        })
-                        .for_each(drop);
-                    self.toggle_row_selection(row_id, &row.response());
+                        });
+                    }
+                    let response = row.response();
+                    if response.clicked() {
+                        let modifiers = response.ctx.input(|i| i.modifiers);
+                        self.click_row(row_id, &modifiers);
+                    }
                 });
             });
-        // });
+        if build {
+            self.loaded = true;
+        }
+        if let Some((id, column, value)) = commit {
+            match self.edit(&[(id, column, value.clone())]) {
+                Ok(()) => self.editing = None,
+                Err(e) => {
+                    // Keep the entry open so the user can correct the value.
+                    self.editing = Some((id, column, value));
+                    self.edit_error = Some(e.to_string());
+                    self.focus_edit = true;
+                }
+            }
+        }
     }
 
-    pub fn contains(&self, fragment: &str) -> Vec<U> {
-        let mut data = Vec::new();
-        let rows = self.view.rows();
-        for row in rows {
-            let mut contains = false;
-            let cols = row.values();
-            for col in cols {
-                let mut value = col;
-                let mut frag = fragment.to_string();
-                if !self.config.case_sensitive {
-                    value = value.to_lowercase();
-                    frag = frag.to_lowercase();
-                }
-                if value.contains(&frag) {
-                    contains = true;
+    /// The `edit_cell` method shows the text entry for the cell at `id` and `column`, and returns
+    /// `true`, if the cell is being edited.  When the user presses enter, sets `commit` to the
+    /// row, column and value entered.  Escape cancels the edit.
+    fn edit_cell(
+        &mut self,
+        ui: &mut Ui,
+        id: &Uuid,
+        column: usize,
+        commit: &mut Option<(Uuid, usize, String)>,
+    ) -> bool {
+        let mut text = match &self.editing {
+            Some((edit_id, edit_column, text)) if edit_id == id && *edit_column == column => {
+                text.clone()
+            }
+            _ => return false,
+        };
+        ui.horizontal(|ui| {
+            let entry = ui.text_edit_singleline(&mut text);
+            // Focus the entry when it opens.
+            if self.focus_edit {
+                entry.request_focus();
+                self.focus_edit = false;
+            }
+            if let Some(message) = &self.edit_error {
+                ui.colored_label(ui.visuals().error_fg_color, "⚠")
+                    .on_hover_text(message);
+            }
+            if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                self.editing = None;
+                self.edit_error = None;
+            } else {
+                if entry.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    *commit = Some((*id, column, text.clone()));
                 }
+                self.editing = Some((*id, column, text));
+            }
+        });
+        true
+    }
+
+    /// The `filter_cell` method shows the filter input for column `column` in the filter row.
+    /// Enum columns get a dropdown of the distinct values in `view`, read only while the dropdown
+    /// is open, numeric columns get a range, and other columns get a text entry that toggles
+    /// between contains and equals.
+    fn filter_cell(&mut self, ui: &mut Ui, column: usize) {
+        let sample = self.kinds.get(column).cloned().unwrap_or_default();
+        let filter = self.column_filters.entry(column).or_default();
+        match sample {
+            Cell::Enum(..) | Cell::Bool(_) => {
+                let current = match filter {
+                    ColumnFilter::Equals(value) => value.clone(),
+                    _ => String::new(),
+                };
+                let text = match (current.is_empty(), &filter) {
+                    (_, ColumnFilter::Empty) => "(empty)".to_string(),
+                    (true, _) => "All".to_string(),
+                    (false, _) => current.clone(),
+                };
+                egui::ComboBox::from_id_source(("column_filter", column))
+                    .selected_text(text)
+                    .show_ui(ui, |ui| {
+                        let all = current.is_empty() && !matches!(filter, ColumnFilter::Empty);
+                        if ui.selectable_label(all, "All").clicked() {
+                            *filter = ColumnFilter::Any;
+                        }
+                        for value in distinct_values(&self.view.rows_ref(), column) {
+                            if ui.selectable_label(current == value, &value).clicked() {
+                                *filter = ColumnFilter::Equals(value);
+                            }
+                        }
+                    });
             }
-            if contains {
-                data.push(row);
+            Cell::Integer(_) | Cell::Float(_) => {
+                let (min, max) = self.range_input.entry(column).or_default();
+                ui.horizontal(|ui| {
+                    let low = ui.add(
+                        egui::TextEdit::singleline(min)
+                            .hint_text("Min")
+                            .desired_width(40.0),
+                    );
+                    let high = ui.add(
+                        egui::TextEdit::singleline(max)
+                            .hint_text("Max")
+                            .desired_width(40.0),
+                    );
+                    if low.changed() || high.changed() {
+                        *filter = ColumnFilter::Range {
+                            min: min.trim().parse().ok(),
+                            max: max.trim().parse().ok(),
+                        };
+                    }
+                });
+            }
+            _ => {
+                ui.horizontal(|ui| {
+                    let equals = matches!(filter, ColumnFilter::Equals(_));
+                    let mut text = match filter {
+                        ColumnFilter::Contains(value) | ColumnFilter::Equals(value) => {
+                            value.clone()
+                        }
+                        _ => String::new(),
+                    };
+                    let symbol = match equals {
+                        true => "=",
+                        false => "≈",
+                    };
+                    let toggle = ui
+                        .small_button(symbol)
+                        .on_hover_text("Toggle between contains and equals.")
+                        .clicked();
+                    let entry = ui.add(
+                        egui::TextEdit::singleline(&mut text)
+                            .hint_text("Filter")
+                            .desired_width(80.0),
+                    );
+                    if toggle || entry.changed() {
+                        *filter = match equals ^ toggle {
+                            true => ColumnFilter::Equals(text),
+                            false => ColumnFilter::Contains(text),
+                        };
+                    }
+                });
             }
         }
-        data
+    }
+
+    /// Clears the filters in the filter row.
+    pub fn clear_filters(&mut self) {
+        self.column_filters.clear();
+        self.range_input.clear();
+    }
+
+    /// Returns the rows in `view` with a cell matching `fragment` in the search mode of the
+    /// config.  Fuzzy searches order the rows by match score.  A fragment that is not a valid
+    /// regular expression in regex mode matches no rows.
+    pub fn contains(&self, fragment: &str) -> Vec<U> {
+        match Matcher::new(
+            fragment,
+            self.config.search_mode,
+            self.config.case_sensitive,
+        ) {
+            Ok(matcher) => search_rows(self.view.rows(), &matcher),
+            Err(_) => Vec::new(),
+        }
     }
 
     /// Returns the [`Uuid`] of the current row in focus.
@@ -406,22 +1349,84 @@ impl<T: Tabular<U> + Default + Filtration<T, V> + Clone, U: Columnar + Default,
         self.row_focus = self.current_row();
     }
 
+    /// Selects the row `row_id` alone and scrolls it into view, as when identifying its feature
+    /// on a map.  Returns `false` if the row is not visible.
+    pub fn focus_row(&mut self, row_id: &Uuid) -> bool {
+        if self.row_position(row_id).is_none() {
+            return false;
+        }
+        self.click_row(row_id, &egui::Modifiers::NONE);
+        self.row_focus = Some(*row_id);
+        true
+    }
+
+    /// Selects the visible rows with an id in `ids` in `mode`, as when drawing a selection on a
+    /// map, or checks their boxes instead if `check` is set.  Returns the number of rows chosen,
+    /// and the number of ids skipped because the search or filters hide their rows.
+    pub fn select_rows(
+        &mut self,
+        ids: &HashSet<Uuid>,
+        mode: SelectMode,
+        check: bool,
+    ) -> (usize, usize) {
+        let visible = self.row_ids();
+        let chosen = visible
+            .iter()
+            .filter(|v| ids.contains(v))
+            .copied()
+            .collect::<HashSet<Uuid>>();
+        match check {
+            true => {
+                let mut checked = self.marked_checks();
+                mode.apply(&mut checked, &chosen);
+                self.checks.values_mut().for_each(|v| *v = false);
+                for id in checked {
+                    self.checks.insert(id, true);
+                }
+            }
+            false => {
+                mode.apply(&mut self.selection, &chosen);
+                self.anchor = None;
+            }
+        }
+        if let Some(id) = visible.iter().find(|v| chosen.contains(v)) {
+            self.row_focus = Some(*id);
+        }
+        (chosen.len(), ids.len() - chosen.len())
+    }
+
+    /// Ids of the rows with a checked box.
+    fn marked_checks(&self) -> HashSet<Uuid> {
+        self.checks
+            .iter()
+            .filter(|(_, checked)| **checked)
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
     /// Advances focus to the next row and returns the new row [`Uuid`].
     pub fn next_row(&mut self) -> Option<Uuid> {
+        // No rows pass the search and filters, so there is no row to move to.
+        if self.index.is_empty() {
+            self.row_index = None;
+            return self.row_select;
+        }
         // take a mutable reference to the index of the row
         if let Some(index) = &mut self.row_index {
             tracing::info!("Current index: {}", index);
             tracing::info!("Advancing row index.");
             // Wraps to beginning if at the end
-            if (*index + 1) > (self.row_ids.len() - 1) {
+            if (*index + 1) > (self.index.len() - 1) {
                 *index = 0;
                 tracing::info!("Wrapped row index to 0.");
             } else {
                 *index += 1;
                 tracing::info!("Adding one: {}", index);
             }
-            // match the selected row id to the updated index.
-            self.row_select = Some(self.row_ids[*index]);
+        }
+        // match the selected row id to the updated index.
+        if let Some(index) = self.row_index {
+            self.row_select = self.row_id(index);
             if let Some(id) = self.row_select {
                 tracing::info!("Row id: {}", id);
             }
@@ -439,17 +1444,22 @@ impl<T: Tabular<U> + Default + Filtration<T, V> + Clone, U: Columnar + Default,
 
     /// Moves focus to the previous row and returns the new row [`Uuid`].
     pub fn previous_row(&mut self) -> Option<Uuid> {
+        // No rows pass the search and filters, so there is no row to move to.
+        if self.index.is_empty() {
+            self.row_index = None;
+            return self.row_select;
+        }
         if let Some(mut index) = self.row_index {
             tracing::info!("Decrementing row index.");
             if index == 0 {
-                index = self.row_ids.len() - 1;
+                index = self.index.len() - 1;
             } else {
                 index -= 1;
                 tracing::info!("Minus one: {}", index);
             }
             self.row_index = Some(index);
             tracing::info!("Row index: {}", index);
-            self.row_select = Some(self.row_ids[index]);
+            self.row_select = self.row_id(index);
             if let Some(id) = self.row_select {
                 tracing::info!("Row id: {}", id);
             }
@@ -464,10 +1474,75 @@ impl<T: Tabular<U> + Default + Filtration<T, V> + Clone, U: Columnar + Default,
     }
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+/// The `highlight` function lays out `text` with the ranges matched by `matcher` drawn on the
+/// selection color.
+#[cfg(feature = "gui")]
+fn highlight(ui: &Ui, text: &str, matcher: Option<&Matcher>) -> egui::WidgetText {
+    let ranges = match matcher.and_then(|v| v.find(text)) {
+        Some(found) => found.ranges,
+        None => return text.into(),
+    };
+    let plain = egui::TextFormat {
+        font_id: egui::TextStyle::Body.resolve(ui.style()),
+        color: ui.visuals().text_color(),
+        ..Default::default()
+    };
+    let marked = egui::TextFormat {
+        background: ui.visuals().selection.bg_fill,
+        color: ui.visuals().selection.stroke.color,
+        ..plain.clone()
+    };
+    let mut job = egui::text::LayoutJob::default();
+    let mut start = 0;
+    for range in ranges {
+        job.append(&text[start..range.start], 0.0, plain.clone());
+        job.append(&text[range.clone()], 0.0, marked.clone());
+        start = range.end;
+    }
+    job.append(&text[start..], 0.0, plain);
+    job.into()
+}
+
+/// The inputs of the cached row index of a [`TableView`].
+#[cfg(feature = "gui")]
+#[derive(Debug, Default, Clone, PartialEq)]
+struct ViewKey {
+    revision: u64,
+    search: String,
+    mode: SearchMode,
+    case_sensitive: bool,
+    filters: ColumnFilters,
+}
+
+/// Drag and drop payload for moving a column, holding the index of the dragged column.
+#[cfg(feature = "gui")]
+struct ColumnDrag(usize);
+
+/// The `ColumnConfig` struct holds the display settings of a single data column.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ColumnConfig {
+    /// Index of the column in [`Tabular::headers`].
+    pub index: usize,
+    pub visible: bool,
+    /// Width of the column in points, if set by the user.
+    pub width: Option<f32>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
 pub struct TableConfig {
     pub case_sensitive: bool,
     pub checked: bool,
+    /// Shows the column chooser menu.
+    pub chooser: bool,
+    /// Allows cells to be edited with a double click.
+    pub editable: bool,
+    /// Shows a row of filter inputs under the column headers.
+    pub filter_row: bool,
+    /// How the search term matches the text of each cell.
+    pub search_mode: SearchMode,
+    /// Settings for each data column, in display order.  Filled in with every column visible when
+    /// the table is first shown, if empty.
+    pub columns: Vec<ColumnConfig>,
     pub resizable: bool,
     pub search: bool,
     pub slider: bool,
@@ -508,12 +1583,920 @@ impl TableConfig {
         self.case_sensitive = true;
         self
     }
+
+    /// Sets the search mode of the search widget.
+    pub fn with_search_mode(mut self, mode: SearchMode) -> Self {
+        self.search_mode = mode;
+        self
+    }
+
+    /// Enables editing of cells in the table.
+    pub fn with_editing(mut self) -> Self {
+        self.editable = true;
+        self
+    }
+
+    /// Enables the filter row under the column headers.
+    pub fn with_filter_row(mut self) -> Self {
+        self.filter_row = true;
+        self
+    }
+
+    /// Enables the column chooser menu.
+    pub fn with_chooser(mut self) -> Self {
+        self.chooser = true;
+        self
+    }
+
+    /// Shows the columns in `indices`, in the order given.  Other columns start hidden.
+    pub fn with_columns(mut self, indices: &[usize]) -> Self {
+        self.columns = indices
+            .iter()
+            .map(|index| ColumnConfig {
+                index: *index,
+                visible: true,
+                width: None,
+            })
+            .collect();
+        self
+    }
+
+    /// Matches the column settings to a table with `count` columns.  Drops settings for columns
+    /// that no longer exist, and adds any missing columns at the end.  Missing columns are
+    /// visible if no columns were set, and hidden otherwise.
+    pub fn fit(&mut self, count: usize) {
+        let visible = self.columns.is_empty();
+        self.columns.retain(|v| v.index < count);
+        for index in 0..count {
+            if !self.columns.iter().any(|v| v.index == index) {
+                self.columns.push(ColumnConfig {
+                    index,
+                    visible,
+                    width: None,
+                });
+            }
+        }
+    }
+
+    /// Indices of the visible columns, in display order.
+    pub fn visible_columns(&self) -> Vec<usize> {
+        self.columns
+            .iter()
+            .filter(|v| v.visible)
+            .map(|v| v.index)
+            .collect()
+    }
+
+    fn column(&self, index: usize) -> Option<&ColumnConfig> {
+        self.columns.iter().find(|v| v.index == index)
+    }
+
+    fn column_mut(&mut self, index: usize) -> Option<&mut ColumnConfig> {
+        self.columns.iter_mut().find(|v| v.index == index)
+    }
+
+    pub fn is_visible(&self, index: usize) -> bool {
+        self.column(index).map(|v| v.visible).unwrap_or(true)
+    }
+
+    pub fn set_visible(&mut self, index: usize, visible: bool) {
+        if let Some(column) = self.column_mut(index) {
+            column.visible = visible;
+        }
+    }
+
+    pub fn width(&self, index: usize) -> Option<f32> {
+        self.column(index).and_then(|v| v.width)
+    }
+
+    pub fn set_width(&mut self, index: usize, width: f32) {
+        if let Some(column) = self.column_mut(index) {
+            column.width = Some(width);
+        }
+    }
+
+    /// Moves column `index` to the display position of column `target`.
+    pub fn move_column(&mut self, index: usize, target: usize) {
+        let from = self.columns.iter().position(|v| v.index == index);
+        let to = self.columns.iter().position(|v| v.index == target);
+        if let (Some(from), Some(to)) = (from, to) {
+            let column = self.columns.remove(from);
+            self.columns.insert(to, column);
+        }
+    }
+}
+
+/// The `TableLayouts` struct holds the [`TableConfig`] of each table by name, so that column
+/// settings are saved with the session.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize, Deref, DerefMut)]
+pub struct TableLayouts(HashMap<String, TableConfig>);
+
+/// The `SortKey` struct holds a column index and the direction to sort it.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct SortKey {
+    pub column: usize,
+    /// Sorts in descending order if `true`.
+    pub reverse: bool,
+}
+
+/// The `SortSpec` struct is an ordered list of [`SortKey`], from highest to lowest priority.
+/// Rows that compare equal on the first key are ordered by the second, and so on.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Deserialize, Serialize, Deref, DerefMut)]
+pub struct SortSpec(Vec<SortKey>);
+
+impl SortSpec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `column` as the next key, sorting in descending order if `reverse` is `true`.
+    pub fn then(mut self, column: usize, reverse: bool) -> Self {
+        self.0.retain(|v| v.column != column);
+        self.0.push(SortKey { column, reverse });
+        self
+    }
+
+    /// Returns the key for `column`, if present.
+    pub fn key(&self, column: usize) -> Option<&SortKey> {
+        self.0.iter().find(|v| v.column == column)
+    }
+
+    /// Returns the priority of `column`, starting from one, if present.
+    pub fn priority(&self, column: usize) -> Option<usize> {
+        self.0
+            .iter()
+            .position(|v| v.column == column)
+            .map(|v| v + 1)
+    }
+
+    /// Sorts by `column` alone.  If `column` is already the only key, toggles its direction.
+    pub fn set(&mut self, column: usize) {
+        let reverse = match self.0.as_slice() {
+            [key] if key.column == column => !key.reverse,
+            _ => false,
+        };
+        self.0 = vec![SortKey { column, reverse }];
+    }
+
+    /// Adds `column` as the lowest priority key.  If `column` is already a key, toggles its
+    /// direction and leaves its priority unchanged.
+    pub fn push(&mut self, column: usize) {
+        match self.0.iter_mut().find(|v| v.column == column) {
+            Some(key) => key.reverse = !key.reverse,
+            None => self.0.push(SortKey {
+                column,
+                reverse: false,
+            }),
+        }
+    }
+}
+
+/// The `Cell` enum holds the typed value of a single cell in a table.  Typed cells let the
+/// [`TableView`] sort numbers numerically, search and align values without knowing the row type.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub enum Cell {
+    Text(String),
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    /// A value drawn from a fixed set of variants, such as a status or street type, with the
+    /// position of the variant in its declaration.  Sorts by position, so that values keep the
+    /// order of the variants rather than the alphabet.
+    Enum(u32, String),
+    Date(NaiveDate),
+    /// The missing value of an optional field.
+    #[default]
+    Empty,
+}
+
+impl Cell {
+    /// Returns `true` for integer and float cells, which display right-aligned.
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Self::Integer(_) | Self::Float(_))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        matches!(self, Self::Empty)
+    }
+
+    /// Returns the value of integer and float cells as a float, for range filters.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Integer(value) => Some(*value as f64),
+            Self::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Position of the variant in sort order, used to compare cells of different types.
+    fn rank(&self) -> usize {
+        match self {
+            Self::Integer(_) | Self::Float(_) => 0,
+            Self::Date(_) => 1,
+            Self::Bool(_) => 2,
+            Self::Enum(..) => 3,
+            Self::Text(_) => 4,
+            Self::Empty => 5,
+        }
+    }
+
+    /// Total ordering of cells.  Numbers compare by value across integer and float cells, text
+    /// compares without regard to case, and empty cells sort after everything else.
+    pub fn compare(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Integer(a), Self::Integer(b)) => a.cmp(b),
+            (Self::Float(a), Self::Float(b)) => a.total_cmp(b),
+            (Self::Integer(a), Self::Float(b)) => (*a as f64).total_cmp(b),
+            (Self::Float(a), Self::Integer(b)) => a.total_cmp(&(*b as f64)),
+            (Self::Bool(a), Self::Bool(b)) => a.cmp(b),
+            (Self::Date(a), Self::Date(b)) => a.cmp(b),
+            (Self::Enum(a, x), Self::Enum(b, y)) => a.cmp(b).then_with(|| Self::compare_text(x, y)),
+            (Self::Text(a), Self::Text(b)) => Self::compare_text(a, b),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+
+    /// Compares text without regard to case, breaking ties by case.
+    fn compare_text(a: &str, b: &str) -> Ordering {
+        a.to_lowercase()
+            .cmp(&b.to_lowercase())
+            .then_with(|| a.cmp(b))
+    }
+
+    /// Returns `true` if the displayed value contains `fragment`.
+    pub fn contains(&self, fragment: &str, case_sensitive: bool) -> bool {
+        let value = self.to_string();
+        if case_sensitive {
+            value.contains(fragment)
+        } else {
+            value.to_lowercase().contains(&fragment.to_lowercase())
+        }
+    }
+}
+
+impl std::fmt::Display for Cell {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Text(value) | Self::Enum(_, value) => write!(f, "{}", value),
+            Self::Integer(value) => write!(f, "{}", value),
+            Self::Float(value) => write!(f, "{}", value),
+            Self::Bool(value) => match value {
+                true => write!(f, "Yes"),
+                false => write!(f, "No"),
+            },
+            Self::Date(value) => write!(f, "{}", value.format("%Y-%m-%d")),
+            Self::Empty => write!(f, ""),
+        }
+    }
+}
+
+impl From<String> for Cell {
+    fn from(value: String) -> Self {
+        Self::Text(value)
+    }
+}
+
+impl From<&str> for Cell {
+    fn from(value: &str) -> Self {
+        Self::Text(value.to_string())
+    }
+}
+
+impl From<i64> for Cell {
+    fn from(value: i64) -> Self {
+        Self::Integer(value)
+    }
+}
+
+impl From<f64> for Cell {
+    fn from(value: f64) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<bool> for Cell {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<NaiveDate> for Cell {
+    fn from(value: NaiveDate) -> Self {
+        Self::Date(value)
+    }
+}
+
+impl<T: Into<Cell>> From<Option<T>> for Cell {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(v) => v.into(),
+            None => Self::Empty,
+        }
+    }
+}
+
+/// The `sort_by_cell` function sorts `rows` by the typed [`Cell`] in column `column`, in
+/// descending order if `reverse` is `true`.  The sort is stable, and empty cells sort last in
+/// either direction.  Types implementing [`Tabular`] can call it from
+/// [`Tabular::sort_by_col`] instead of matching on each column.
+pub fn sort_by_cell<T: Columnar>(rows: &mut Vec<T>, column: usize, reverse: bool) {
+    // Compute the cells once per row rather than once per comparison.
+    let mut keyed = rows
+        .drain(..)
+        .map(|row| {
+            let cell = row.cells().into_iter().nth(column).unwrap_or_default();
+            (cell, row)
+        })
+        .collect::<Vec<(Cell, T)>>();
+    keyed.sort_by(|(a, _), (b, _)| match (a.is_empty(), b.is_empty()) {
+        (false, true) => Ordering::Less,
+        (true, false) => Ordering::Greater,
+        _ if reverse => b.compare(a),
+        _ => a.compare(b),
+    });
+    rows.extend(keyed.into_iter().map(|(_, row)| row));
+}
+
+/// The `ColumnFilter` enum holds the filter applied to a single column from the filter row of a
+/// [`TableView`].
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub enum ColumnFilter {
+    /// Matches every cell.
+    #[default]
+    Any,
+    /// Matches cells whose displayed value contains the fragment.
+    Contains(String),
+    /// Matches cells whose displayed value equals the text.  Used by the dropdown of distinct
+    /// values for enum columns.
+    Equals(String),
+    /// Matches numeric cells within the bounds, inclusive.  A missing bound is unlimited.
+    Range { min: Option<f64>, max: Option<f64> },
+    /// Matches cells with no value.  Used to filter to the group of empty values in a summary.
+    Empty,
+}
+
+impl ColumnFilter {
+    /// Returns `false` if the filter matches every cell.
+    pub fn is_active(&self) -> bool {
+        match self {
+            Self::Any => false,
+            Self::Contains(text) | Self::Equals(text) => !text.is_empty(),
+            Self::Range { min, max } => min.is_some() || max.is_some(),
+            Self::Empty => true,
+        }
+    }
+
+    /// Returns `true` if `cell` passes the filter.
+    pub fn matches(&self, cell: &Cell, case_sensitive: bool) -> bool {
+        if !self.is_active() {
+            return true;
+        }
+        match self {
+            Self::Any => true,
+            Self::Contains(text) => cell.contains(text, case_sensitive),
+            Self::Equals(text) => {
+                let value = cell.to_string();
+                if case_sensitive {
+                    value == *text
+                } else {
+                    value.to_lowercase() == text.to_lowercase()
+                }
+            }
+            Self::Range { min, max } => match cell.as_f64() {
+                Some(value) => {
+                    min.map(|v| value >= v).unwrap_or(true)
+                        && max.map(|v| value <= v).unwrap_or(true)
+                }
+                None => false,
+            },
+            Self::Empty => cell.is_empty() || cell.to_string().is_empty(),
+        }
+    }
+}
+
+/// The `ColumnFilters` struct maps the index of a column to its [`ColumnFilter`].
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize, Deref, DerefMut)]
+pub struct ColumnFilters(HashMap<usize, ColumnFilter>);
+
+impl ColumnFilters {
+    /// Returns `true` if any filter is active.
+    pub fn is_active(&self) -> bool {
+        self.values().any(|v| v.is_active())
+    }
+
+    /// Returns `true` if every cell in `cells` passes the filter for its column.
+    pub fn matches(&self, cells: &[Cell], case_sensitive: bool) -> bool {
+        self.iter()
+            .all(|(column, filter)| match cells.get(*column) {
+                Some(cell) => filter.matches(cell, case_sensitive),
+                None => !filter.is_active(),
+            })
+    }
+
+    /// Retains the rows in `rows` that pass every filter.
+    pub fn apply<T: Columnar>(&self, rows: &mut Vec<T>, case_sensitive: bool) {
+        if self.is_active() {
+            rows.retain(|row| self.matches(&row.cells(), case_sensitive));
+        }
+    }
+}
+
+/// The `distinct_values` function returns the distinct non-empty values of column `column` in
+/// `rows`, in sort order.  Used to fill the dropdown of enum columns in the filter row.
+pub fn distinct_values<T: Columnar>(rows: &[T], column: usize) -> Vec<String> {
+    let mut cells = rows
+        .iter()
+        .filter_map(|row| row.cells().into_iter().nth(column))
+        .filter(|v| !v.is_empty())
+        .collect::<Vec<Cell>>();
+    cells.sort_by(|a, b| a.compare(b));
+    let mut values = cells.iter().map(|v| v.to_string()).collect::<Vec<String>>();
+    values.dedup();
+    values
+}
+
+/// The `EditError` enum describes why a value could not be written to a cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditError {
+    /// The column does not accept edits.
+    ReadOnly,
+    /// No row has the given id.
+    MissingRow,
+    /// The value is not valid for the column.  Holds a message for the user.
+    Invalid(String),
+}
+
+impl std::fmt::Display for EditError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::ReadOnly => write!(f, "Column is read-only."),
+            Self::MissingRow => write!(f, "Row not found."),
+            Self::Invalid(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for EditError {}
+
+/// The `EditKind` enum is the kind of change recorded by an [`Edit`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum EditKind {
+    /// A new value in one cell.
+    #[default]
+    Cell,
+    /// A row added to the table.
+    Insert,
+    /// A row removed from the table.
+    Remove,
+}
+
+/// The `Edit` struct records a change to a single cell, holding the displayed value before and
+/// after the change so that it can be undone.  An edit adding or removing a row holds the
+/// position of the row in `column`, and the row as written by [`Tabular::row_text`] in `after`
+/// or `before`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Edit {
+    pub id: Uuid,
+    pub column: usize,
+    pub before: String,
+    pub after: String,
+    #[serde(default)]
+    pub kind: EditKind,
+    /// The whole row before a cell edit, as written by [`Tabular::row_text`], for tables that
+    /// write their rows.  The displayed value may not hold the exact value of the cell, so undo
+    /// restores the row from this instead.
+    #[serde(default)]
+    pub before_row: Option<String>,
+    /// The whole row after a cell edit, restored by redo in the same way as `before_row`.
+    #[serde(default)]
+    pub after_row: Option<String>,
+}
+
+impl Edit {
+    /// The edit that reverses `self`.
+    pub fn inverse(&self) -> Self {
+        let kind = match self.kind {
+            EditKind::Cell => EditKind::Cell,
+            EditKind::Insert => EditKind::Remove,
+            EditKind::Remove => EditKind::Insert,
+        };
+        Self {
+            id: self.id,
+            column: self.column,
+            before: self.after.clone(),
+            after: self.before.clone(),
+            kind,
+            before_row: self.after_row.clone(),
+            after_row: self.before_row.clone(),
+        }
+    }
+
+    /// Writes the change to `data`: the `after` value of a cell, or the row added or removed.
+    /// A cell edit holding the row after the change restores the row instead.
+    pub fn write<T: Tabular<U>, U: Columnar>(&self, data: &mut T) -> Result<(), EditError> {
+        match self.kind {
+            EditKind::Cell => match &self.after_row {
+                Some(row) => data.replace_row(&self.id, row),
+                None => data.set_value(&self.id, self.column, &self.after),
+            },
+            EditKind::Insert => data.insert_row(self.column, &self.after),
+            EditKind::Remove => data.remove_row(&self.id).map(|_| ()),
+        }
+    }
+}
+
+/// The `EditHistory` struct holds the undo and redo stacks of a table.  Each step is a list of
+/// edits that undo together.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct EditHistory {
+    undo: Vec<Vec<Edit>>,
+    redo: Vec<Vec<Edit>>,
+}
+
+impl EditHistory {
+    /// Pushes `step` onto the undo stack.  Clears the redo stack, since the redone steps no
+    /// longer follow from the current values.
+    pub fn record(&mut self, step: Vec<Edit>) {
+        if !step.is_empty() {
+            self.undo.push(step);
+            self.redo.clear();
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Writes the `before` value of each edit in the last step to `data`, in reverse order, and
+    /// moves the step to the redo stack.  Returns the ids of the rows changed.  If any edit
+    /// cannot be undone, as when its row has since been removed, the step is left in place and
+    /// on the undo stack, and the error is returned.
+    pub fn undo<T: Tabular<U>, U: Columnar>(
+        &mut self,
+        data: &mut T,
+    ) -> Result<Vec<Uuid>, EditError> {
+        let Some(step) = self.undo.pop() else {
+            return Ok(Vec::new());
+        };
+        let inverse = step.iter().rev().map(Edit::inverse).collect::<Vec<Edit>>();
+        match write_step(data, &inverse) {
+            Ok(ids) => {
+                self.redo.push(step);
+                Ok(ids)
+            }
+            Err(e) => {
+                self.undo.push(step);
+                Err(e)
+            }
+        }
+    }
+
+    /// Writes the `after` value of each edit in the last undone step to `data`, and moves the
+    /// step back to the undo stack.  Returns the ids of the rows changed.  If any edit cannot be
+    /// redone, the step is left in place and on the redo stack, and the error is returned.
+    pub fn redo<T: Tabular<U>, U: Columnar>(
+        &mut self,
+        data: &mut T,
+    ) -> Result<Vec<Uuid>, EditError> {
+        let Some(step) = self.redo.pop() else {
+            return Ok(Vec::new());
+        };
+        match write_step(data, &step) {
+            Ok(ids) => {
+                self.undo.push(step);
+                Ok(ids)
+            }
+            Err(e) => {
+                self.redo.push(step);
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Writes each of `edits` to `data` in order, and returns the ids of the rows changed.  If any
+/// write fails, reverts the edits already written and returns the error, so that the edits apply
+/// all together or not at all.
+fn write_step<T: Tabular<U>, U: Columnar>(
+    data: &mut T,
+    edits: &[Edit],
+) -> Result<Vec<Uuid>, EditError> {
+    for (count, edit) in edits.iter().enumerate() {
+        if let Err(e) = edit.write(data) {
+            revert(data, &edits[..count]);
+            return Err(e);
+        }
+    }
+    Ok(edits.iter().map(|v| v.id).collect())
+}
+
+/// The `apply_edits` function writes each `(id, column, value)` in `changes` to `data`, and
+/// returns the edits made.  If any value is invalid, reverts the edits already made and returns
+/// the error, so that the changes apply all together or not at all.  For tables that write their
+/// rows, each edit also holds the whole row before and after the changes, so that undo and redo
+/// restore the exact values.
+pub fn apply_edits<T: Tabular<U>, U: Columnar + Clone>(
+    data: &mut T,
+    changes: &[(Uuid, usize, String)],
+) -> Result<Vec<Edit>, EditError> {
+    // Index the current values of the rows changed by row id, rather than searching the rows for
+    // each change.
+    let ids = changes
+        .iter()
+        .map(|(id, _, _)| *id)
+        .collect::<HashSet<Uuid>>();
+    let current = data
+        .rows_ref()
+        .iter()
+        .filter(|row| ids.contains(row.id()))
+        .map(|row| (*row.id(), (row.cells(), T::row_text(row).ok())))
+        .collect::<HashMap<Uuid, (Vec<Cell>, Option<String>)>>();
+    let mut edits = Vec::new();
+    for (id, column, value) in changes {
+        let (before, before_row) = match current.get(id) {
+            Some((cells, row)) => (
+                cells
+                    .get(*column)
+                    .map(|v| v.to_string())
+                    .unwrap_or_default(),
+                row.clone(),
+            ),
+            None => {
+                revert(data, &edits);
+                return Err(EditError::MissingRow);
+            }
+        };
+        if let Err(e) = data.set_value(id, *column, value) {
+            revert(data, &edits);
+            return Err(e);
+        }
+        edits.push(Edit {
+            id: *id,
+            column: *column,
+            before,
+            after: value.clone(),
+            kind: EditKind::Cell,
+            before_row,
+            after_row: None,
+        });
+    }
+    // Every edit of a row restores the row as it stands after the last of them.
+    let after = data
+        .rows_ref()
+        .iter()
+        .filter(|row| ids.contains(row.id()))
+        .filter_map(|row| Some((*row.id(), T::row_text(row).ok()?)))
+        .collect::<HashMap<Uuid, String>>();
+    for edit in edits.iter_mut().filter(|v| v.before_row.is_some()) {
+        edit.after_row = after.get(&edit.id).cloned();
+    }
+    Ok(edits)
+}
+
+/// Reverses each edit in `edits` in `data`, in reverse order.
+fn revert<T: Tabular<U>, U: Columnar>(data: &mut T, edits: &[Edit]) {
+    for edit in edits.iter().rev() {
+        let _ = edit.inverse().write(data);
+    }
+}
+
+/// The `insert_rows` function adds each of `rows` to the end of `data`, and returns the edits
+/// made.  Each row is validated by [`Tabular::insert_row`], and none are kept if any is invalid.
+pub fn insert_rows<T: Tabular<U>, U: Columnar>(
+    data: &mut T,
+    rows: &[U],
+) -> Result<Vec<Edit>, EditError> {
+    let mut edits = Vec::new();
+    for row in rows {
+        let edit = Edit {
+            id: *row.id(),
+            column: data.len(),
+            before: String::new(),
+            after: T::row_text(row)?,
+            kind: EditKind::Insert,
+            ..Default::default()
+        };
+        if let Err(e) = edit.write(data) {
+            revert(data, &edits);
+            return Err(e);
+        }
+        edits.push(edit);
+    }
+    Ok(edits)
+}
+
+/// The `remove_rows` function removes the rows of `data` with an id in `ids`, and returns the
+/// edits made.  If any row is missing, restores the rows already removed and returns the error.
+pub fn remove_rows<T: Tabular<U>, U: Columnar>(
+    data: &mut T,
+    ids: &[Uuid],
+) -> Result<Vec<Edit>, EditError> {
+    let mut edits = Vec::new();
+    for id in ids {
+        match data.remove_row(id) {
+            Ok((position, row)) => edits.push(Edit {
+                id: *id,
+                column: position,
+                before: row,
+                after: String::new(),
+                kind: EditKind::Remove,
+                ..Default::default()
+            }),
+            Err(e) => {
+                revert(data, &edits);
+                return Err(e);
+            }
+        }
+    }
+    Ok(edits)
+}
+
+/// The `BulkScope` enum selects the rows changed by a [`BulkEdit`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum BulkScope {
+    /// Rows checked or selected in the table.
+    #[default]
+    Marked,
+    /// Every row whose value in the edited column equals the text, ignoring case.  Used to
+    /// rename a value across the dataset, such as a renamed street.
+    Matching(String),
+}
+
+/// The `SelectMode` enum is how a set of rows chosen at once, as by a selection drawn on a map,
+/// combines with the rows already selected.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum SelectMode {
+    /// The rows replace the selection.
+    #[default]
+    Replace,
+    /// The rows join the selection.
+    Add,
+    /// The rows leave the selection.
+    Remove,
+}
+
+impl SelectMode {
+    /// Returns the mode for the modifier keys held: shift adds and command removes.
+    #[cfg(feature = "gui")]
+    pub fn from_modifiers(modifiers: &egui::Modifiers) -> Self {
+        if modifiers.shift {
+            Self::Add
+        } else if modifiers.command {
+            Self::Remove
+        } else {
+            Self::Replace
+        }
+    }
+
+    /// Combines `ids` with the `selection`.
+    pub fn apply(&self, selection: &mut HashSet<Uuid>, ids: &HashSet<Uuid>) {
+        match self {
+            Self::Replace => *selection = ids.clone(),
+            Self::Add => selection.extend(ids.iter().copied()),
+            Self::Remove => selection.retain(|v| !ids.contains(v)),
+        }
+    }
+}
+
+/// The `BulkEdit` struct describes a change of one column to a single value across many rows.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BulkEdit {
+    pub column: usize,
+    pub value: String,
+    pub scope: BulkScope,
+}
+
+impl BulkEdit {
+    /// The `preview` method returns the edit each row of `data` in scope would receive.  Rows
+    /// in `marked` are in scope for [`BulkScope::Marked`].  Skips rows already holding the new
+    /// value.
+    pub fn preview<T: Tabular<U>, U: Columnar + Clone>(
+        &self,
+        data: &T,
+        marked: &HashSet<Uuid>,
+    ) -> Vec<Edit> {
+        data.rows_ref()
+            .iter()
+            .filter(|row| match &self.scope {
+                BulkScope::Marked => marked.contains(row.id()),
+                BulkScope::Matching(_) => true,
+            })
+            .filter_map(|row| {
+                let before = row
+                    .cells()
+                    .get(self.column)
+                    .map(|v| v.to_string())
+                    .unwrap_or_default();
+                if let BulkScope::Matching(text) = &self.scope {
+                    if before.to_lowercase() != text.trim().to_lowercase() {
+                        return None;
+                    }
+                }
+                match before == self.value {
+                    true => None,
+                    false => Some(Edit {
+                        id: *row.id(),
+                        column: self.column,
+                        before,
+                        after: self.value.clone(),
+                        kind: EditKind::Cell,
+                        ..Default::default()
+                    }),
+                }
+            })
+            .collect()
+    }
+
+    /// Converts the edits in `preview` to the changes taken by [`apply_edits`].
+    pub fn changes(preview: &[Edit]) -> Vec<(Uuid, usize, String)> {
+        preview
+            .iter()
+            .map(|v| (v.id, v.column, v.after.clone()))
+            .collect()
+    }
 }
 
 pub trait Tabular<T: Columnar> {
     fn headers() -> Vec<String>;
     fn rows(&self) -> Vec<T>;
+    /// Borrowed access to the rows.  Defaults to the owned rows from [`Tabular::rows`].  Types
+    /// that store their rows should return them borrowed, so that a [`TableView`] can read the
+    /// rows each frame without cloning them.
+    fn rows_ref(&self) -> Cow<'_, [T]>
+    where
+        T: Clone,
+    {
+        Cow::Owned(self.rows())
+    }
+    /// The row at position `index`, or `None` past the end.  Defaults to indexing
+    /// [`Tabular::rows_ref`].  Types that decode their rows on demand should read only the
+    /// requested row, so that a [`TableView`] can draw the rows in view of a large dataset
+    /// without decoding the rest.
+    fn row(&self, index: usize) -> Option<Cow<'_, T>>
+    where
+        T: Clone,
+    {
+        match self.rows_ref() {
+            Cow::Borrowed(rows) => rows.get(index).map(Cow::Borrowed),
+            Cow::Owned(rows) => rows.into_iter().nth(index).map(Cow::Owned),
+        }
+    }
+    /// Sorts the rows by column `column_index`, in descending order if `reverse` is `true`.
+    /// Implementations must use a stable sort, and must not reverse equal rows, so that
+    /// [`Tabular::sort_by_spec`] can sort by more than one column.
     fn sort_by_col(&mut self, column_index: usize, reverse: bool);
+    /// Sorts the rows by each key in `spec`.  Sorts by the lowest priority key first, so that
+    /// each stable sort by a higher priority key keeps the order of rows it considers equal.
+    fn sort_by_spec(&mut self, spec: &SortSpec) {
+        for key in spec.iter().rev() {
+            self.sort_by_col(key.column, key.reverse);
+        }
+    }
+    /// Writes `value` to column `column` of the row with id `id`, after validating it for the
+    /// column.  Tables are read-only unless they implement this method.
+    fn set_value(&mut self, id: &Uuid, column: usize, value: &str) -> Result<(), EditError> {
+        let _ = (id, column, value);
+        Err(EditError::ReadOnly)
+    }
+    /// Returns `true` if column `column` accepts edits through [`Tabular::set_value`].
+    fn is_editable(column: usize) -> bool {
+        let _ = column;
+        false
+    }
+    /// Writes `row` to text, kept in the edit history to restore the row after it is removed.
+    /// Tables cannot add or remove rows unless they implement this method along with
+    /// [`Tabular::insert_row`] and [`Tabular::remove_row`].
+    fn row_text(row: &T) -> Result<String, EditError> {
+        let _ = row;
+        Err(EditError::ReadOnly)
+    }
+    /// Inserts the row written to `row` by [`Tabular::row_text`] at position `index`, after
+    /// validating it.  An `index` past the end appends the row.
+    fn insert_row(&mut self, index: usize, row: &str) -> Result<(), EditError> {
+        let _ = (index, row);
+        Err(EditError::ReadOnly)
+    }
+    /// Removes the row with id `id`, returning its position and its text from
+    /// [`Tabular::row_text`].
+    fn remove_row(&mut self, id: &Uuid) -> Result<(usize, String), EditError> {
+        let _ = id;
+        Err(EditError::ReadOnly)
+    }
+    /// Replaces the row with id `id` by the row written to `row` by [`Tabular::row_text`], in
+    /// place.  Used to undo and redo cell edits exactly.  Defaults to removing the row and
+    /// inserting `row` at its position, restoring the row if `row` is rejected.
+    fn replace_row(&mut self, id: &Uuid, row: &str) -> Result<(), EditError> {
+        let (index, before) = self.remove_row(id)?;
+        if let Err(e) = self.insert_row(index, row) {
+            let _ = self.insert_row(index, &before);
+            return Err(e);
+        }
+        Ok(())
+    }
     fn len(&self) -> usize {
         self.rows().len()
     }
@@ -531,6 +2514,11 @@ pub trait Tabular<T: Columnar> {
 pub trait Columnar {
     fn names() -> Vec<String>;
     fn values(&self) -> Vec<String>;
+    /// Typed values of each column, used by the [`TableView`] to sort, search and align cells.
+    /// Defaults to a [`Cell::Text`] for each of `values`.
+    fn cells(&self) -> Vec<Cell> {
+        self.values().into_iter().map(Cell::Text).collect()
+    }
     fn id(&self) -> &Uuid;
 }
 
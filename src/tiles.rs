@@ -0,0 +1,324 @@
+//! The `tiles` module reads basemap tiles from a local MBTiles file or a `z/x/y` tile directory.
+//!
+//! Tiles use the XYZ scheme of web maps in EPSG:3857, the same projection as
+//! [`AddressPoint::point`](crate::addresses::AddressPoint) and
+//! [`Parcel::geometry`](crate::parcels::Parcel), so a tile covers the map coordinates returned by
+//! [`TileId::bounds`] with no reprojection.  MBTiles files store rows in the TMS scheme, counting
+//! up from the south, and [`Tileset::tile`] flips the row when reading them.
+use crate::utils::EARTH_RADIUS;
+use galileo_types::cartesian::Rect;
+use polite::Polite;
+use serde::{Deserialize, Serialize};
+use std::{f64::consts::PI, io};
+#[cfg(feature = "gui")]
+use std::{
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+/// Width of a tile in pixels.
+pub const TILE_SIZE: f64 = 256.0;
+/// Greatest number of tiles requested to cover one view.
+pub const MAX_TILES: usize = 256;
+
+/// Half the width of the EPSG:3857 world, in meters.
+fn half_world() -> f64 {
+    PI * EARTH_RADIUS
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// The `TileSource` enum locates a tileset in the dataset catalog.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum TileSource {
+    /// An MBTiles file.
+    Mbtiles { path: String },
+    /// A directory of tiles at `path/{z}/{x}/{y}.{extension}`.
+    Directory { path: String, extension: String },
+}
+
+/// The `TileFormat` enum is the encoding of the tiles in a tileset.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, strum_macros::Display)]
+pub enum TileFormat {
+    Png,
+    Jpeg,
+    Webp,
+    /// Mapbox vector tiles.
+    Pbf,
+}
+
+impl TileFormat {
+    /// Returns the format named by a file extension or the `format` entry of MBTiles metadata.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_lowercase().as_str() {
+            "png" => Some(Self::Png),
+            "jpg" | "jpeg" => Some(Self::Jpeg),
+            "webp" => Some(Self::Webp),
+            "pbf" | "mvt" => Some(Self::Pbf),
+            _ => None,
+        }
+    }
+
+    pub fn is_vector(&self) -> bool {
+        *self == Self::Pbf
+    }
+}
+
+/// The `TileId` struct identifies a tile by zoom level, column and row in the XYZ scheme.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TileId {
+    pub z: u8,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl TileId {
+    pub fn new(z: u8, x: u32, y: u32) -> Self {
+        Self { z, x, y }
+    }
+
+    /// Width of the tiles at zoom level `z` in meters.
+    pub fn span(z: u8) -> f64 {
+        2.0 * half_world() / f64::from(1u32 << z)
+    }
+
+    /// Returns the area covered by the tile in EPSG:3857.
+    pub fn bounds(&self) -> Rect {
+        let span = Self::span(self.z);
+        let x_min = -half_world() + f64::from(self.x) * span;
+        let y_max = half_world() - f64::from(self.y) * span;
+        Rect::new(x_min, y_max - span, x_min + span, y_max)
+    }
+
+    /// Returns the zoom level whose tiles best match `resolution` in meters per pixel, limited
+    /// to the range from `min` to `max`.
+    pub fn zoom_for(resolution: f64, min: u8, max: u8) -> u8 {
+        let zoom = (2.0 * half_world() / (TILE_SIZE * resolution))
+            .log2()
+            .round();
+        (zoom.max(0.0) as u8).clamp(min, max)
+    }
+
+    /// Returns the tiles at zoom level `z` covering `bounds`, up to [`MAX_TILES`].
+    pub fn covering(bounds: &Rect, z: u8) -> Vec<Self> {
+        let span = Self::span(z);
+        let last = (1u32 << z) - 1;
+        let index = |v: f64| (v / span).floor().clamp(0.0, f64::from(last)) as u32;
+        let (x_min, x_max) = (
+            index(bounds.x_min() + half_world()),
+            index(bounds.x_max() + half_world()),
+        );
+        let (y_min, y_max) = (
+            index(half_world() - bounds.y_max()),
+            index(half_world() - bounds.y_min()),
+        );
+        let mut tiles = Vec::new();
+        for y in y_min..=y_max {
+            for x in x_min..=x_max {
+                if tiles.len() == MAX_TILES {
+                    return tiles;
+                }
+                tiles.push(Self::new(z, x, y));
+            }
+        }
+        tiles
+    }
+}
+
+#[cfg(feature = "gui")]
+#[derive(Debug)]
+enum TileStore {
+    Mbtiles(rusqlite::Connection),
+    Directory { path: PathBuf, extension: String },
+}
+
+/// The `Tileset` struct is an open [`TileSource`].  Reading tiles needs the `gui` feature, which
+/// brings in SQLite for MBTiles files and gzip for compressed vector tiles.
+#[cfg(feature = "gui")]
+#[derive(Debug)]
+pub struct Tileset {
+    store: TileStore,
+    pub format: TileFormat,
+    pub min_zoom: u8,
+    pub max_zoom: u8,
+}
+
+#[cfg(feature = "gui")]
+impl Tileset {
+    /// Opens the tileset at `source`.  The format and zoom range of an MBTiles file come from
+    /// its metadata, and those of a directory from its extension and subdirectories.
+    pub fn open(source: &TileSource) -> Polite<Self> {
+        match source {
+            TileSource::Mbtiles { path } => Self::mbtiles(path),
+            TileSource::Directory { path, extension } => Self::directory(path, extension),
+        }
+    }
+
+    fn mbtiles<P: AsRef<Path>>(path: P) -> Polite<Self> {
+        let sql = |e: rusqlite::Error| invalid(e.to_string());
+        let connection =
+            rusqlite::Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+                .map_err(sql)?;
+        let mut metadata = Vec::new();
+        {
+            let mut statement = connection
+                .prepare("SELECT name, value FROM metadata")
+                .map_err(sql)?;
+            let rows = statement
+                .query_map([], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+                })
+                .map_err(sql)?;
+            for row in rows {
+                metadata.push(row.map_err(sql)?);
+            }
+        }
+        let value = |name: &str| {
+            metadata
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.clone())
+        };
+        let format = value("format").unwrap_or_else(|| "png".to_string());
+        let format = TileFormat::from_extension(&format)
+            .ok_or_else(|| invalid(format!("Unsupported tile format {format}.")))?;
+        let zoom = |name: &str, default: u8| {
+            value(name)
+                .and_then(|v| v.parse::<u8>().ok())
+                .unwrap_or(default)
+        };
+        Ok(Self {
+            format,
+            min_zoom: zoom("minzoom", 0),
+            max_zoom: zoom("maxzoom", 22),
+            store: TileStore::Mbtiles(connection),
+        })
+    }
+
+    fn directory(path: &str, extension: &str) -> Polite<Self> {
+        let format = TileFormat::from_extension(extension)
+            .ok_or_else(|| invalid(format!("Unsupported tile format {extension}.")))?;
+        let levels = fs::read_dir(path)?
+            .filter_map(|v| v.ok())
+            .filter_map(|v| v.file_name().to_str().and_then(|v| v.parse::<u8>().ok()))
+            .collect::<Vec<u8>>();
+        Ok(Self {
+            format,
+            min_zoom: levels.iter().min().copied().unwrap_or_default(),
+            max_zoom: levels.iter().max().copied().unwrap_or_default(),
+            store: TileStore::Directory {
+                path: PathBuf::from(path),
+                extension: extension.to_string(),
+            },
+        })
+    }
+
+    /// Reads the encoded tile `id`, or `None` if the tileset does not hold it.  Gzipped vector
+    /// tiles are decompressed.
+    pub fn tile(&self, id: &TileId) -> Polite<Option<Vec<u8>>> {
+        let data = match &self.store {
+            TileStore::Mbtiles(connection) => {
+                let row = (1u32 << id.z) - 1 - id.y;
+                let result = connection.query_row(
+                    "SELECT tile_data FROM tiles WHERE zoom_level = ?1 AND tile_column = ?2 AND tile_row = ?3",
+                    (id.z, id.x, row),
+                    |row| row.get::<_, Vec<u8>>(0),
+                );
+                match result {
+                    Ok(data) => data,
+                    Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+                    Err(e) => return Err(invalid(e.to_string()).into()),
+                }
+            }
+            TileStore::Directory { path, extension } => {
+                let file = path
+                    .join(id.z.to_string())
+                    .join(id.x.to_string())
+                    .join(format!("{}.{extension}", id.y));
+                match fs::read(file) {
+                    Ok(data) => data,
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        };
+        if data.starts_with(&[0x1f, 0x8b]) {
+            let mut inflated = Vec::new();
+            flate2::read::GzDecoder::new(&data[..]).read_to_end(&mut inflated)?;
+            return Ok(Some(inflated));
+        }
+        Ok(Some(data))
+    }
+}
+
+/// The `TilePath` struct is a line or polygon ring from a vector tile.  Points are fractions of
+/// the tile width from its top left corner.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TilePath {
+    pub points: Vec<(f32, f32)>,
+    /// Set for polygon rings, which connect the last point back to the first.
+    pub closed: bool,
+}
+
+/// The `vector_paths` function decodes the lines and polygon rings of the Mapbox vector tile
+/// `data`.  Point features are skipped, since the basemap draws only outlines.
+pub fn vector_paths(data: &[u8]) -> Polite<Vec<TilePath>> {
+    use geozero::mvt::tile::GeomType;
+    use geozero::mvt::{Message, Tile};
+
+    let tile = Tile::decode(data).map_err(|e| invalid(e.to_string()))?;
+    let mut paths = Vec::new();
+    for layer in &tile.layers {
+        let extent = layer.extent.unwrap_or(4096) as f32;
+        for feature in &layer.features {
+            let closed = match feature.r#type() {
+                GeomType::Linestring => false,
+                GeomType::Polygon => true,
+                _ => continue,
+            };
+            let mut cursor = (0i32, 0i32);
+            let mut points: Vec<(f32, f32)> = Vec::new();
+            let mut commands = feature.geometry.iter();
+            while let Some(command) = commands.next() {
+                let (id, count) = (command & 0x7, command >> 3);
+                match id {
+                    1 | 2 => {
+                        for _ in 0..count {
+                            let (Some(dx), Some(dy)) = (commands.next(), commands.next()) else {
+                                break;
+                            };
+                            let zigzag = |v: u32| ((v >> 1) as i32) ^ -((v & 1) as i32);
+                            cursor = (cursor.0 + zigzag(*dx), cursor.1 + zigzag(*dy));
+                            if id == 1 && points.len() > 1 {
+                                paths.push(TilePath {
+                                    points: std::mem::take(&mut points),
+                                    closed,
+                                });
+                            } else if id == 1 {
+                                points.clear();
+                            }
+                            points.push((cursor.0 as f32 / extent, cursor.1 as f32 / extent));
+                        }
+                    }
+                    7 => {
+                        if points.len() > 1 {
+                            paths.push(TilePath {
+                                points: std::mem::take(&mut points),
+                                closed: true,
+                            });
+                        }
+                    }
+                    _ => break,
+                }
+            }
+            if points.len() > 1 {
+                paths.push(TilePath { points, closed });
+            }
+        }
+    }
+    Ok(paths)
+}
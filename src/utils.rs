@@ -3,6 +3,7 @@ use indicatif::{ProgressBar, ProgressStyle};
 use polite::Polite;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::io::Write;
 use std::{fs, io, path, time};
 use tracing::info;
 
@@ -52,11 +53,106 @@ pub fn point_bounds(point: &Point2d, buffer: f64) -> Rect {
     Rect::new(xmin, ymin, xmax, ymax)
 }
 
+/// Equatorial radius of the WGS 84 ellipsoid in meters, used by the EPSG:3857 projection.
+pub const EARTH_RADIUS: f64 = 6_378_137.0;
+
+/// The `mercator_to_lonlat` function converts a point in EPSG:3857 to longitude and latitude in
+/// degrees.
+pub fn mercator_to_lonlat(point: &Point2d) -> (f64, f64) {
+    let lon = (point.x() / EARTH_RADIUS).to_degrees();
+    let lat = (point.y() / EARTH_RADIUS).sinh().atan().to_degrees();
+    (lon, lat)
+}
+
+/// The `lonlat_to_mercator` function converts longitude and latitude in degrees to a point in
+/// EPSG:3857.
+pub fn lonlat_to_mercator(lon: f64, lat: f64) -> Point2d {
+    let x = lon.to_radians() * EARTH_RADIUS;
+    let y = lat.to_radians().tan().asinh() * EARTH_RADIUS;
+    Point2d::new(x, y)
+}
+
+/// The `mercator_distance` function returns the ground distance in meters between two points in
+/// EPSG:3857.  Planar distances in Web Mercator are inflated by the scale factor `1 / cos(lat)`,
+/// so we divide by the scale factor at the mean latitude of the two points.
+pub fn mercator_distance(from: &Point2d, to: &Point2d) -> f64 {
+    let dx = to.x() - from.x();
+    let dy = to.y() - from.y();
+    let planar = (dx * dx + dy * dy).sqrt();
+    let mid = (from.y() + to.y()) / 2.0;
+    planar / (mid / EARTH_RADIUS).cosh()
+}
+
+/// The `mercator_area` function returns the ground area in square meters of the polygon `ring` in
+/// EPSG:3857.  Planar areas are inflated by the square of the scale factor, so we divide by the
+/// square of the scale factor at the mean latitude of the ring.
+pub fn mercator_area(ring: &[Point2d]) -> f64 {
+    if ring.len() < 3 {
+        return 0.0;
+    }
+    let planar = ring
+        .iter()
+        .zip(ring.iter().cycle().skip(1))
+        .map(|(a, b)| a.x() * b.y() - b.x() * a.y())
+        .sum::<f64>()
+        .abs()
+        / 2.0;
+    let mid = ring.iter().map(|v| v.y()).sum::<f64>() / ring.len() as f64;
+    planar / (mid / EARTH_RADIUS).cosh().powi(2)
+}
+
+/// The `point_in_ring` function returns `true` if `point` lies inside the polygon `ring`, by
+/// counting the sides of the ring crossed by a horizontal line from the point.  The ring may be
+/// open or closed, and may cross itself, as a lasso drawn by hand often does.
+pub fn point_in_ring(point: &Point2d, ring: &[Point2d]) -> bool {
+    ring.iter()
+        .zip(ring.iter().cycle().skip(1))
+        .filter(|(a, b)| (a.y() > point.y()) != (b.y() > point.y()))
+        .filter(|(a, b)| {
+            point.x() < a.x() + (point.y() - a.y()) / (b.y() - a.y()) * (b.x() - a.x())
+        })
+        .count()
+        % 2
+        == 1
+}
+
+/// The `save` function serializes `data` to a file at location `path` with `bincode`, replacing
+/// the file as by [`replace_file`].
 pub fn save<T: Serialize, P: AsRef<path::Path>>(data: &T, path: P) -> Polite<()> {
     info!("Serializing to binary.");
     let encode = bincode::serialize(data)?;
     info!("Writing to file.");
-    fs::write(path, encode)?;
+    replace_file(path, |writer| Ok(writer.write_all(&encode)?))
+}
+
+/// The `replace_file` function writes a file at location `path` by passing `write` a writer to
+/// a temporary file beside it, then renaming the temporary file over `path`.  Memory maps of the
+/// old file keep reading its old contents, where truncating the file in place would be
+/// undefined behavior for them.  The temporary file is removed if writing fails.
+pub fn replace_file<P, F>(path: P, write: F) -> Polite<()>
+where
+    P: AsRef<path::Path>,
+    F: FnOnce(&mut io::BufWriter<fs::File>) -> Polite<()>,
+{
+    let path = path.as_ref();
+    let mut name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Path names no file."))?
+        .to_os_string();
+    name.push(".tmp");
+    let temp = path.with_file_name(name);
+    let written: Polite<()> = (|| {
+        let mut writer = io::BufWriter::new(fs::File::create(&temp)?);
+        write(&mut writer)?;
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+        Ok(())
+    })();
+    if let Err(e) = written {
+        let _ = fs::remove_file(&temp);
+        return Err(e);
+    }
+    fs::rename(&temp, path)?;
     Ok(())
 }
 
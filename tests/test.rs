@@ -2,8 +2,11 @@ use address::prelude::Portable;
 use polite::Polite;
 use tracing::info;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+#[cfg(feature = "gui")]
 use whimsy::prelude::Command;
+#[cfg(feature = "gui")]
 use whimsy::prelude::Modifiers;
+#[cfg(feature = "gui")]
 use winit::keyboard::ModifiersState;
 
 fn init_tracing() {
@@ -18,6 +21,7 @@ fn init_tracing() {
     {};
 }
 
+#[cfg(feature = "gui")]
 #[test]
 fn observer() -> Polite<()> {
     if tracing_subscriber::registry()
@@ -46,6 +50,7 @@ fn observer() -> Polite<()> {
     Ok(())
 }
 
+#[cfg(feature = "gui")]
 fn parses_keys() -> Polite<()> {
     let mut input = ('a'..='z')
         .into_iter()
@@ -65,6 +70,7 @@ fn parses_keys() -> Polite<()> {
     Ok(())
 }
 
+#[cfg(feature = "gui")]
 fn parses_modifier() -> Polite<()> {
     let c1 = "<cr> + a";
     let c2 = "<control> + b";
@@ -103,6 +109,7 @@ fn parses_modifier() -> Polite<()> {
     Ok(())
 }
 
+#[cfg(feature = "gui")]
 fn parses_command() -> Polite<()> {
     let c1 = "<cr> + a";
     let c2 = "<control> + b";
@@ -160,11 +167,12 @@ fn import_addresses() -> Polite<()> {
     info!("Subscriber initialized.");
     let sa = address::prelude::GrantsPassSpatialAddresses::from_csv("data/addresses.csv").unwrap();
     let sa = address::prelude::SpatialAddresses::from(&sa[..]);
-    let sa = whimsy::prelude::AddressPoints::from(&sa);
-    sa.save("data/addresses.data")?;
+    // Re-importing keeps the ids of the records already in the dataset.
+    whimsy::prelude::AddressPoints::import(&sa, "data/addresses.data")?;
     Ok(())
 }
 
+#[cfg(feature = "gui")]
 #[test]
 fn iterate_enum() -> Polite<()> {
     use strum::IntoEnumIterator;
@@ -204,3 +212,1397 @@ fn iterate_enum() -> Polite<()> {
 
     Ok(())
 }
+
+#[test]
+fn stable_address_ids() -> Polite<()> {
+    init_tracing();
+    let address = address::prelude::SpatialAddress::default();
+    let first = whimsy::prelude::AddressPoint::from(&address);
+    let second = whimsy::prelude::AddressPoint::from(&address);
+    assert_eq!(first.id, second.id);
+    let mut points = whimsy::prelude::AddressPoints {
+        records: vec![first.clone(), second].into(),
+    };
+    points.rekey();
+    assert_eq!(points.records[0].id, first.id);
+    assert_ne!(points.records[0].id, points.records[1].id);
+    let mut rekeyed = points.clone();
+    rekeyed.rekey();
+    assert_eq!(points, rekeyed);
+    Ok(())
+}
+
+#[test]
+fn kept_address_ids() -> Polite<()> {
+    init_tracing();
+    use whimsy::prelude::{AddressColumns, AddressPoint, AddressPoints};
+    let mut values = vec![String::new(); AddressColumns::names().len()];
+    values[AddressColumns::Number as usize] = "100".to_string();
+    values[AddressColumns::StreetName as usize] = "Main".to_string();
+    let first = AddressPoint::pending(&values, 42.43, -123.32).unwrap();
+    values[AddressColumns::Number as usize] = "200".to_string();
+    let second = AddressPoint::pending(&values, 42.44, -123.33).unwrap();
+    let previous = AddressPoints {
+        records: vec![first.clone(), second.clone()].into(),
+    };
+    // At the source, the first point moves and the second is renamed.
+    let mut moved = first.clone();
+    moved.locate(42.45, -123.34);
+    let mut renamed = second.clone();
+    renamed
+        .set_cell(&AddressColumns::StreetName, "Oak")
+        .unwrap();
+    let mut fresh = AddressPoints {
+        records: vec![moved, renamed.clone()].into(),
+    };
+    fresh.rekey();
+    assert_ne!(fresh.records[0].id, first.id);
+    assert_ne!(fresh.records[1].id, second.id);
+    fresh.keep_ids(&previous);
+    assert_eq!(fresh.records[0].id, first.id);
+    assert_eq!(fresh.records[1].id, second.id);
+
+    // Random ids from before ids were content-derived are replaced on load, kept ids are not.
+    fresh.records[1].id = uuid::Uuid::new_v4();
+    let path = std::env::temp_dir().join("whimsy_kept_ids.data");
+    fresh.save(&path)?;
+    let loaded = AddressPoints::load(&path)?;
+    std::fs::remove_file(&path)?;
+    assert_eq!(loaded.records[0].id, first.id);
+    assert_eq!(
+        loaded.records[1].id,
+        AddressPoint::stable_id(&AddressPoint::id_key(&renamed.address))
+    );
+    Ok(())
+}
+
+#[test]
+fn address_snapshot_diff() -> Polite<()> {
+    init_tracing();
+    let address = address::prelude::SpatialAddress::default();
+    let point = whimsy::prelude::AddressPoint::from(&address);
+    let before = whimsy::prelude::AddressPoints {
+        records: vec![point.clone()].into(),
+    };
+    let mut moved = point.clone();
+    moved.point.x += 10.0;
+    let mut added = point.clone();
+    added.id = uuid::Uuid::new_v4();
+    let after = whimsy::prelude::AddressPoints {
+        records: vec![moved, added].into(),
+    };
+    let log = before.diff(&after);
+    assert_eq!(log.count(&whimsy::prelude::ChangeKind::Moved), 1);
+    assert_eq!(log.count(&whimsy::prelude::ChangeKind::Added), 1);
+    assert_eq!(log.count(&whimsy::prelude::ChangeKind::Removed), 0);
+    assert!(before.diff(&before).is_empty());
+
+    // Records paired by their components come out in the order of the later snapshot.
+    use whimsy::prelude::{AddressColumns, AddressPoints};
+    let named = |name: &str| {
+        let mut record = point.clone();
+        record.set_cell(&AddressColumns::StreetName, name).unwrap();
+        record.id = uuid::Uuid::new_v4();
+        record
+    };
+    let olds = ["Ash", "Birch", "Cedar", "Douglas", "Elm"].map(named);
+    let news = olds.clone().map(|mut record| {
+        record.id = uuid::Uuid::new_v4();
+        record.point.x += 10.0;
+        record
+    });
+    let before = AddressPoints {
+        records: olds.to_vec().into(),
+    };
+    let after = AddressPoints {
+        records: news.to_vec().into(),
+    };
+    let ids = before
+        .diff(&after)
+        .records
+        .iter()
+        .map(|v| v.id)
+        .collect::<Vec<uuid::Uuid>>();
+    assert_eq!(ids, news.iter().map(|v| v.id).collect::<Vec<uuid::Uuid>>());
+    Ok(())
+}
+
+#[test]
+fn mapped_round_trip() -> Polite<()> {
+    init_tracing();
+    let address = address::prelude::SpatialAddress::default();
+    let mut points = whimsy::prelude::AddressPoints {
+        records: vec![whimsy::prelude::AddressPoint::from(&address); 3].into(),
+    };
+    points.rekey();
+    let path = std::env::temp_dir().join("whimsy_mapped_round_trip.data");
+    points.save_mapped(&path)?;
+    assert!(whimsy::prelude::is_mapped(&path)?);
+    let mapped = whimsy::prelude::AddressPoints::open_mapped(&path)?;
+    assert_eq!(mapped.len(), 3);
+    assert_eq!(mapped.get(1)?, points.records[1]);
+    assert_eq!(mapped.range(1..10)?.len(), 2);
+    assert!(mapped.get(3).is_err());
+    // Loading keeps the records in the file, and decodes only the rows read.
+    let loaded = whimsy::prelude::AddressPoints::load(&path)?;
+    assert!(!loaded.records.is_decoded());
+    assert_eq!(loaded.records.len(), 3);
+    assert_eq!(loaded.records.row(2), Some(&points.records[2]));
+    assert!(loaded.records.row(3).is_none());
+    assert!(!loaded.records.is_decoded());
+    assert_eq!(loaded, points);
+    assert!(loaded.records.is_decoded());
+
+    // Saving again replaces the file rather than truncating it under the open maps.
+    let mut shorter = points.clone();
+    shorter.records.truncate(2);
+    shorter.save_mapped(&path)?;
+    assert_eq!(mapped.get(2)?, points.records[2]);
+    assert_eq!(
+        whimsy::prelude::AddressPoints::load(&path)?.records.len(),
+        2
+    );
+    points.save_mapped(&path)?;
+
+    // A corrupt offset makes the record unreadable rather than panicking.  The corrupt copy goes
+    // to its own file, since writing over a mapped file is undefined behavior.
+    let mut bytes = std::fs::read(&path)?;
+    bytes[24..32].copy_from_slice(&u64::MAX.to_le_bytes());
+    let corrupt_path = std::env::temp_dir().join("whimsy_mapped_corrupt.data");
+    std::fs::write(&corrupt_path, bytes)?;
+    let corrupt = whimsy::prelude::AddressPoints::open_mapped(&corrupt_path)?;
+    assert!(corrupt.bytes(0).is_none());
+    assert!(corrupt.get(1).is_err());
+    assert!(corrupt.row(0).is_none());
+    assert_eq!(corrupt.get(2)?, points.records[2]);
+    drop(corrupt);
+    std::fs::remove_file(&corrupt_path)?;
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[test]
+fn three_way_merge() -> Polite<()> {
+    init_tracing();
+    let address = address::prelude::SpatialAddress::default();
+    let first = whimsy::prelude::AddressPoint::from(&address);
+    let mut second = first.clone();
+    second.id = uuid::Uuid::new_v4();
+    let base = whimsy::prelude::AddressPoints {
+        records: vec![first.clone(), second.clone()].into(),
+    };
+    // Ours moves the first record and deletes the second.
+    let mut ours_first = first.clone();
+    ours_first.point.x += 1.0;
+    let ours = whimsy::prelude::AddressPoints {
+        records: vec![ours_first.clone()].into(),
+    };
+    // Theirs moves the first record somewhere else.
+    let mut theirs_first = first.clone();
+    theirs_first.point.x += 2.0;
+    let theirs = whimsy::prelude::AddressPoints {
+        records: vec![theirs_first, second.clone()].into(),
+    };
+    let mut merge = base.merge(&ours, &theirs)?;
+    assert_eq!(merge.applied, 1);
+    assert_eq!(merge.conflicts.len(), 1);
+    assert!(merge.resolve().is_err());
+    merge.resolve_all(whimsy::prelude::MergeSide::Ours);
+    let merged = merge.resolve()?;
+    assert_eq!(merged.records, vec![ours_first]);
+
+    // Moves on both sides conflict as one location, even when they change different fields.
+    let mut ours_first = first.clone();
+    ours_first.point.y += 1.0;
+    let mut theirs_first = first.clone();
+    theirs_first.address.latitude += 1.0;
+    let ours = whimsy::prelude::AddressPoints {
+        records: vec![ours_first, second.clone()].into(),
+    };
+    let theirs = whimsy::prelude::AddressPoints {
+        records: vec![theirs_first.clone(), second].into(),
+    };
+    let mut merge = base.merge(&ours, &theirs)?;
+    assert_eq!(merge.conflicts.len(), 1);
+    assert_eq!(merge.conflicts[0].field(), "location");
+    merge.resolve_all(whimsy::prelude::MergeSide::Theirs);
+    assert_eq!(merge.resolve()?.records[0], theirs_first);
+    Ok(())
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+struct Pair {
+    id: uuid::Uuid,
+    street: String,
+    number: i64,
+}
+
+impl whimsy::prelude::Columnar for Pair {
+    fn names() -> Vec<String> {
+        vec!["Street".to_string(), "Number".to_string()]
+    }
+
+    fn values(&self) -> Vec<String> {
+        vec![self.street.clone(), self.number.to_string()]
+    }
+
+    fn id(&self) -> &uuid::Uuid {
+        &self.id
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+struct Pairs(Vec<Pair>);
+
+impl whimsy::prelude::Filtration<Pairs, String> for Pairs {
+    fn filter(self, _: &String) -> Self {
+        self
+    }
+}
+
+impl whimsy::prelude::Tabular<Pair> for Pairs {
+    fn headers() -> Vec<String> {
+        <Pair as whimsy::prelude::Columnar>::names()
+    }
+
+    fn rows(&self) -> Vec<Pair> {
+        self.0.clone()
+    }
+
+    fn sort_by_col(&mut self, column_index: usize, reverse: bool) {
+        match (column_index, reverse) {
+            (0, false) => self.0.sort_by(|a, b| a.street.cmp(&b.street)),
+            (0, true) => self.0.sort_by(|a, b| b.street.cmp(&a.street)),
+            (_, false) => self.0.sort_by(|a, b| a.number.cmp(&b.number)),
+            (_, true) => self.0.sort_by(|a, b| b.number.cmp(&a.number)),
+        }
+    }
+
+    fn set_value(
+        &mut self,
+        id: &uuid::Uuid,
+        column: usize,
+        value: &str,
+    ) -> Result<(), whimsy::prelude::EditError> {
+        let row = self
+            .0
+            .iter_mut()
+            .find(|v| v.id == *id)
+            .ok_or(whimsy::prelude::EditError::MissingRow)?;
+        match column {
+            0 => row.street = value.to_string(),
+            _ => {
+                row.number = value
+                    .parse()
+                    .map_err(|_| whimsy::prelude::EditError::Invalid(value.to_string()))?
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "gui")]
+#[test]
+fn table_index_cache() {
+    use whimsy::prelude::{ColumnFilter, SortSpec, TableView};
+    let pair = |street: &str, number: i64| Pair {
+        id: uuid::Uuid::new_v4(),
+        street: street.to_string(),
+        number,
+    };
+    let pairs = Pairs(vec![pair("Oak", 2), pair("Elm", 1), pair("Ash", 3)]);
+    let mut table: TableView<Pairs, Pair, String> = TableView::new(pairs.clone());
+    assert!(table.update_index());
+    // The cached index is reused while the rows, search and filters are unchanged.
+    assert!(!table.update_index());
+    assert_eq!(table.row_ids().len(), 3);
+
+    table.with_sort(SortSpec::new().then(0, false));
+    assert!(table.update_index());
+    assert!(!table.update_index());
+    assert_eq!(table.row_id(0), Some(pairs.0[2].id));
+
+    table.search = "elm".to_string();
+    assert!(table.update_index());
+    assert!(!table.update_index());
+    assert_eq!(table.row_ids(), vec![pairs.0[1].id]);
+
+    table.search.clear();
+    table
+        .column_filters
+        .insert(1, ColumnFilter::Equals("3".to_string()));
+    assert!(table.update_index());
+    assert!(!table.update_index());
+    assert_eq!(table.row_ids(), vec![pairs.0[2].id]);
+
+    table.refresh();
+    assert!(table.update_index());
+    assert!(!table.update_index());
+
+    // Sorts, searches and filters leave the data revision alone, while edits and undos advance it.
+    assert_eq!(table.data_revision(), 0);
+    table
+        .edit(&[(pairs.0[0].id, 0, "Fir".to_string())])
+        .unwrap();
+    assert_eq!(table.data_revision(), 1);
+    table.undo().unwrap();
+    assert_eq!(table.data_revision(), 2);
+}
+
+#[cfg(feature = "gui")]
+#[test]
+fn table_selection() {
+    use std::collections::HashSet;
+    use whimsy::prelude::{ColumnFilter, SelectMode, SortSpec, TableView};
+    let pair = |street: &str, number: i64| Pair {
+        id: uuid::Uuid::new_v4(),
+        street: street.to_string(),
+        number,
+    };
+    let pairs = Pairs(vec![
+        pair("Ash", 1),
+        pair("Birch", 2),
+        pair("Cedar", 1),
+        pair("Douglas", 2),
+        pair("Elm", 1),
+    ]);
+    let id = |position: usize| pairs.0[position].id;
+    let ids = |positions: &[usize]| positions.iter().map(|v| id(*v)).collect::<HashSet<_>>();
+    let mut table: TableView<Pairs, Pair, String> = TableView::new(pairs.clone());
+    table.with_sort(SortSpec::new().then(0, false));
+    table.update_index();
+
+    // A plain click selects one row, and a shift-click selects the range from it.
+    assert!(table.focus_row(&id(1)));
+    assert_eq!(table.selection, ids(&[1]));
+    let shift = egui::Modifiers {
+        shift: true,
+        ..Default::default()
+    };
+    table.click_row(&id(3), &shift);
+    assert_eq!(table.selection, ids(&[1, 2, 3]));
+    // The range runs backward from the anchor too.
+    table.click_row(&id(0), &shift);
+    assert_eq!(table.selection, ids(&[0, 1]));
+
+    // A ctrl-click toggles a row without touching the rest.
+    let command = egui::Modifiers {
+        command: true,
+        ..Default::default()
+    };
+    table.click_row(&id(4), &command);
+    assert_eq!(table.selection, ids(&[0, 1, 4]));
+    table.click_row(&id(0), &command);
+    assert_eq!(table.selection, ids(&[1, 4]));
+
+    table.invert_selection();
+    assert_eq!(table.selection, ids(&[0, 2, 3]));
+
+    // Under a filter, select all and invert only touch the visible rows.
+    table
+        .column_filters
+        .insert(1, ColumnFilter::Equals("1".to_string()));
+    table.update_index();
+    table.select_none();
+    table.select_all();
+    assert_eq!(table.selection, ids(&[0, 2, 4]));
+    table.selection.insert(id(1));
+    table.invert_selection();
+    assert_eq!(table.selection, ids(&[1]));
+    // Rows hidden by the filter are skipped and counted.
+    let chosen = table.select_rows(&ids(&[1, 2, 3]), SelectMode::Replace, false);
+    assert_eq!(chosen, (1, 2));
+    assert_eq!(table.selection, ids(&[2]));
+    table.clear_filters();
+    table.update_index();
+
+    // Extending stops at the last and first rows instead of wrapping.
+    assert!(table.focus_row(&id(3)));
+    table.extend_next();
+    assert_eq!(table.selection, ids(&[3, 4]));
+    table.extend_next();
+    assert_eq!(table.current_row(), Some(id(4)));
+    assert_eq!(table.selection, ids(&[3, 4]));
+    assert!(table.focus_row(&id(1)));
+    table.extend_previous();
+    assert_eq!(table.selection, ids(&[0, 1]));
+    table.extend_previous();
+    assert_eq!(table.current_row(), Some(id(0)));
+    assert_eq!(table.selection, ids(&[0, 1]));
+}
+
+#[test]
+fn multi_column_sort() -> Polite<()> {
+    use whimsy::prelude::Tabular;
+    init_tracing();
+    let pair = |street: &str, number: i64| Pair {
+        id: uuid::Uuid::new_v4(),
+        street: street.to_string(),
+        number,
+    };
+    let mut pairs = Pairs(vec![pair("B", 1), pair("A", 2), pair("B", 3), pair("A", 1)]);
+    let spec = whimsy::prelude::SortSpec::new()
+        .then(0, false)
+        .then(1, true);
+    pairs.sort_by_spec(&spec);
+    let order = pairs
+        .0
+        .iter()
+        .map(|v| format!("{}{}", v.street, v.number))
+        .collect::<Vec<String>>();
+    assert_eq!(order, vec!["A2", "A1", "B3", "B1"]);
+
+    let mut spec = whimsy::prelude::SortSpec::new();
+    spec.set(1);
+    spec.push(0);
+    assert_eq!(spec.priority(0), Some(2));
+    spec.push(0);
+    assert!(spec.key(0).unwrap().reverse);
+    spec.set(0);
+    assert_eq!(spec.len(), 1);
+    assert!(!spec.key(0).unwrap().reverse);
+    Ok(())
+}
+
+#[test]
+fn typed_cells() -> Polite<()> {
+    use whimsy::prelude::Cell;
+    init_tracing();
+    // Numbers compare by value rather than lexically.
+    assert!(Cell::from(9i64).compare(&Cell::from(10i64)).is_lt());
+    assert!(Cell::from(2.5f64).compare(&Cell::from(2i64)).is_gt());
+    assert_eq!(Cell::from(None::<i64>), Cell::Empty);
+    assert!(Cell::from("Main").contains("mai", false));
+    assert!(!Cell::from("Main").contains("mai", true));
+
+    let mut pairs = vec![
+        Pair {
+            id: uuid::Uuid::new_v4(),
+            street: "10".to_string(),
+            number: 10,
+        },
+        Pair {
+            id: uuid::Uuid::new_v4(),
+            street: "9".to_string(),
+            number: 9,
+        },
+    ];
+    whimsy::prelude::sort_by_cell(&mut pairs, 0, false);
+    // Pair uses the default text cells, so the street column sorts lexically.
+    assert_eq!(pairs[0].number, 10);
+
+    // Enum cells sort in the order of their variants rather than by name.
+    assert!(Cell::Enum(3, "Temporary".to_string())
+        .compare(&Cell::Enum(5, "Other".to_string()))
+        .is_lt());
+    use whimsy::prelude::{AddressColumns, AddressPoint, AddressPoints};
+    let address = address::prelude::SpatialAddress::default();
+    let mut other = AddressPoint::from(&address);
+    other.set_cell(&AddressColumns::Status, "Other").unwrap();
+    let mut temporary = other.clone();
+    temporary.id = uuid::Uuid::new_v4();
+    temporary
+        .set_cell(&AddressColumns::Status, "Temporary")
+        .unwrap();
+    let mut points = AddressPoints {
+        records: vec![other, temporary.clone()].into(),
+    };
+    points.sort_by_col(AddressColumns::Status as usize, false);
+    assert_eq!(points.records[0], temporary);
+    Ok(())
+}
+
+#[test]
+fn column_layout() {
+    let mut config = whimsy::prelude::TableConfig::new().with_columns(&[2, 0]);
+    config.fit(4);
+    assert_eq!(config.visible_columns(), vec![2, 0]);
+    assert_eq!(config.columns.len(), 4);
+    config.set_visible(3, true);
+    config.move_column(3, 2);
+    assert_eq!(config.visible_columns(), vec![3, 2, 0]);
+    config.set_width(0, 120.0);
+    config.fit(3);
+    assert_eq!(config.visible_columns(), vec![2, 0]);
+    assert_eq!(config.width(0), Some(120.0));
+
+    let mut layouts = whimsy::prelude::TableLayouts::default();
+    layouts.insert("addresses".to_string(), config.clone());
+    let bytes = bincode::serialize(&layouts).unwrap();
+    let saved: whimsy::prelude::TableLayouts = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(saved.get("addresses"), Some(&config));
+}
+
+#[cfg(feature = "gui")]
+#[test]
+fn diff_column_layout() {
+    // The changes table of a diff view keeps its settings across comparisons and sessions.
+    let mut layouts = whimsy::prelude::TableLayouts::default();
+    let before = std::env::temp_dir().join("whimsy_layout_before.data");
+    let after = std::env::temp_dir().join("whimsy_layout_after.data");
+    let points = whimsy::prelude::AddressPoints::default();
+    points.save(&before).unwrap();
+    points.save(&after).unwrap();
+    let mut changes = whimsy::prelude::TableConfig::new().with_columns(&[1, 0]);
+    changes.fit(6);
+    layouts.insert("changes".to_string(), changes.clone());
+    let mut diff = whimsy::prelude::DiffView::new().with_layouts(layouts);
+    diff.before = before.display().to_string();
+    diff.after = after.display().to_string();
+    diff.compare().unwrap();
+    let table = diff.table.as_mut().unwrap();
+    assert_eq!(table.config, changes);
+    table.config.set_width(1, 80.0);
+    diff.compare().unwrap();
+    assert_eq!(diff.table.as_ref().unwrap().config.width(1), Some(80.0));
+    let mut stored = whimsy::prelude::TableLayouts::default();
+    diff.store_layout(&mut stored);
+    assert_eq!(stored.get("changes").and_then(|v| v.width(1)), Some(80.0));
+    std::fs::remove_file(&before).unwrap();
+    std::fs::remove_file(&after).unwrap();
+}
+
+#[test]
+fn column_filters() {
+    use whimsy::prelude::{Cell, ColumnFilter, ColumnFilters};
+    let mut filters = ColumnFilters::default();
+    assert!(!filters.is_active());
+    filters.insert(0, ColumnFilter::Contains("nw".to_string()));
+    filters.insert(
+        1,
+        ColumnFilter::Range {
+            min: Some(100.0),
+            max: None,
+        },
+    );
+    assert!(filters.is_active());
+    let row = vec![Cell::Text("NW Main".to_string()), Cell::Integer(120)];
+    assert!(filters.matches(&row, false));
+    assert!(!filters.matches(&row, true));
+    let low = vec![Cell::Text("NW Main".to_string()), Cell::Integer(80)];
+    assert!(!filters.matches(&low, false));
+    filters.insert(0, ColumnFilter::Equals("nw main".to_string()));
+    assert!(filters.matches(&row, false));
+    filters.insert(2, ColumnFilter::Equals("Current".to_string()));
+    let status = vec![
+        Cell::Text("NW Main".to_string()),
+        Cell::Integer(120),
+        Cell::Enum(2, "Retired".to_string()),
+    ];
+    assert!(!filters.matches(&status, false));
+    // The empty filter is active and keeps only cells without a value.
+    let empty = ColumnFilter::Empty;
+    assert!(empty.is_active());
+    assert!(empty.matches(&Cell::Empty, false));
+    assert!(empty.matches(&Cell::Text(String::new()), false));
+    assert!(!empty.matches(&Cell::Text("Main".to_string()), false));
+}
+
+#[test]
+fn search_modes() {
+    use whimsy::prelude::{Matcher, SearchMode};
+    let text = "NW Hawthorne Ave";
+    let substring = Matcher::new("thor", SearchMode::Substring, false).unwrap();
+    assert_eq!(substring.find(text).unwrap().ranges, vec![6..10]);
+    let word = Matcher::new("thor", SearchMode::WholeWord, false).unwrap();
+    assert!(word.find(text).is_none());
+    let word = Matcher::new("nw", SearchMode::WholeWord, false).unwrap();
+    assert!(word.is_match(text));
+    let regex = Matcher::new(r"^nw\s+h", SearchMode::Regex, false).unwrap();
+    assert_eq!(regex.find(text).unwrap().ranges, vec![0..4]);
+    assert!(Matcher::new("(", SearchMode::Regex, false).is_err());
+    let fuzzy = Matcher::new("hwa", SearchMode::Fuzzy, false).unwrap();
+    assert!(fuzzy.find(text).is_some());
+    assert!(fuzzy.find("Ave").is_none());
+    // Consecutive matches at the start of a word outrank scattered ones.
+    let close = fuzzy.find("Hwa Rd").unwrap();
+    let scattered = fuzzy.find(text).unwrap();
+    assert!(close.score > scattered.score);
+}
+
+#[test]
+fn edit_undo_redo() {
+    use whimsy::prelude::{apply_edits, EditError, EditHistory};
+    let first = uuid::Uuid::new_v4();
+    let second = uuid::Uuid::new_v4();
+    let mut pairs = Pairs(vec![
+        Pair {
+            id: first,
+            street: "Main".to_string(),
+            number: 1,
+        },
+        Pair {
+            id: second,
+            street: "Oak".to_string(),
+            number: 2,
+        },
+    ]);
+    let original = pairs.clone();
+    // An invalid value reverts the edits already made in the step.
+    let changes = vec![(first, 1, "10".to_string()), (second, 1, "ten".to_string())];
+    let result = apply_edits(&mut pairs, &changes);
+    assert_eq!(result, Err(EditError::Invalid("ten".to_string())));
+    assert_eq!(pairs, original);
+
+    let mut history = EditHistory::default();
+    let changes = vec![(first, 1, "10".to_string()), (second, 0, "Elm".to_string())];
+    history.record(apply_edits(&mut pairs, &changes).unwrap());
+    assert_eq!(pairs.0[0].number, 10);
+    assert_eq!(pairs.0[1].street, "Elm");
+    // The step undoes as a whole.
+    assert_eq!(history.undo(&mut pairs).unwrap().len(), 2);
+    assert_eq!(pairs, original);
+    assert!(history.can_redo());
+    history.redo(&mut pairs).unwrap();
+    assert_eq!(pairs.0[1].street, "Elm");
+    assert!(!history.can_redo());
+    // A step that cannot be undone is reported, and stays on the undo stack unchanged.
+    pairs.0.remove(0);
+    let edited = pairs.clone();
+    assert_eq!(history.undo(&mut pairs), Err(EditError::MissingRow));
+    assert_eq!(pairs, edited);
+    assert!(history.can_undo());
+
+    // Undo restores the exact values of tables that write their rows, rather than the
+    // displayed values.
+    use whimsy::prelude::{AddressColumns, AddressPoint, AddressPoints, Tabular};
+    let address = address::prelude::SpatialAddress::default();
+    let mut point = AddressPoint::from(&address);
+    *address::prelude::Address::street_name_mut(&mut point.address) = "Van Ness".to_string();
+    point.locate(42.123456789, -123.987654321);
+    let mut points = AddressPoints {
+        records: vec![point.clone()].into(),
+    };
+    let changes = vec![
+        (
+            point.id,
+            AddressColumns::StreetName as usize,
+            "Oak".to_string(),
+        ),
+        (
+            point.id,
+            AddressColumns::Location as usize,
+            "42.5, -123.5".to_string(),
+        ),
+    ];
+    let mut history = EditHistory::default();
+    history.record(apply_edits(&mut points, &changes).unwrap());
+    let edited = points.rows();
+    history.undo(&mut points).unwrap();
+    assert_eq!(points.records, vec![point]);
+    history.redo(&mut points).unwrap();
+    assert_eq!(points.rows(), edited);
+}
+
+#[test]
+fn bulk_edit_preview() {
+    use whimsy::prelude::{apply_edits, BulkEdit, BulkScope, EditHistory};
+    let pair = |street: &str, number: i64| Pair {
+        id: uuid::Uuid::new_v4(),
+        street: street.to_string(),
+        number,
+    };
+    let mut pairs = Pairs(vec![pair("Main", 1), pair("Oak", 2), pair("main", 3)]);
+    let original = pairs.clone();
+    // Rename a street across every matching row.
+    let bulk = BulkEdit {
+        column: 0,
+        value: "Central".to_string(),
+        scope: BulkScope::Matching("MAIN".to_string()),
+    };
+    let preview = bulk.preview(&pairs, &Default::default());
+    assert_eq!(preview.len(), 2);
+    let mut history = EditHistory::default();
+    history.record(apply_edits(&mut pairs, &BulkEdit::changes(&preview)).unwrap());
+    assert_eq!(pairs.0[0].street, "Central");
+    assert_eq!(pairs.0[2].street, "Central");
+    // Only marked rows change under the marked scope.
+    let bulk = BulkEdit {
+        column: 1,
+        value: "9".to_string(),
+        scope: BulkScope::Marked,
+    };
+    let marked = std::iter::once(pairs.0[1].id).collect();
+    assert_eq!(bulk.preview(&pairs, &marked).len(), 1);
+    // The whole rename undoes as one step.
+    history.undo(&mut pairs).unwrap();
+    assert_eq!(pairs, original);
+}
+
+#[test]
+fn group_summary() -> Polite<()> {
+    use whimsy::prelude::{Columnar, Summary};
+    let pair = |street: &str, number: i64| Pair {
+        id: uuid::Uuid::new_v4(),
+        street: street.to_string(),
+        number,
+    };
+    let pairs = [
+        pair("Main", 1),
+        pair("Oak", 2),
+        pair("Main", 3),
+        pair("Main", 3),
+    ];
+    let summary = Summary::new(pairs.iter(), &[0], Pair::names());
+    assert_eq!(summary.groups.len(), 2);
+    assert_eq!(summary.count(), 4);
+    let main = &summary.groups[0];
+    assert_eq!(main.keys[0].to_string(), "Main");
+    assert_eq!(main.count, 3);
+    assert_eq!(main.stats[1].distinct, 2);
+    // Pair uses text cells, so no column reports a range.
+    assert!(summary.numeric.is_empty());
+    assert_eq!(
+        summary.columns(),
+        vec!["Street", "Count", "Number Distinct"]
+    );
+    assert_eq!(summary.values(main), vec!["Main", "3", "2"]);
+    let path = std::env::temp_dir().join("whimsy_group_summary.csv");
+    summary.to_csv(&path)?;
+    let text = std::fs::read_to_string(&path)?;
+    assert!(text.starts_with("Street,Count,Number Distinct"));
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[test]
+fn map_camera() {
+    use galileo_types::cartesian::{CartesianPoint2d, Point2d, Rect};
+    use whimsy::prelude::{lonlat_to_mercator, mercator_to_lonlat, MapCamera};
+    let mut camera = MapCamera::new(Point2d::new(1000.0, 2000.0), 2.0).with_size(400.0, 200.0);
+    // The center of the viewport is the center of the camera, and screen y grows downward.
+    assert_eq!(camera.to_map(200.0, 100.0), Point2d::new(1000.0, 2000.0));
+    assert_eq!(camera.to_map(0.0, 0.0), Point2d::new(600.0, 2200.0));
+    assert_eq!(camera.to_screen(&Point2d::new(600.0, 2200.0)), (0.0, 0.0));
+    // Dragging right moves the map right, so the center moves left.
+    camera.pan(10.0, 0.0);
+    assert_eq!(camera.center, Point2d::new(980.0, 2000.0));
+    // Zooming keeps the point under the pointer in place.
+    let anchor = camera.to_map(50.0, 50.0);
+    camera.zoom(2.0, 50.0, 50.0);
+    assert_eq!(camera.resolution, 1.0);
+    assert_eq!(camera.to_map(50.0, 50.0), anchor);
+    camera.fit(&Rect::new(0.0, 0.0, 800.0, 100.0));
+    assert_eq!(camera.center, Point2d::new(400.0, 50.0));
+    assert!(camera.bounds().x_min() <= 0.0 && camera.bounds().x_max() >= 800.0);
+    let point = lonlat_to_mercator(-123.33, 42.44);
+    let (lon, lat) = mercator_to_lonlat(&point);
+    assert!((lon + 123.33).abs() < 1e-9 && (lat - 42.44).abs() < 1e-9);
+    assert!(point.y() > 0.0);
+}
+
+#[cfg(feature = "gui")]
+#[test]
+fn basemap_tiles() -> Polite<()> {
+    use galileo_types::cartesian::Rect;
+    use geozero::mvt::tile::{Feature, GeomType, Layer};
+    use geozero::mvt::{Message, Tile};
+    use std::io::Write;
+    use whimsy::prelude::{vector_paths, Catalog, TileFormat, TileId, TileSource, Tileset};
+    // The single tile at zoom 0 covers the whole EPSG:3857 world.
+    let world = TileId::new(0, 0, 0).bounds();
+    assert!((world.x_max() - 20_037_508.342789244).abs() < 1e-6);
+    assert_eq!(world.x_min(), -world.x_max());
+    // Rows count down from the north edge.
+    let tile = TileId::new(1, 1, 0).bounds();
+    assert_eq!((tile.x_min(), tile.y_min()), (0.0, 0.0));
+    let tiles = TileId::covering(&Rect::new(-1.0, -1.0, 1.0, 1.0), 1);
+    assert_eq!(tiles.len(), 4);
+    assert_eq!(TileId::zoom_for(156_543.033_928_041, 0, 22), 0);
+    assert_eq!(TileId::zoom_for(1.0, 0, 12), 12);
+
+    let text = "addresses = \"a.data\"\n[basemap]\nkind = \"directory\"\npath = \"tiles\"\nextension = \"png\"";
+    let path = std::env::temp_dir().join("whimsy_catalog.toml");
+    std::fs::write(&path, text)?;
+    let catalog = Catalog::load(&path)?;
+    assert_eq!(catalog.addresses, "a.data");
+    assert_eq!(catalog.parcels, "data/parcels.data");
+    std::fs::remove_file(&path)?;
+
+    let root = std::env::temp_dir().join("whimsy_tiles");
+    std::fs::create_dir_all(root.join("3").join("2"))?;
+    std::fs::write(root.join("3").join("2").join("5.png"), [1u8, 2, 3])?;
+    let source = TileSource::Directory {
+        path: root.to_string_lossy().to_string(),
+        extension: "png".to_string(),
+    };
+    let tileset = Tileset::open(&source)?;
+    assert_eq!(tileset.format, TileFormat::Png);
+    assert_eq!(tileset.max_zoom, 3);
+    assert_eq!(tileset.tile(&TileId::new(3, 2, 5))?, Some(vec![1, 2, 3]));
+    assert_eq!(tileset.tile(&TileId::new(3, 2, 6))?, None);
+    std::fs::remove_dir_all(&root)?;
+
+    // A vector tile holding one line across the tile, gzipped as in most MBTiles files.
+    let line = Feature {
+        r#type: Some(GeomType::Linestring as i32),
+        // Move to (0, 0), then draw a line to (4096, 4096), zigzag encoded.
+        geometry: vec![9, 0, 0, 10, 8192, 8192],
+        ..Default::default()
+    };
+    let layer = Layer {
+        version: 2,
+        name: "roads".to_string(),
+        features: vec![line],
+        extent: Some(4096),
+        ..Default::default()
+    };
+    let pbf = Tile {
+        layers: vec![layer],
+    }
+    .encode_to_vec();
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(&pbf)?;
+    let gzipped = encoder.finish()?;
+
+    // MBTiles rows count up from the south, so XYZ row 0 at zoom 2 is stored as row 3.
+    let path = std::env::temp_dir().join("whimsy_tiles.mbtiles");
+    if path.exists() {
+        std::fs::remove_file(&path)?;
+    }
+    let connection = rusqlite::Connection::open(&path).unwrap();
+    connection
+        .execute_batch(
+            "CREATE TABLE metadata (name TEXT, value TEXT);
+            CREATE TABLE tiles (zoom_level INTEGER, tile_column INTEGER, tile_row INTEGER, tile_data BLOB);
+            INSERT INTO metadata VALUES ('format', 'pbf'), ('minzoom', '1'), ('maxzoom', '4');",
+        )
+        .unwrap();
+    connection
+        .execute(
+            "INSERT INTO tiles VALUES (?1, ?2, ?3, ?4)",
+            (2, 1, 3, gzipped),
+        )
+        .unwrap();
+    drop(connection);
+    let source = TileSource::Mbtiles {
+        path: path.to_string_lossy().to_string(),
+    };
+    let tileset = Tileset::open(&source)?;
+    assert_eq!(tileset.format, TileFormat::Pbf);
+    assert_eq!((tileset.min_zoom, tileset.max_zoom), (1, 4));
+    let data = tileset.tile(&TileId::new(2, 1, 0))?;
+    assert_eq!(data.as_ref(), Some(&pbf));
+    assert_eq!(tileset.tile(&TileId::new(2, 1, 3))?, None);
+    let paths = vector_paths(&data.unwrap_or_default())?;
+    assert_eq!(paths.len(), 1);
+    assert_eq!(paths[0].points, vec![(0.0, 0.0), (1.0, 1.0)]);
+    assert!(!paths[0].closed);
+    drop(tileset);
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[test]
+fn layer_stack() {
+    use galileo_types::cartesian::Point2d;
+    use whimsy::prelude::{Catalog, LayerKind, LayerStack, MapCamera, MapLayer};
+    let mut layers = LayerStack::from_catalog(&Catalog::default());
+    layers.insert(
+        0,
+        MapLayer::new("Matches", LayerKind::Matches, "data/matches.csv"),
+    );
+    let names = |layers: &LayerStack| {
+        layers
+            .iter()
+            .map(|v| v.name.clone())
+            .collect::<Vec<String>>()
+    };
+    assert_eq!(names(&layers), vec!["Matches", "Addresses", "Parcels"]);
+    // Dragging the parcels onto the top of the list draws them last.
+    layers.move_layer(2, 0);
+    assert_eq!(names(&layers), vec!["Parcels", "Matches", "Addresses"]);
+    let shown = |layers: &LayerStack, zoom: f64| {
+        layers
+            .shown(zoom)
+            .iter()
+            .map(|v| v.name.clone())
+            .collect::<Vec<String>>()
+    };
+    assert_eq!(
+        shown(&layers, 10.0),
+        vec!["Addresses", "Matches", "Parcels"]
+    );
+    layers[1].visible = false;
+    layers[2].min_zoom = 15.0;
+    assert_eq!(shown(&layers, 10.0), vec!["Parcels"]);
+    assert_eq!(shown(&layers, 16.0), vec!["Addresses", "Parcels"]);
+    // At zoom level 0 the world fits one 256 point tile.
+    let camera = MapCamera::new(Point2d::new(0.0, 0.0), 156_543.033_928_041);
+    assert!(camera.zoom_level().abs() < 1e-9);
+}
+
+#[test]
+fn map_pick() {
+    use galileo_types::cartesian::{Point2d, Rect};
+    use galileo_types::geo::impls::GeoPoint2d;
+    use galileo_types::geo::NewGeoPoint;
+    use galileo_types::impls::{ClosedContour, MultiPolygon, Polygon};
+    use whimsy::parcels::Owner;
+    use whimsy::prelude::{lonlat_to_mercator, LayerData, Parcel};
+
+    let address = address::prelude::SpatialAddress::default();
+    let mut near = whimsy::prelude::AddressPoint::from(&address);
+    near.geo_point = GeoPoint2d::latlon(45.0, -122.0);
+    let mut far = near.clone();
+    far.id = uuid::Uuid::new_v4();
+    far.geo_point = GeoPoint2d::latlon(45.001, -122.0);
+    let addresses = LayerData::Addresses(vec![far.clone(), near.clone()].into());
+    let click = lonlat_to_mercator(-122.00001, 45.0);
+    let pick = addresses.pick(&click, 10.0).unwrap();
+    assert_eq!(pick.id, Some(near.id));
+    assert!(pick.rings.is_empty());
+    assert!(addresses
+        .pick(&lonlat_to_mercator(-121.9, 45.0), 10.0)
+        .is_none());
+    let ids = std::collections::HashSet::from([far.id]);
+    assert_eq!(addresses.locate(&ids).len(), 1);
+
+    let ring = vec![
+        Point2d::new(0.0, 0.0),
+        Point2d::new(10.0, 0.0),
+        Point2d::new(10.0, 10.0),
+        Point2d::new(0.0, 10.0),
+    ];
+    let parcel = Parcel {
+        owner: Owner {
+            name: Some("Pat Doe".to_string()),
+            id: "1S101".to_string(),
+        },
+        geometry: MultiPolygon {
+            parts: vec![Polygon::from(ClosedContour::new(ring))],
+        },
+        bounds: Rect::new(0.0, 0.0, 10.0, 10.0),
+        selected: false,
+    };
+    let parcels = LayerData::Parcels(vec![parcel].into());
+    let pick = parcels.pick(&Point2d::new(5.0, 5.0), 0.0).unwrap();
+    assert_eq!(pick.id, None);
+    assert_eq!(pick.attributes[0].1, "Pat Doe");
+    assert_eq!(pick.rings[0].len(), 4);
+    assert_eq!(pick.location, Point2d::new(5.0, 5.0));
+    assert!(parcels.pick(&Point2d::new(20.0, 5.0), 0.0).is_none());
+}
+
+#[test]
+fn symbology_rules() -> Polite<()> {
+    use whimsy::prelude::{Attributed, Cell, HexColor, LayerStyle, Rule, Style, Symbology};
+
+    struct Lot(i64);
+    impl Attributed for Lot {
+        fn columns() -> Vec<String> {
+            vec!["Area".to_string()]
+        }
+        fn attribute(&self, column: &str) -> Cell {
+            match column {
+                "Area" => Cell::Integer(self.0),
+                _ => Cell::Empty,
+            }
+        }
+    }
+
+    // The sample config matches the built-in colors.
+    assert_eq!(
+        Symbology::load("data/symbology.toml")?,
+        Symbology::default()
+    );
+    let color = "#dbc20080".parse::<HexColor>()?;
+    assert_eq!(color, HexColor([0xdb, 0xc2, 0x00, 0x80]));
+    assert_eq!(color.to_string(), "#dbc20080");
+    assert!("#dbc2".parse::<HexColor>().is_err());
+
+    let values = [10, 20, 30, 40, 50].map(Cell::Integer);
+    let base = Style::fill("#ffffff");
+    let style = LayerStyle {
+        default: base.clone(),
+        rule: Some(Rule::graduate(
+            "Area",
+            values.clone(),
+            2,
+            &base,
+            HexColor::rgb(0, 0, 0),
+        )),
+        ..Default::default()
+    };
+    assert_eq!(style.style_for(&Lot(15)).fill, HexColor::rgb(255, 255, 255));
+    assert_eq!(style.style_for(&Lot(50)).fill, HexColor::rgb(0, 0, 0));
+    assert_eq!(style.style_for(&Lot(99)), &base);
+    assert_eq!(style.legend().len(), 3);
+    assert_eq!(style.legend()[2].0, "All other values");
+
+    let rule = Rule::categorize("Area", values, &base);
+    let style = LayerStyle {
+        default: base.clone(),
+        rule: Some(rule),
+        ..Default::default()
+    };
+    assert_ne!(
+        style.style_for(&Lot(20)).fill,
+        style.style_for(&Lot(30)).fill
+    );
+    let text = toml::to_string(&style).unwrap();
+    assert_eq!(toml::from_str::<LayerStyle>(&text).unwrap(), style);
+    Ok(())
+}
+
+#[test]
+fn label_placement() {
+    use galileo_types::cartesian::{CartesianPoint2d, Point2d};
+    use whimsy::prelude::{declutter, interior_point, LabelBox};
+
+    // The centroid of a U-shaped lot falls in the notch, outside the lot.
+    let ring = vec![
+        Point2d::new(0.0, 0.0),
+        Point2d::new(30.0, 0.0),
+        Point2d::new(30.0, 30.0),
+        Point2d::new(20.0, 30.0),
+        Point2d::new(20.0, 10.0),
+        Point2d::new(10.0, 10.0),
+        Point2d::new(10.0, 30.0),
+        Point2d::new(0.0, 30.0),
+    ];
+    let point = interior_point(&ring).unwrap();
+    let inside = point.y() < 10.0 || point.x() < 10.0 || point.x() > 20.0;
+    assert!(inside, "{:?} is in the notch", point);
+    assert!(interior_point(&ring[..2]).is_none());
+
+    let boxes = [
+        LabelBox::new(0.0, 0.0, 40.0, 12.0),
+        LabelBox::new(30.0, 6.0, 40.0, 12.0),
+        LabelBox::new(100.0, 0.0, 40.0, 12.0),
+        LabelBox::new(0.0, 12.0, 40.0, 12.0),
+    ];
+    assert_eq!(declutter(&boxes), vec![0, 2, 3]);
+    let boxes = boxes.map(|v| v.expand(1.0));
+    assert_eq!(declutter(&boxes), vec![0, 2]);
+}
+
+#[test]
+fn measurement() {
+    use galileo_types::cartesian::{CartesianPoint2d, Point2d, Rect};
+    use galileo_types::impls::{ClosedContour, MultiPolygon, Polygon};
+    use whimsy::parcels::Owner;
+    use whimsy::prelude::{
+        format_area, format_length, lonlat_to_mercator, mercator_area, LayerData, MeasureKind,
+        Measurement, Parcel,
+    };
+
+    // A square 100 meters on a side on the ground, stretched by the scale factor at 42.4°N.
+    let origin = lonlat_to_mercator(-122.7, 42.4);
+    let side = 100.0 / 42.4_f64.to_radians().cos();
+    let ring = vec![
+        origin,
+        Point2d::new(origin.x() + side, origin.y()),
+        Point2d::new(origin.x() + side, origin.y() + side),
+        Point2d::new(origin.x(), origin.y() + side),
+    ];
+    assert!((mercator_area(&ring) - 10_000.0).abs() < 10.0);
+
+    let mut distance = Measurement::new(MeasureKind::Distance);
+    ring.iter().for_each(|v| distance.push(*v));
+    assert!((distance.length() - 300.0).abs() < 0.5);
+    assert_eq!(distance.area(), 0.0);
+    let mut area = Measurement::new(MeasureKind::Area);
+    ring.iter().for_each(|v| area.push(*v));
+    assert!((area.length() - 400.0).abs() < 0.5);
+    assert!((area.area() - 10_000.0).abs() < 10.0);
+    assert!(area.summary().contains("perimeter"));
+
+    assert_eq!(format_length(1609.344), "5280.0 ft (1.000 mi)");
+    assert_eq!(format_area(4046.8564224), "43560 sq ft (1.000 ac)");
+
+    let square = vec![
+        Point2d::new(0.0, 0.0),
+        Point2d::new(10.0, 0.0),
+        Point2d::new(10.0, 10.0),
+        Point2d::new(0.0, 10.0),
+    ];
+    let parcel = Parcel {
+        owner: Owner {
+            name: None,
+            id: "1S101".to_string(),
+        },
+        geometry: MultiPolygon {
+            parts: vec![Polygon::from(ClosedContour::new(square))],
+        },
+        bounds: Rect::new(0.0, 0.0, 10.0, 10.0),
+        selected: false,
+    };
+    let parcels = LayerData::Parcels(vec![parcel].into());
+    assert_eq!(
+        parcels.snap(&Point2d::new(9.0, 11.0), 2.0),
+        Some(Point2d::new(10.0, 10.0))
+    );
+    assert!(parcels.snap(&Point2d::new(5.0, 5.0), 2.0).is_none());
+    assert!(LayerData::Addresses(Default::default())
+        .snap(&Point2d::new(0.0, 0.0), 2.0)
+        .is_none());
+}
+
+#[test]
+fn spatial_selection() {
+    use galileo_types::cartesian::{CartesianPoint2d, Point2d, Rect};
+    use galileo_types::geo::impls::GeoPoint2d;
+    use galileo_types::geo::NewGeoPoint;
+    use galileo_types::impls::{ClosedContour, MultiPolygon, Polygon};
+    use std::collections::HashSet;
+    use whimsy::parcels::Owner;
+    use whimsy::prelude::{lonlat_to_mercator, point_in_ring, LayerData, Parcel, SelectMode};
+
+    // An L-shaped lasso, leaving out the upper right corner of its bounds.
+    let lasso = vec![
+        Point2d::new(0.0, 0.0),
+        Point2d::new(10.0, 0.0),
+        Point2d::new(10.0, 5.0),
+        Point2d::new(5.0, 5.0),
+        Point2d::new(5.0, 10.0),
+        Point2d::new(0.0, 10.0),
+    ];
+    assert!(point_in_ring(&Point2d::new(2.0, 8.0), &lasso));
+    assert!(point_in_ring(&Point2d::new(8.0, 2.0), &lasso));
+    assert!(!point_in_ring(&Point2d::new(8.0, 8.0), &lasso));
+    assert!(!point_in_ring(&Point2d::new(-1.0, 2.0), &lasso));
+
+    let address = address::prelude::SpatialAddress::default();
+    let mut inside = whimsy::prelude::AddressPoint::from(&address);
+    inside.geo_point = GeoPoint2d::latlon(45.0, -122.0);
+    let mut outside = inside.clone();
+    outside.id = uuid::Uuid::new_v4();
+    outside.geo_point = GeoPoint2d::latlon(45.01, -122.0);
+    let addresses = LayerData::Addresses(vec![inside.clone(), outside.clone()].into());
+    let center = lonlat_to_mercator(-122.0, 45.0);
+    let square = |x: f64, y: f64, side: f64| {
+        vec![
+            Point2d::new(x, y),
+            Point2d::new(x + side, y),
+            Point2d::new(x + side, y + side),
+            Point2d::new(x, y + side),
+        ]
+    };
+    let shape = square(center.x() - 50.0, center.y() - 50.0, 100.0);
+    assert_eq!(addresses.within(&[shape.clone()]), vec![inside.id]);
+    assert!(addresses.within(&[]).is_empty());
+
+    let parcel = |x: f64| Parcel {
+        owner: Owner {
+            name: None,
+            id: format!("1S1{x}"),
+        },
+        geometry: MultiPolygon {
+            parts: vec![Polygon::from(ClosedContour::new(square(x, 0.0, 10.0)))],
+        },
+        bounds: Rect::new(x, 0.0, x + 10.0, 10.0),
+        selected: false,
+    };
+    let parcels = LayerData::Parcels(vec![parcel(0.0), parcel(20.0)].into());
+    // Takes in most of the first parcel and none of the second.
+    let selected = parcels.parcels_within(&square(-2.0, -2.0, 11.0));
+    assert_eq!(selected.len(), 1);
+    assert_eq!(selected[0][0].len(), 4);
+    assert!(parcels.parcels_within(&square(12.0, 0.0, 5.0)).is_empty());
+
+    let a = uuid::Uuid::new_v4();
+    let b = uuid::Uuid::new_v4();
+    let mut selection = HashSet::from([a]);
+    SelectMode::Add.apply(&mut selection, &HashSet::from([b]));
+    assert_eq!(selection, HashSet::from([a, b]));
+    SelectMode::Remove.apply(&mut selection, &HashSet::from([a]));
+    assert_eq!(selection, HashSet::from([b]));
+    SelectMode::Replace.apply(&mut selection, &HashSet::from([a]));
+    assert_eq!(selection, HashSet::from([a]));
+}
+
+#[test]
+fn print_layout() -> Polite<()> {
+    use galileo_types::cartesian::{CartesianPoint2d, Point2d, Rect};
+    use galileo_types::geo::impls::GeoPoint2d;
+    use galileo_types::geo::NewGeoPoint;
+    use galileo_types::impls::{ClosedContour, MultiPolygon, Polygon};
+    use whimsy::parcels::Owner;
+    use whimsy::prelude::{
+        lonlat_to_mercator, mercator_distance, nice_scale, text_width, wrap, LayerData, Layout,
+        MapLayer, Mark, Parcel, PrintLayer, Symbology,
+    };
+
+    // Engineering scales of 200 and 400 feet to the inch are kept.
+    assert_eq!(nice_scale(2400.0), 2400.0);
+    assert_eq!(nice_scale(2450.0), 2500.0);
+    assert_eq!(nice_scale(4700.0), 4800.0);
+    assert_eq!(nice_scale(5500.0), 6000.0);
+    assert_eq!(nice_scale(1200.0), 1200.0);
+    assert_eq!(nice_scale(3500.0), 3600.0);
+    assert_eq!(nice_scale(7000.0), 7200.0);
+    assert_eq!(nice_scale(7300.0), 8000.0);
+    assert!((text_width("Main", 10.0, false) - 21.67).abs() < 0.01);
+    let lines = wrap(whimsy::prelude::CREDIT, 6.5, 300.0);
+    assert!(lines.len() > 1);
+    assert!(lines.iter().all(|v| text_width(v, 6.5, false) <= 300.0));
+
+    let center = lonlat_to_mercator(-123.33, 42.44);
+    let mut layout = Layout {
+        title: "Review map & notice".to_string(),
+        date: chrono::NaiveDate::from_ymd_opt(2024, 7, 1),
+        ..Default::default()
+    };
+    layout.view(&center, 1.0);
+    assert_eq!(layout.center, [center.x(), center.y()]);
+    // A point on the page spans `scale` points on the ground.
+    let [_, _, width, _] = layout.frame();
+    let extent = layout.extent();
+    let ground = mercator_distance(
+        &Point2d::new(extent.x_min(), center.y()),
+        &Point2d::new(extent.x_max(), center.y()),
+    );
+    let expected = width * layout.scale * 0.0254 / 72.0;
+    assert!((ground - expected).abs() / expected < 1e-6);
+    let mut fitted = layout.clone();
+    fitted.fit(&extent);
+    assert!(fitted.scale >= layout.scale);
+
+    let address = address::prelude::SpatialAddress::default();
+    let mut point = whimsy::prelude::AddressPoint::from(&address);
+    point.geo_point = GeoPoint2d::latlon(42.44, -123.33);
+    let addresses = LayerData::Addresses(vec![point].into());
+    let ring = vec![
+        Point2d::new(center.x() - 20.0, center.y() - 20.0),
+        Point2d::new(center.x() + 20.0, center.y() - 20.0),
+        Point2d::new(center.x() + 20.0, center.y() + 20.0),
+        Point2d::new(center.x() - 20.0, center.y() + 20.0),
+    ];
+    let parcels = LayerData::Parcels(
+        vec![Parcel {
+            owner: Owner {
+                name: None,
+                id: "36-05-05".to_string(),
+            },
+            geometry: MultiPolygon {
+                parts: vec![Polygon::from(ClosedContour::new(ring))],
+            },
+            bounds: Rect::new(
+                center.x() - 20.0,
+                center.y() - 20.0,
+                center.x() + 20.0,
+                center.y() + 20.0,
+            ),
+            selected: false,
+        }]
+        .into(),
+    );
+    let symbology = Symbology::default();
+    let address_layer = MapLayer::new("Addresses", whimsy::prelude::LayerKind::Addresses, "");
+    let parcel_layer = MapLayer::new("Parcels", whimsy::prelude::LayerKind::Parcels, "");
+    let layers = vec![
+        PrintLayer::new(&address_layer, &addresses, &symbology.addresses),
+        PrintLayer::new(&parcel_layer, &parcels, &symbology.parcels),
+    ];
+    let page = layout.compose(&layers);
+    assert_eq!((page.width, page.height), (792.0, 612.0));
+    let clipped = page
+        .marks
+        .iter()
+        .find_map(|v| match v {
+            Mark::Clip { marks, .. } => Some(marks),
+            _ => None,
+        })
+        .unwrap();
+    // The frame background, then the parcel beneath the address point.
+    assert_eq!(clipped.len(), 3);
+    assert!(matches!(clipped[2], Mark::Circle { .. }));
+
+    let svg = page.to_svg();
+    assert!(svg.starts_with("<?xml"));
+    assert!(svg.contains("Review map &amp; notice"));
+    assert!(svg.contains("Printed July 1, 2024"));
+    assert!(svg.contains("Scale 1:"));
+    assert!(svg.contains("Retired"));
+    assert!(svg.trim_end().ends_with("</svg>"));
+
+    let pdf = page.to_pdf()?;
+    assert!(pdf.starts_with(b"%PDF-1.4"));
+    let text = String::from_utf8_lossy(&pdf);
+    assert!(text.trim_end().ends_with("%%EOF"));
+    assert!(text.contains("/BaseFont /Helvetica-Bold"));
+    // The first entry of the cross reference table points at the first object.
+    let xref = text.find("xref\n").unwrap();
+    let first = text[xref..].lines().nth(3).unwrap();
+    let offset = first[..10].parse::<usize>().unwrap();
+    assert!(pdf[offset..].starts_with(b"1 0 obj"));
+    Ok(())
+}
+
+#[test]
+fn address_editing() -> Polite<()> {
+    init_tracing();
+    use whimsy::prelude::{
+        insert_rows, remove_rows, AddressColumns, AddressPoint, AddressPoints, EditError,
+        EditHistory, Tabular,
+    };
+    let location = AddressColumns::Location as usize;
+    let mut values = vec![String::new(); AddressColumns::names().len()];
+    values[AddressColumns::Number as usize] = "100".to_string();
+    values[AddressColumns::StreetName as usize] = "Main".to_string();
+    values[AddressColumns::StreetType as usize] = "St".to_string();
+    values[AddressColumns::Zip as usize] = "97526".to_string();
+    let first = AddressPoint::pending(&values, 42.439123456789, -123.328987654321).unwrap();
+    assert_eq!(
+        first.column::<String>(&AddressColumns::Location),
+        "42.439123456789, -123.328987654321"
+    );
+    assert_eq!(first.column::<String>(&AddressColumns::Status), "Pending");
+    // A street name is required.
+    values[AddressColumns::StreetName as usize].clear();
+    assert!(AddressPoint::pending(&values, 42.44, -123.33).is_err());
+    values[AddressColumns::StreetName as usize] = "Oak".to_string();
+    let second = AddressPoint::pending(&values, 42.44, -123.33).unwrap();
+
+    let mut points = AddressPoints::default();
+    let mut history = EditHistory::default();
+    history.record(insert_rows(&mut points, &[first.clone(), second.clone()]).unwrap());
+    assert_eq!(points.len(), 2);
+    // Ids stay unique, and a rejected step keeps none of its rows.
+    let mut third = second.clone();
+    third.id = uuid::Uuid::new_v4();
+    assert!(matches!(
+        insert_rows(&mut points, &[third, first.clone()]),
+        Err(EditError::Invalid(_))
+    ));
+    assert_eq!(points.len(), 2);
+
+    // Moving a point updates both coordinates and keeps its id.
+    history.record(
+        whimsy::prelude::apply_edits(
+            &mut points,
+            &[(
+                first.id,
+                location,
+                "42.44123456789, -123.33987654321".to_string(),
+            )],
+        )
+        .unwrap(),
+    );
+    let moved = &points.records[0];
+    assert_eq!(moved.id, first.id);
+    assert_eq!(
+        moved.column::<String>(&AddressColumns::Location),
+        "42.44123456789, -123.33987654321"
+    );
+    let (lon, lat) = whimsy::prelude::mercator_to_lonlat(&moved.point);
+    assert!((lon + 123.33987654321).abs() < 1e-9 && (lat - 42.44123456789).abs() < 1e-9);
+    assert!(points.set_value(&first.id, location, "91, 0").is_err());
+    assert!(points.set_value(&first.id, location, "north").is_err());
+
+    // Deleting restores the row in place on undo.
+    history.record(remove_rows(&mut points, &[first.id]).unwrap());
+    assert_eq!(points.records, vec![second.clone()]);
+    history.undo(&mut points).unwrap();
+    assert_eq!(points.records[0].id, first.id);
+    // Undoing the move restores the exact location.
+    history.undo(&mut points).unwrap();
+    assert_eq!(points.records[0], first);
+    assert_eq!(points.records[0].address.latitude, 42.439123456789);
+    history.undo(&mut points).unwrap();
+    assert!(points.is_empty());
+    history.redo(&mut points).unwrap();
+    assert_eq!(points.records, vec![first, second]);
+    Ok(())
+}